@@ -0,0 +1,114 @@
+use std::net::IpAddr;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
+
+// The format vanilla writes dates in, like `2021-05-01 18:00:00 +0000`
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+const FOREVER: &str = "forever";
+pub const DEFAULT_REASON: &str = "Banned by an operator.";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BanInfo {
+    #[serde(with = "date")]
+    pub created: DateTime<FixedOffset>,
+    pub source: String,
+    #[serde(with = "expiry")]
+    pub expires: Option<DateTime<FixedOffset>>,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerBan {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(flatten)]
+    pub info: BanInfo,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IpBan {
+    pub ip: IpAddr,
+    #[serde(flatten)]
+    pub info: BanInfo,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+impl BanInfo {
+    pub fn new(source: &str, reason: Option<&str>,
+        expires: Option<DateTime<FixedOffset>>) -> Self
+    {
+        Self {
+            created: now(),
+            source: source.to_owned(),
+            expires,
+            reason: reason.unwrap_or(DEFAULT_REASON).to_owned(),
+        }
+    }
+
+    pub fn has_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires < now())
+    }
+}
+
+pub fn now() -> DateTime<FixedOffset> {
+    DateTime::<Utc>::from(std::time::SystemTime::now()).fixed_offset()
+}
+
+// When a ban made now for `duration` ends, None if that's too far away
+pub fn expiry_after(duration: std::time::Duration) -> Option<DateTime<FixedOffset>> {
+    chrono::Duration::from_std(duration).ok()
+        .and_then(|duration| now().checked_add_signed(duration))
+}
+
+pub fn format_date(date: &DateTime<FixedOffset>) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+mod date {
+    use super::*;
+
+    pub fn serialize<S>(date: &DateTime<FixedOffset>, serializer: S)
+        -> Result<S::Ok, S::Error> where S: Serializer
+    {
+        serializer.serialize_str(&format_date(date))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error>
+        where D: Deserializer<'de>
+    {
+        let text = String::deserialize(deserializer)?;
+        DateTime::parse_from_str(&text, DATE_FORMAT).map_err(serde::de::Error::custom)
+    }
+}
+
+// Bans without an expiry date have `forever` instead
+mod expiry {
+    use super::*;
+
+    pub fn serialize<S>(date: &Option<DateTime<FixedOffset>>, serializer: S)
+        -> Result<S::Ok, S::Error> where S: Serializer
+    {
+        match date {
+            Some(date) => serializer.serialize_str(&format_date(date)),
+            None => serializer.serialize_str(FOREVER),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D)
+        -> Result<Option<DateTime<FixedOffset>>, D::Error> where D: Deserializer<'de>
+    {
+        let text = String::deserialize(deserializer)?;
+        if text == FOREVER {
+            return Ok(None);
+        }
+        DateTime::parse_from_str(&text, DATE_FORMAT)
+            .map(Some)
+            .map_err(serde::de::Error::custom)
+    }
+}
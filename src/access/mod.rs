@@ -0,0 +1,276 @@
+mod entries;
+
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+use crate::config;
+use crate::net::offline_uuid;
+use crate::text::TextComponent;
+use crate::util::spawn_write;
+
+pub use entries::{BanInfo, IpBan, PlayerBan, WhitelistEntry, expiry_after, format_date};
+
+const BANNED_PLAYERS_PATH: &str = "banned-players.json";
+const BANNED_IPS_PATH: &str = "banned-ips.json";
+const WHITELIST_PATH: &str = "whitelist.json";
+
+// Bans and the whitelist, in the same files vanilla uses. Every change is
+// saved in the background right away
+pub struct AccessLists {
+    dir: PathBuf,
+    banned_players: Vec<PlayerBan>,
+    banned_ips: Vec<IpBan>,
+    whitelist: Vec<WhitelistEntry>,
+    whitelist_enabled: bool,
+}
+
+impl AccessLists {
+    pub fn load(dir: &Path, whitelist_enabled: bool) -> Self {
+        let mut lists = Self {
+            dir: dir.to_owned(),
+            banned_players: vec![],
+            banned_ips: vec![],
+            whitelist: vec![],
+            whitelist_enabled,
+        };
+        lists.reload();
+        lists
+    }
+
+    pub fn reload(&mut self) {
+        self.banned_players = load_list(&self.dir.join(BANNED_PLAYERS_PATH));
+        self.banned_ips = load_list(&self.dir.join(BANNED_IPS_PATH));
+        self.whitelist = load_list(&self.dir.join(WHITELIST_PATH));
+    }
+
+    // Returns why the player can't join, if they can't. Same order and
+    // messages as vanilla. Expired bans are skipped, they're only removed
+    // from the files when the lists change or are listed
+    pub fn check(&self, uuid: Uuid, name: &str, address: IpAddr) -> Option<TextComponent> {
+        let player_ban = self.banned_players.iter()
+            .find(|ban| matches(ban, uuid, name) && !ban.info.has_expired());
+        if let Some(ban) = player_ban {
+            return Some(ban_message("multiplayer.disconnect.banned.reason", &ban.info));
+        }
+        if self.whitelist_enabled && !self.is_whitelisted(uuid, name) {
+            return Some(TextComponent::translate("multiplayer.disconnect.not_whitelisted", vec![]));
+        }
+        let ip_ban = self.banned_ips.iter()
+            .find(|ban| ban.ip == address && !ban.info.has_expired());
+        if let Some(ban) = ip_ban {
+            return Some(ban_message("multiplayer.disconnect.banned_ip.reason", &ban.info));
+        }
+        None
+    }
+
+    pub fn remove_expired(&mut self) {
+        let players = self.banned_players.len();
+        self.banned_players.retain(|ban| !ban.info.has_expired());
+        if players != self.banned_players.len() {
+            save_list(&self.dir.join(BANNED_PLAYERS_PATH), &self.banned_players);
+        }
+        let ips = self.banned_ips.len();
+        self.banned_ips.retain(|ban| !ban.info.has_expired());
+        if ips != self.banned_ips.len() {
+            save_list(&self.dir.join(BANNED_IPS_PATH), &self.banned_ips);
+        }
+    }
+
+    pub fn banned_players(&self) -> &[PlayerBan] {
+        &self.banned_players
+    }
+
+    pub fn banned_ips(&self) -> &[IpBan] {
+        &self.banned_ips
+    }
+
+    // Returns false if the player was already banned
+    pub fn ban_player(&mut self, uuid: Uuid, name: &str, info: BanInfo) -> bool {
+        self.remove_expired();
+        if self.banned_players.iter().any(|ban| matches(ban, uuid, name)) {
+            return false;
+        }
+        self.banned_players.push(PlayerBan { uuid, name: name.to_owned(), info });
+        save_list(&self.dir.join(BANNED_PLAYERS_PATH), &self.banned_players);
+        true
+    }
+
+    pub fn pardon_player(&mut self, name: &str) -> bool {
+        let count = self.banned_players.len();
+        self.banned_players.retain(|ban| !ban.name.eq_ignore_ascii_case(name));
+        if count == self.banned_players.len() {
+            return false;
+        }
+        save_list(&self.dir.join(BANNED_PLAYERS_PATH), &self.banned_players);
+        true
+    }
+
+    pub fn ban_ip(&mut self, ip: IpAddr, info: BanInfo) -> bool {
+        self.remove_expired();
+        if self.banned_ips.iter().any(|ban| ban.ip == ip) {
+            return false;
+        }
+        self.banned_ips.push(IpBan { ip, info });
+        save_list(&self.dir.join(BANNED_IPS_PATH), &self.banned_ips);
+        true
+    }
+
+    pub fn pardon_ip(&mut self, ip: IpAddr) -> bool {
+        let count = self.banned_ips.len();
+        self.banned_ips.retain(|ban| ban.ip != ip);
+        if count == self.banned_ips.len() {
+            return false;
+        }
+        save_list(&self.dir.join(BANNED_IPS_PATH), &self.banned_ips);
+        true
+    }
+
+    pub fn is_whitelist_enabled(&self) -> bool {
+        self.whitelist_enabled
+    }
+
+    // Saved to the config, like vanilla saves it to server.properties
+    pub fn set_whitelist_enabled(&mut self, enabled: bool) {
+        self.whitelist_enabled = enabled;
+        config::save_whitelist(&self.dir, enabled);
+    }
+
+    pub fn is_whitelisted(&self, uuid: Uuid, name: &str) -> bool {
+        self.whitelist.iter()
+            .any(|entry| is_same_player(entry.uuid, &entry.name, uuid, name))
+    }
+
+    pub fn whitelist(&self) -> &[WhitelistEntry] {
+        &self.whitelist
+    }
+
+    pub fn whitelist_add(&mut self, uuid: Uuid, name: &str) -> bool {
+        if self.is_whitelisted(uuid, name) {
+            return false;
+        }
+        self.whitelist.push(WhitelistEntry { uuid, name: name.to_owned() });
+        save_list(&self.dir.join(WHITELIST_PATH), &self.whitelist);
+        true
+    }
+
+    pub fn whitelist_remove(&mut self, name: &str) -> bool {
+        let count = self.whitelist.len();
+        self.whitelist.retain(|entry| !entry.name.eq_ignore_ascii_case(name));
+        if count == self.whitelist.len() {
+            return false;
+        }
+        save_list(&self.dir.join(WHITELIST_PATH), &self.whitelist);
+        true
+    }
+}
+
+// Players banned before they ever joined only have a guessed UUID
+fn matches(ban: &PlayerBan, uuid: Uuid, name: &str) -> bool {
+    is_same_player(ban.uuid, &ban.name, uuid, name)
+}
+
+// Whether an entry for `entry_name` with `entry_uuid` is about this player.
+// Names change hands, so they only count for entries made without the
+// real UUID, like the ones for players that never joined an offline server
+pub fn is_same_player(entry_uuid: Uuid, entry_name: &str, uuid: Uuid, name: &str) -> bool {
+    entry_uuid == uuid
+        || (entry_uuid == offline_uuid(entry_name) && entry_name.eq_ignore_ascii_case(name))
+}
+
+pub fn ban_message(key: &str, info: &BanInfo) -> TextComponent {
+    let message = TextComponent::translate(key, vec![info.reason.as_str().into()]);
+    match &info.expires {
+        Some(expires) => message.append(TextComponent::translate(
+            "multiplayer.disconnect.banned.expiration", vec![format_date(expires).into()])),
+        None => message,
+    }
+}
+
+pub fn load_list<T: DeserializeOwned>(path: &Path) -> Vec<T> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return vec![],
+    };
+    serde_json::from_str(&content).unwrap_or_else(|err| {
+        log!("Couldn't read {}: {}", path.display(), err);
+        vec![]
+    })
+}
+
+pub fn save_list<T: Serialize>(path: &Path, list: &[T]) {
+    match serde_json::to_string_pretty(list) {
+        Ok(json) => spawn_write(path, json.into_bytes()),
+        Err(err) => log!("Couldn't save {}: {}", path.display(), err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use chrono::DateTime;
+    use uuid::Uuid;
+    use crate::net::offline_uuid;
+    use super::{AccessLists, BanInfo, IpBan, PlayerBan, WhitelistEntry, is_same_player};
+
+    #[test]
+    fn format_test() {
+        let json = r#"[{"uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5", "name": "Notch",
+            "created": "2021-05-01 18:00:00 +0000", "source": "Server",
+            "expires": "forever", "reason": "Banned by an operator."}]"#;
+        let bans: Vec<PlayerBan> = serde_json::from_str(json).unwrap();
+        assert_eq!(bans[0].uuid,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap());
+        assert!(bans[0].info.expires.is_none());
+        let saved = serde_json::to_string(&bans).unwrap();
+        assert!(saved.contains(r#""created":"2021-05-01 18:00:00 +0000""#));
+        assert!(saved.contains(r#""expires":"forever""#));
+
+        let ban = IpBan {
+            ip: "127.0.0.1".parse().unwrap(),
+            info: BanInfo::new("Server", None, DateTime::parse_from_str(
+                "2000-01-01 00:00:00 +0000", "%Y-%m-%d %H:%M:%S %z").ok()),
+        };
+        assert!(ban.info.has_expired());
+        let saved = serde_json::to_string(&ban).unwrap();
+        assert!(saved.contains(r#""ip":"127.0.0.1""#));
+        assert!(saved.contains(r#""expires":"2000-01-01 00:00:00 +0000""#));
+    }
+
+    #[test]
+    fn same_player_test() {
+        let notch = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let other = Uuid::new_v4();
+        assert!(is_same_player(notch, "Notch", notch, "Renamed"));
+        // Someone else took the name
+        assert!(!is_same_player(notch, "Notch", other, "Notch"));
+        // Added before the player ever joined, only the name is known
+        assert!(is_same_player(offline_uuid("Notch"), "Notch", notch, "notch"));
+        assert!(!is_same_player(offline_uuid("Notch"), "Notch", notch, "Jeb"));
+    }
+
+    #[test]
+    fn renamed_player_test() {
+        let notch = Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let jeb = Uuid::parse_str("853c80ef-3c37-49fd-aa49-938b674adae6").unwrap();
+        let address = "127.0.0.1".parse().unwrap();
+        let mut lists = AccessLists {
+            dir: PathBuf::new(),
+            banned_players: vec![PlayerBan {
+                uuid: notch,
+                name: "Notch".into(),
+                info: BanInfo::new("Server", None, None),
+            }],
+            banned_ips: vec![],
+            whitelist: vec![WhitelistEntry { uuid: jeb, name: "jeb_".into() }],
+            whitelist_enabled: true,
+        };
+        // The banned player renamed, and someone else took the name
+        assert!(lists.check(notch, "Renamed", address).is_some());
+        lists.banned_players.clear();
+        assert!(lists.check(notch, "jeb_", address).is_some());
+        assert!(lists.check(jeb, "Renamed", address).is_none());
+    }
+}
@@ -0,0 +1,299 @@
+// Load test that connects a swarm of offline mode bots. They walk around
+// at random and place and break blocks, and every few seconds it prints what
+// they got from the server and how long its ticks took.
+//
+//     cargo run --release --bin swarm -- [address] [bots]
+//
+// The server needs `online_mode: false` and `connection_throttle: -1`,
+// otherwise only one bot gets in every few seconds
+
+use std::convert::TryInto;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use glow::blocks::BlockFace;
+use glow::items::{ItemStack, ItemType};
+use glow::net::{Client, ClientReader, ClientboundPacket, ServerboundPacket};
+use glow::players::TICK_TIMES_CHANNEL;
+use nalgebra::{Vector3, vector};
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tokio::time::{interval, sleep};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:25565";
+const DEFAULT_BOTS: usize = 10;
+// Bots act once per tick
+const ACTION_INTERVAL: Duration = Duration::from_millis(50);
+const JOIN_INTERVAL: Duration = Duration::from_millis(100);
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+const WANDER_RADIUS: f64 = 32.0;
+const STEP_LENGTH: f64 = 0.2;
+// Chance of placing or breaking a block each tick
+const BUILD_CHANCE: f64 = 0.05;
+// First slot of the hotbar, in the window numbering Creative Inventory Action uses
+const HOTBAR_START: i16 = 36;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let address: SocketAddr = args.next().as_deref().unwrap_or(DEFAULT_ADDRESS).parse()
+        .map_err(|_| anyhow!("Usage: swarm [address] [bots]"))?;
+    let bots = match args.next() {
+        Some(bots) => bots.parse().map_err(|_| anyhow!("Usage: swarm [address] [bots]"))?,
+        None => DEFAULT_BOTS,
+    };
+    let stats = Arc::new(Stats::default());
+    tokio::spawn(report(stats.clone()));
+    for number in 0..bots {
+        let name = format!("Bot{}", number);
+        let stats = stats.clone();
+        // The server sends its tick times to every bot that asks, one is enough
+        let tick_times = number == 0;
+        tokio::spawn(async move {
+            if let Err(err) = run_bot(address, &name, tick_times, stats).await {
+                eprintln!("{} disconnected: {}", name, err);
+            }
+        });
+        sleep(JOIN_INTERVAL).await;
+    }
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Stats {
+    online: AtomicUsize,
+    chunks: AtomicU64,
+    chunk_bytes: AtomicU64,
+    movements: AtomicU64,
+    // From sending a block change to seeing it come back, which is mostly
+    // waiting for the next tick
+    latencies: Mutex<Vec<Duration>>,
+    // Average and longest tick of each second, measured by the server
+    tick_times: Mutex<Vec<(Duration, Duration)>>,
+}
+
+async fn report(stats: Arc<Stats>) {
+    let mut interval = interval(REPORT_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let seconds = REPORT_INTERVAL.as_secs_f64();
+        let chunks = stats.chunks.swap(0, Ordering::Relaxed) as f64 / seconds;
+        let chunk_bytes = stats.chunk_bytes.swap(0, Ordering::Relaxed) as f64 / seconds;
+        let movements = stats.movements.swap(0, Ordering::Relaxed) as f64 / seconds;
+        let mut latencies = std::mem::take(&mut *stats.latencies.lock().unwrap());
+        latencies.sort_unstable();
+        let latency = if latencies.is_empty() {
+            "no block changes".to_owned()
+        } else {
+            let average = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+            let p99 = latencies[(latencies.len() - 1) * 99 / 100];
+            format!("block change latency avg {:?}, p99 {:?}, max {:?}",
+                average, p99, latencies[latencies.len() - 1])
+        };
+        let tick_times = std::mem::take(&mut *stats.tick_times.lock().unwrap());
+        let tick_time = if tick_times.is_empty() {
+            "no tick times".to_owned()
+        } else {
+            let average = tick_times.iter().map(|(average, _)| *average).sum::<Duration>()
+                / tick_times.len() as u32;
+            let max = tick_times.iter().map(|(_, max)| *max).max().unwrap();
+            format!("tick avg {:?}, max {:?}", average, max)
+        };
+        println!("{} bots | {} | {:.1} chunks/s ({:.1} KiB/s) | {:.1} movement packets/s | {}",
+            stats.online.load(Ordering::Relaxed), tick_time, chunks, chunk_bytes / 1024.0,
+            movements, latency);
+    }
+}
+
+// What the bot needs to know from the packets it gets, the rest is only counted
+enum Event {
+    KeepAlive(u64),
+    Teleport(Vector3<f64>, u32),
+    BlockChange(Vector3<i32>),
+    Disconnect(String),
+}
+
+async fn run_bot(address: SocketAddr, name: &str, tick_times: bool, stats: Arc<Stats>)
+    -> Result<()>
+{
+    let mut client = Client::connect(address).await?;
+    client.login(name).await?;
+    let (reader, mut writer) = client.into_split();
+    if tick_times {
+        writer.send(&ServerboundPacket::PluginMessage {
+            channel: "minecraft:register".into(),
+            data: TICK_TIMES_CHANNEL.as_bytes().to_vec(),
+        }).await?;
+    }
+    stats.online.fetch_add(1, Ordering::Relaxed);
+    let (event_send, mut events) = unbounded_channel();
+    let reading = tokio::spawn(read_packets(reader, event_send, stats.clone()));
+    let stone = ItemType::from_name("minecraft:stone")?;
+    writer.send(&ServerboundPacket::CreativeInventoryAction {
+        slot: HOTBAR_START,
+        stack: Some(ItemStack { item: stone, count: stone.max_stack_size(), nbt: None }),
+    }).await?;
+    writer.send(&ServerboundPacket::HeldItemChange { slot: 0 }).await?;
+
+    let mut bot = Bot::default();
+    let mut actions = interval(ACTION_INTERVAL);
+    let result = loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(Event::KeepAlive(id)) => {
+                    writer.send(&ServerboundPacket::KeepAlive { id }).await?;
+                }
+                Some(Event::Teleport(position, teleport_id)) => {
+                    // The server ignores movement until the teleport is confirmed
+                    writer.send(&ServerboundPacket::TeleportConfirm { teleport_id }).await?;
+                    bot.teleport(position);
+                }
+                Some(Event::BlockChange(pos)) => {
+                    if let Some(latency) = bot.block_changed(pos) {
+                        stats.latencies.lock().unwrap().push(latency);
+                    }
+                }
+                Some(Event::Disconnect(reason)) => break Err(anyhow!(reason)),
+                None => break Err(anyhow!("Connection closed")),
+            },
+            _ = actions.tick() => {
+                for packet in bot.act() {
+                    writer.send(&packet).await?;
+                }
+            }
+        }
+    };
+    reading.abort();
+    stats.online.fetch_sub(1, Ordering::Relaxed);
+    result
+}
+
+async fn read_packets(mut reader: ClientReader, events: UnboundedSender<Event>,
+    stats: Arc<Stats>)
+{
+    loop {
+        let event = match reader.recv().await {
+            Ok(ClientboundPacket::ChunkData { data, .. }) => {
+                stats.chunks.fetch_add(1, Ordering::Relaxed);
+                stats.chunk_bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+                continue;
+            }
+            Ok(ClientboundPacket::EntityPosition { .. })
+            | Ok(ClientboundPacket::EntityPositionAndRotation { .. })
+            | Ok(ClientboundPacket::EntityRotation { .. })
+            | Ok(ClientboundPacket::EntityHeadLook { .. })
+            | Ok(ClientboundPacket::EntityTeleport { .. }) => {
+                stats.movements.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            Ok(ClientboundPacket::PluginMessage { channel, content })
+                if channel == TICK_TIMES_CHANNEL && content.len() == 8 =>
+            {
+                let micros = |bytes: &[u8]| {
+                    Duration::from_micros(u32::from_be_bytes(bytes.try_into().unwrap()) as u64)
+                };
+                let times = (micros(&content[0..4]), micros(&content[4..8]));
+                stats.tick_times.lock().unwrap().push(times);
+                continue;
+            }
+            Ok(ClientboundPacket::KeepAlive(id)) => Event::KeepAlive(id),
+            Ok(ClientboundPacket::PlayerPositionAndLook { x, y, z, teleport_id, .. }) => {
+                Event::Teleport(vector!(x, y, z), teleport_id)
+            }
+            Ok(ClientboundPacket::BlockChange { pos, .. }) => Event::BlockChange(pos),
+            Ok(ClientboundPacket::Disconnect { reason }) => Event::Disconnect(reason.to_plain()),
+            Ok(_) => continue,
+            Err(err) => Event::Disconnect(err.to_string()),
+        };
+        if events.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Bot {
+    // Where the server last put the bot, it wanders around this
+    spawn: Option<Vector3<f64>>,
+    position: Vector3<f64>,
+    // A block the bot placed and hasn't broken yet
+    placed: Option<Vector3<i32>>,
+    // The change the bot is waiting to see, and when it was sent
+    pending: Option<(Vector3<i32>, Instant)>,
+}
+
+impl Bot {
+    fn teleport(&mut self, position: Vector3<f64>) {
+        self.spawn = Some(position);
+        self.position = position;
+    }
+
+    fn block_changed(&mut self, pos: Vector3<i32>) -> Option<Duration> {
+        match self.pending {
+            Some((pending, sent)) if pending == pos => {
+                self.pending = None;
+                Some(sent.elapsed())
+            }
+            _ => None,
+        }
+    }
+
+    fn act(&mut self) -> Vec<ServerboundPacket> {
+        let spawn = match self.spawn {
+            Some(spawn) => spawn,
+            None => return vec![], // Not in the world yet
+        };
+        let step = vector!(random_offset(STEP_LENGTH), 0.0, random_offset(STEP_LENGTH));
+        let mut next = self.position + step;
+        if (next - spawn).norm() > WANDER_RADIUS {
+            next = self.position - step;
+        }
+        self.position = next;
+        let mut packets = vec![ServerboundPacket::PlayerPosition {
+            x: next.x,
+            y: next.y,
+            z: next.z,
+            on_ground: true,
+        }];
+        if self.pending.is_none() && rand::random::<f64>() < BUILD_CHANCE {
+            packets.push(self.build());
+        }
+        packets
+    }
+
+    // Alternates between placing a block next to the bot and breaking it
+    fn build(&mut self) -> ServerboundPacket {
+        match self.placed.take() {
+            Some(pos) => {
+                self.pending = Some((pos, Instant::now()));
+                ServerboundPacket::PlayerDigging {
+                    status: 0,
+                    position: pos,
+                    face: BlockFace::PosY as u8,
+                }
+            }
+            None => {
+                let feet = self.position.map(|coord| coord.floor() as i32);
+                let ground = feet + vector!(1, -1, 0);
+                let pos = BlockFace::PosY.get_adjacent(ground);
+                self.placed = Some(pos);
+                self.pending = Some((pos, Instant::now()));
+                ServerboundPacket::PlayerBlockPlacement {
+                    hand: 0,
+                    location: ground,
+                    face: BlockFace::PosY,
+                    cursor_position: vector!(0.5, 1.0, 0.5),
+                    inside_block: false,
+                }
+            }
+        }
+    }
+}
+
+fn random_offset(max: f64) -> f64 {
+    (rand::random::<f64>() * 2.0 - 1.0) * max
+}
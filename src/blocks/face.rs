@@ -1,7 +1,7 @@
 use nalgebra::{Vector3, vector};
 use num_derive::FromPrimitive;
 
-#[derive(FromPrimitive)]
+#[derive(Clone, Copy, FromPrimitive)]
 pub enum BlockFace {
     NegY = 0, PosY,
     NegZ, PosZ,
@@ -10,14 +10,15 @@ use super::behavior::{
 use crate::blocks::{BlockFace, Block, BlockType, BlockClass};
 
 impl BlockType {
+    // Returns whether the block went down
     pub fn place(&self, view: &WorldView, face: BlockFace, 
-        cursor: Vector3<f32>, angle: (f32, f32))
+        cursor: Vector3<f32>, angle: (f32, f32)) -> bool
     {
         let mut view = view.clone();
         if !view.get(0, 0, 0).material.replaceable {
             view.displace(face.get_direction());
             if !view.get(0, 0, 0).material.replaceable {
-                return;
+                return false;
             }
         }
         let mut props = self.auto_fill_props(
@@ -37,7 +38,7 @@ impl BlockType {
             },
             BlockClass::LadderBlock => {
                 match face {
-                    BlockFace::PosY | BlockFace::NegY => return,
+                    BlockFace::PosY | BlockFace::NegY => return false,
                     face => {
                         props.insert("facing".into(),
                             facing_from_face(&face));
@@ -52,7 +53,7 @@ impl BlockType {
             },
             BlockClass::DoorBlock => {
                 if !view.get(0, 1, 0).material.replaceable {
-                    return;
+                    return false;
                 }
                 props.insert("facing".into(), 
                 facing_from_angle(angle.0 + 180.0));
@@ -63,10 +64,10 @@ impl BlockType {
             },
             BlockClass::TallFlowerBlock | BlockClass::TallPlantBlock => {
                 if !can_survive_on(view.get(0, -1, 0)) {
-                    return;
+                    return false;
                 }
                 if !view.get(0, 1, 0).material.replaceable {
-                    return;
+                    return false;
                 }
                 props.insert("half".into(), "upper".into());
                 let block = self.with_props(&props).unwrap();
@@ -75,12 +76,12 @@ impl BlockType {
             },
             BlockClass::FlowerBlock | BlockClass::FernBlock => {
                 if !can_survive_on(view.get(0, -1, 0)) {
-                    return;
+                    return false;
                 }
             },
             BlockClass::CropBlock => {
                 if view.get(0, -1, 0).btype.name != "minecraft:farmland" {
-                    return;
+                    return false;
                 }
             },
             BlockClass::FenceBlock | BlockClass::PaneBlock |
@@ -93,6 +94,7 @@ impl BlockType {
         }
         let block = self.with_props(&props).unwrap();
         view.set(0, 0, 0, block);
+        true
     }
 
     fn auto_fill_props(&self, replacing: &Block, face: &BlockFace, 
@@ -8,6 +8,7 @@ use super::BlockClass;
 
 const MATERIALS_JSON: &str = include_str!("materials.json");
 const BLOCKS_JSON: &str = include_str!("blocks.json");
+const HARDNESS_JSON: &str = include_str!("hardness.json");
 
 lazy_static! {
     pub static ref MATERIALS: HashMap<String, BlockMaterial> = {
@@ -19,6 +20,9 @@ lazy_static! {
         map
     };
     
+    static ref HARDNESS: HashMap<String, HardnessJson> =
+        serde_json::from_str(HARDNESS_JSON).unwrap();
+
     pub static ref BLOCK_TYPES: Vec<BlockType> = {
         let mut result: Vec<BlockType> = 
             serde_json::from_str::<HashMap<String, BlockTypeJson>>(BLOCKS_JSON).unwrap()
@@ -30,6 +34,7 @@ lazy_static! {
                     .map(|(id, state)| {
                         (state.into_props(), id)
                     }).collect();
+                let hardness = HARDNESS.get(&name).cloned().unwrap_or_default();
                 BlockType {
                     id: json.id,
                     name,
@@ -37,6 +42,8 @@ lazy_static! {
                     class: json.class,
                     default_state,
                     states,
+                    hardness: hardness.hardness,
+                    requires_tool: hardness.requires_tool,
                 }
             })
             .collect();
@@ -80,6 +87,14 @@ pub struct BlockTypeJson {
     states: HashMap<u16, BlockStateJson>,
 }
 
+// How long it takes to break and whether it drops anything without the right tool
+#[derive(Clone, Default, Deserialize)]
+struct HardnessJson {
+    hardness: f32,
+    #[serde(default)]
+    requires_tool: bool,
+}
+
 #[derive(Clone, Deserialize)]
 pub struct BlockStateJson {
     material: String,
@@ -13,6 +13,10 @@ pub struct BlockType {
     pub class: BlockClass,
     pub default_state: BTreeMap<String, String>,
     pub states: HashMap<BTreeMap<String, String>, u16>,
+    // -1 for blocks that can't be broken
+    pub hardness: f32,
+    // Only drops when broken with the correct tool
+    pub requires_tool: bool,
 }
 
 impl PartialEq for BlockType {
@@ -27,6 +27,10 @@ impl Bucket {
         self.entities.remove(&id);
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
     pub fn get_entities(&self) -> Vec<(u32, Entity)> {
         self.entities.iter()
             .map(|(k, v)| (*k, *v))
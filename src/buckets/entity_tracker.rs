@@ -12,7 +12,9 @@ pub fn unload_buckets(#[resource] tracker: &mut EntityTracker) {
     let buckets = tracker.buckets.get_mut().unwrap();
     buckets.retain(|_, bucket| {
         let mut bucket = bucket.write().unwrap();
-        bucket.time_unobserved() < UNLOAD_TIME
+        // Entities nobody sees, like dropped items, still have to be sent
+        // once a player comes back
+        !bucket.is_empty() || bucket.time_unobserved() < UNLOAD_TIME
     });
 }
 
@@ -1,5 +1,6 @@
 use legion::Entity;
 use nalgebra::Vector3;
+use crate::items::ItemStack;
 use super::coords::BucketCoords;
 
 #[derive(Clone, Debug)]
@@ -35,5 +36,18 @@ pub enum EntityEventData {
     },
     RotateHead {
         yaw: f32,
-    }
+    },
+    BreakBlock {
+        pos: Vector3<i32>,
+        stage: i8,
+    },
+    // An item picked up by `collector`, it's removed after this if nothing is left
+    Collect {
+        collector: u32,
+        count: u8,
+    },
+    // The stack of an item entity changed
+    SetItem {
+        stack: ItemStack,
+    },
 }
\ No newline at end of file
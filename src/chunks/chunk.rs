@@ -36,6 +36,10 @@ impl Chunk {
         })
     }
 
+    pub fn is_loaded(&self) -> bool {
+        self.data.is_some()
+    }
+
     pub fn get_block(&self, x: usize, y: usize, z: usize) -> &'static Block {
         match &self.data {
             Some(data) => data.read().unwrap().get_block(x, y, z),
@@ -98,3 +98,9 @@ impl ChunkData {
         chunk_tag
     }
 }
+
+impl Default for ChunkData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
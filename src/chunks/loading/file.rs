@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 use std::iter::repeat_with;
+use std::path::PathBuf;
 
 use anvil_nbt::{CompoundTag, Tag};
 use anvil_region::position::RegionChunkPosition;
@@ -18,18 +19,22 @@ use super::ChunkLoader;
 
 const SECTIONS_PER_CHUNK: usize = 16;
 
-pub struct AnvilChunkLoader;
+pub struct AnvilChunkLoader {
+    region_dir: PathBuf,
+}
 
 impl AnvilChunkLoader {
-    pub fn new() -> Self {
-        Self
+    pub fn new(region_dir: PathBuf) -> Self {
+        Self {
+            region_dir,
+        }
     }
 }
 
 #[async_trait]
 impl ChunkLoader for AnvilChunkLoader {
     async fn load_chunk(&self, coords: ChunkCoords) -> Option<ChunkData> {
-        let chunk = read_chunk(coords).await?;
+        let chunk = read_chunk(self.region_dir.clone(), coords).await?;
         let section_tags = chunk
             .get_compound_tag("Level").unwrap()
             .get_compound_tag_vec("Sections").unwrap();
@@ -56,14 +61,14 @@ impl ChunkLoader for AnvilChunkLoader {
     }
 }
 
-async fn read_chunk(coords: ChunkCoords) -> Option<CompoundTag> {
+async fn read_chunk(region_dir: PathBuf, coords: ChunkCoords) -> Option<CompoundTag> {
     let ChunkCoords(chunk_x, chunk_z) = coords;
     let region_position = 
         RegionPosition::from_chunk_position(chunk_x, chunk_z);
     let chunk_position = 
         RegionChunkPosition::from_chunk_position(chunk_x, chunk_z);
     task::spawn_blocking(move || {
-        let provider = FolderRegionProvider::new("world/region");
+        let provider = FolderRegionProvider::new(region_dir.to_str()?);
         let mut region = provider.get_region(region_position).ok()?;
         region.read_chunk(chunk_position).ok()
     }).await.ok()?
@@ -7,26 +7,27 @@ mod saving;
 mod view;
 pub mod events;
 
-use std::io::Write;
-
 pub use data::ChunkData;
 pub use coords::ChunkCoords;
 pub use world::World;
 pub use view::WorldView;
 use legion::{systems::Builder, Resources};
 use loading::{FlatGenerator, AnvilChunkLoader};
+use crate::config::Config;
+
+const REGION_DIR: &str = "world/region";
 
-pub fn register(schedule: &mut Builder, resources: &mut Resources) {
+pub fn register(schedule: &mut Builder, resources: &mut Resources, config: &Config) {
     world::register(schedule);
-    resources.insert(World::new(vec![
-        Box::new(AnvilChunkLoader::new()),
+    let region_dir = config.data_dir.join(REGION_DIR);
+    resources.insert(World::new(region_dir.clone(), vec![
+        Box::new(AnvilChunkLoader::new(region_dir)),
         Box::new(FlatGenerator),
     ]));
 }
 
 pub async fn on_stop(resources: &mut Resources) {
-    print!("Saving chunks...         ");
-    let _ = std::io::stdout().flush();
+    log!("Saving chunks...");
     resources.get_mut::<World>().unwrap().save_all();
-    println!("Done");
+    log!("Saved chunks");
 }
@@ -1,5 +1,6 @@
 use std::fs;
 use std::mem::replace;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock, mpsc::{Receiver, Sender, channel}};
 use std::thread::{self, JoinHandle};
 
@@ -11,16 +12,18 @@ use anvil_region::provider::{FolderRegionProvider, RegionProvider};
 use super::{ChunkCoords, ChunkData};
 
 pub struct ChunkSaver {
+    region_dir: PathBuf,
     sender: Sender<Job>,
     worker: JoinHandle<()>,
 }
 
 impl ChunkSaver {
-    pub fn new() -> Self {
+    pub fn new(region_dir: PathBuf) -> Self {
         let (sender, recv) = channel();
         Self {
+            worker: spawn_worker(region_dir.clone(), recv),
+            region_dir,
             sender,
-            worker: spawn_worker(recv),
         }
     }
 
@@ -39,22 +42,22 @@ impl ChunkSaver {
     fn replace_worker(&mut self) -> JoinHandle<()> {
         let (new_send, new_recv) = channel();
         self.sender = new_send;
-        let new_worker = spawn_worker(new_recv);
+        let new_worker = spawn_worker(self.region_dir.clone(), new_recv);
         replace(&mut self.worker, new_worker)
     }
 }
 
-fn spawn_worker(recv: Receiver<Job>) -> JoinHandle<()> {
+fn spawn_worker(region_dir: PathBuf, recv: Receiver<Job>) -> JoinHandle<()> {
     thread::Builder::new()
         .name("chunk saving".into())
         .spawn(move || {
-            worker(recv)
+            worker(region_dir, recv)
         }).unwrap()
 }
 
-fn worker(recv: Receiver<Job>) {
-    fs::create_dir_all("world/region").unwrap();
-    let provider = FolderRegionProvider::new("world/region");
+fn worker(region_dir: PathBuf, recv: Receiver<Job>) {
+    fs::create_dir_all(&region_dir).unwrap();
+    let provider = FolderRegionProvider::new(region_dir.to_str().unwrap());
 
     while let Ok(job) = recv.recv() {
         let Job(coords, chunk) = job;
@@ -76,7 +79,7 @@ fn worker(recv: Receiver<Job>) {
                     => anyhow!(format!("Too large ({} bytes)", length)),
                     ChunkWriteError::IOError { io_error } => io_error.into(),
                 };
-                eprintln!("Error saving chunk at ({}, {}): {}", 
+                log!("Error saving chunk at ({}, {}): {}", 
                     coords.0, coords.1, err);
             }
         };
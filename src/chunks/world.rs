@@ -12,6 +12,7 @@ use legion::systems::Builder;
 use nalgebra::Vector3;
 use nalgebra::vector;
 use std::mem::take;
+use std::path::PathBuf;
 use std::sync::Mutex;
 use std::time::Duration;
 use std::collections::HashMap;
@@ -85,11 +86,12 @@ pub struct World {
 }
 
 impl World {
-    pub fn new(chunk_sources: Vec<Box<dyn ChunkLoader>>) -> Self {
+    // Chunks are saved to the region files in `region_dir`
+    pub fn new(region_dir: PathBuf, chunk_sources: Vec<Box<dyn ChunkLoader>>) -> Self {
         Self {
             chunks: Arc::new(RwLock::new(HashMap::new())),
             chunk_loaders: Arc::new(chunk_sources),
-            saver: Mutex::new(ChunkSaver::new()),
+            saver: Mutex::new(ChunkSaver::new(region_dir)),
             changed: Mutex::new(vec![]),
         }
     }
@@ -116,7 +118,7 @@ impl World {
                             chunk.load(data);
                         }
                     } else {
-                        eprintln!("No chunk source could load chunk at {:?}", coords);
+                        log!("No chunk source could load chunk at {:?}", coords);
                     }
                 });
             }
@@ -131,6 +133,13 @@ impl World {
         }
     }
 
+    // Its data is there, not just waiting to be loaded
+    pub fn is_loaded(&self, coords: ChunkCoords) -> bool {
+        self.chunks.read().unwrap()
+            .get(&coords)
+            .is_some_and(|chunk| chunk.is_loaded())
+    }
+
     pub fn get_block(&self, pos: &Vector3<i32>) -> &'static Block {
         if pos.y < 0 || pos.y >= CHUNK_HEIGHT as i32 {
             return Block::air();
@@ -166,12 +175,17 @@ impl World {
     }
 
     pub fn save_all(&mut self) {
+        self.spawn_save_all();
+        self.saver.lock().unwrap().wait_completion();
+    }
+
+    // Leaves the saving to the saving thread without waiting for it
+    pub fn spawn_save_all(&self) {
         let mut saver = self.saver.lock().unwrap();
-        let chunks = self.chunks.write().unwrap();
+        let chunks = self.chunks.read().unwrap();
         for (coords, chunk) in chunks.iter() {
             chunk.save(*coords, &mut saver);
         }
-        saver.wait_completion();
     }
 }
 
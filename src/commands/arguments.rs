@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use nalgebra::{Vector3, vector};
+use crate::blocks::{Block, BlockType};
+use crate::items::{ItemStack, ItemType};
+use super::reader::StringReader;
+use super::selector::{EntitySelector, SELECTORS};
+
+#[derive(Clone, Debug)]
+pub enum ArgumentType {
+    Integer {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    Word,
+    GreedyString,
+    BlockPos,
+    BlockState,
+    ItemStack,
+    Entity {
+        single: bool,
+        players_only: bool,
+    },
+    // A player name, who may be offline
+    GameProfile,
+    // A length of time like `30m`, `12h` or `1d12h`
+    Duration,
+}
+
+#[derive(Clone)]
+pub enum ArgumentValue {
+    Integer(i32),
+    String(String),
+    BlockPos([Coordinate; 3]),
+    BlockState(&'static Block),
+    ItemStack(ItemStack),
+    Entity(EntitySelector),
+    Duration(Duration),
+}
+
+// A coordinate as typed, `~` ones are relative to where the command runs
+#[derive(Clone, Copy, Debug)]
+pub struct Coordinate {
+    pub relative: bool,
+    pub value: f64,
+}
+
+impl ArgumentType {
+    pub fn parse(&self, reader: &mut StringReader) -> Result<ArgumentValue> {
+        match self {
+            Self::Integer { min, max } => {
+                let value = reader.read_int()?;
+                if let Some(min) = min.filter(|min| value < *min) {
+                    return Err(anyhow!("Integer must not be less than {}, found {}", min, value));
+                }
+                if let Some(max) = max.filter(|max| value > *max) {
+                    return Err(anyhow!("Integer must not be more than {}, found {}", max, value));
+                }
+                Ok(ArgumentValue::Integer(value))
+            }
+            Self::Word => {
+                let word = reader.read_word();
+                if word.is_empty() {
+                    return Err(anyhow!("Expected a word"));
+                }
+                Ok(ArgumentValue::String(word.to_owned()))
+            }
+            Self::GreedyString => Ok(ArgumentValue::String(reader.read_rest().to_owned())),
+            Self::GameProfile => {
+                let name = reader.read_word();
+                if name.is_empty() {
+                    return Err(anyhow!("Expected a player name"));
+                }
+                Ok(ArgumentValue::String(name.to_owned()))
+            }
+            Self::BlockPos => {
+                let x = read_coordinate(reader)?;
+                reader.expect(' ')?;
+                let y = read_coordinate(reader)?;
+                reader.expect(' ')?;
+                let z = read_coordinate(reader)?;
+                Ok(ArgumentValue::BlockPos([x, y, z]))
+            }
+            Self::BlockState => Ok(ArgumentValue::BlockState(read_block_state(reader)?)),
+            Self::ItemStack => {
+                let name = read_resource_name(reader);
+                let item = ItemType::from_name(&name)
+                    .map_err(|_| anyhow!("Unknown item '{}'", name))?;
+                if reader.peek() == Some('{') {
+                    return Err(anyhow!("Item NBT isn't supported"));
+                }
+                Ok(ArgumentValue::ItemStack(ItemStack { item, count: 1, nbt: None }))
+            }
+            Self::Entity { single, players_only } => {
+                let selector = EntitySelector::parse(reader)?;
+                if *single && !selector.is_single() {
+                    return Err(anyhow!("Only one entity is allowed, but the provided \
+                        selector allows more than one"));
+                }
+                if *players_only && selector.allows_non_players() {
+                    return Err(anyhow!("Only players may be affected by this command, \
+                        but the provided selector includes entities"));
+                }
+                Ok(ArgumentValue::Entity(selector))
+            }
+            Self::Duration => Ok(ArgumentValue::Duration(read_duration(reader)?)),
+        }
+    }
+
+    // Parser identifier and properties sent in Declare Commands
+    pub fn parser(&self) -> (&'static str, Vec<u8>) {
+        match self {
+            Self::Integer { min, max } => {
+                let mut properties = vec![min.is_some() as u8 | (max.is_some() as u8) << 1];
+                for bound in min.iter().chain(max) {
+                    properties.extend_from_slice(&bound.to_be_bytes());
+                }
+                ("brigadier:integer", properties)
+            }
+            Self::Word => ("brigadier:string", vec![0]),
+            Self::GreedyString => ("brigadier:string", vec![2]),
+            Self::BlockPos => ("minecraft:block_pos", vec![]),
+            Self::BlockState => ("minecraft:block_state", vec![]),
+            Self::ItemStack => ("minecraft:item_stack", vec![]),
+            Self::Entity { single, players_only } => {
+                ("minecraft:entity", vec![*single as u8 | (*players_only as u8) << 1])
+            }
+            Self::GameProfile => ("minecraft:game_profile", vec![]),
+            Self::Duration => ("brigadier:string", vec![0]),
+        }
+    }
+
+    // The client suggests everything else by itself, but doesn't know
+    // which players the server has
+    pub fn asks_server(&self) -> bool {
+        matches!(self, Self::Entity { .. } | Self::GameProfile)
+    }
+
+    pub fn suggest(&self, player_names: &[String]) -> Vec<String> {
+        match self {
+            Self::Entity { .. } => player_names.iter().cloned()
+                .chain(SELECTORS.iter().map(|selector| selector.to_string()))
+                .collect(),
+            Self::GameProfile => player_names.to_vec(),
+            Self::BlockPos => vec!["~ ~ ~".into()],
+            _ => vec![],
+        }
+    }
+}
+
+impl Coordinate {
+    pub fn resolve(&self, origin: f64) -> f64 {
+        if self.relative {
+            origin + self.value
+        } else {
+            self.value
+        }
+    }
+}
+
+pub fn resolve_block_pos(coords: &[Coordinate; 3], origin: Vector3<f64>) -> Vector3<i32> {
+    vector!(
+        coords[0].resolve(origin.x).floor() as i32,
+        coords[1].resolve(origin.y).floor() as i32,
+        coords[2].resolve(origin.z).floor() as i32
+    )
+}
+
+fn read_coordinate(reader: &mut StringReader) -> Result<Coordinate> {
+    match reader.peek() {
+        Some('^') => Err(anyhow!("Local coordinates aren't supported")),
+        Some('~') => {
+            reader.skip();
+            let value = match reader.peek() {
+                Some(' ') | None => 0.0,
+                _ => reader.read_double()?,
+            };
+            Ok(Coordinate { relative: true, value })
+        }
+        _ => Ok(Coordinate { relative: false, value: reader.read_int()? as f64 }),
+    }
+}
+
+// Names without a namespace are in `minecraft:`
+fn read_resource_name(reader: &mut StringReader) -> String {
+    let name = reader.read_while(|c| {
+        c.is_ascii_lowercase() || c.is_ascii_digit() || "_-.:/".contains(c)
+    });
+    if name.contains(':') {
+        name.to_owned()
+    } else {
+        format!("minecraft:{}", name)
+    }
+}
+
+// Numbers each followed by a unit: s, m, h, d or w
+fn read_duration(reader: &mut StringReader) -> Result<Duration> {
+    let word = reader.read_word();
+    let invalid = || anyhow!("Invalid duration '{}', expected something like 30m or 7d", word);
+    let mut seconds: u64 = 0;
+    let mut rest = word;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let amount: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        let unit = match rest[digits..].chars().next() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => 24 * 60 * 60,
+            Some('w') => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        seconds = amount.checked_mul(unit)
+            .and_then(|amount| seconds.checked_add(amount))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + 1..];
+    }
+    if word.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+fn read_block_state(reader: &mut StringReader) -> Result<&'static Block> {
+    let name = read_resource_name(reader);
+    let block_type = BlockType::from_name(&name)
+        .ok_or_else(|| anyhow!("Unknown block type '{}'", name))?;
+    let mut props: BTreeMap<String, String> = block_type.default_state.clone();
+    if reader.peek() == Some('[') {
+        reader.skip();
+        while reader.peek() != Some(']') {
+            let key = reader.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            reader.expect('=')?;
+            let value = reader.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !props.contains_key(key) {
+                return Err(anyhow!("Block {} doesn't have property '{}'", name, key));
+            }
+            props.insert(key.to_owned(), value.to_owned());
+            if reader.peek() == Some(',') {
+                reader.skip();
+            } else if reader.peek() != Some(']') {
+                return Err(anyhow!("Expected end of properties"));
+            }
+        }
+        reader.skip();
+    }
+    block_type.with_props(&props)
+        .map_err(|_| anyhow!("Invalid properties for block {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::reader::StringReader;
+    use super::{ArgumentType, ArgumentValue};
+
+    #[test]
+    fn integer_test() {
+        let argument = ArgumentType::Integer { min: Some(1), max: Some(64) };
+        assert!(matches!(argument.parse(&mut StringReader::new("12")),
+            Ok(ArgumentValue::Integer(12))));
+        assert!(argument.parse(&mut StringReader::new("65")).is_err());
+        assert!(argument.parse(&mut StringReader::new("abc")).is_err());
+        assert_eq!(argument.parser(), ("brigadier:integer", vec![3, 0, 0, 0, 1, 0, 0, 0, 64]));
+    }
+
+    #[test]
+    fn block_pos_test() {
+        let mut reader = StringReader::new("~ ~-1 5");
+        match ArgumentType::BlockPos.parse(&mut reader) {
+            Ok(ArgumentValue::BlockPos([x, y, z])) => {
+                assert!(x.relative && x.value == 0.0);
+                assert!(y.relative && y.value == -1.0);
+                assert!(!z.relative && z.value == 5.0);
+            }
+            _ => panic!("Couldn't parse block position"),
+        }
+        assert!(ArgumentType::BlockPos.parse(&mut StringReader::new("^ ^ ^")).is_err());
+    }
+
+    #[test]
+    fn duration_test() {
+        let parse = |text| match ArgumentType::Duration.parse(&mut StringReader::new(text)) {
+            Ok(ArgumentValue::Duration(duration)) => Some(duration.as_secs()),
+            _ => None,
+        };
+        assert_eq!(parse("30m"), Some(30 * 60));
+        assert_eq!(parse("1d12h"), Some(36 * 60 * 60));
+        assert_eq!(parse("2w"), Some(14 * 24 * 60 * 60));
+        assert_eq!(parse("12"), None);
+        assert_eq!(parse("griefing"), None);
+        assert_eq!(parse(""), None);
+    }
+}
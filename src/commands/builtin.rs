@@ -0,0 +1,163 @@
+use std::sync::atomic::Ordering;
+use anyhow::{Result, anyhow};
+use legion::*;
+use crate::chunks::World as Chunks;
+use crate::entities::{ItemSpawner, Name, Position, Rotation};
+use crate::inventory::Inventory;
+use crate::items::ItemStack;
+use crate::net::{ChatPosition, ClientboundPacket, PlayerConnection, Server};
+use crate::players::{Chat, GameMode, PlayerList, change_game_mode, spawn_save_players};
+use crate::text::TextComponent;
+use crate::STOP_SIGNAL;
+use super::arguments::ArgumentType;
+use super::CommandSource;
+use super::context::CommandContext;
+use super::dispatcher::{CommandBuilder, Dispatcher, Executor, argument, literal};
+
+const MAX_GIVE_COUNT: i32 = 6400;
+
+pub fn register(dispatcher: &mut Dispatcher) {
+    let players = || ArgumentType::Entity { single: false, players_only: true };
+    dispatcher.register(literal("list").executes(list));
+    dispatcher.register(literal("save-all").requires(4).executes(save_all));
+    dispatcher.register(literal("stop").requires(4).executes(stop));
+    dispatcher.register(literal("kick").requires(3)
+        .then(argument("targets", players())
+            .executes(kick)
+            .then(argument("reason", ArgumentType::GreedyString).executes(kick))));
+    dispatcher.register(literal("say").requires(2)
+        .then(argument("message", ArgumentType::GreedyString).executes(say)));
+    dispatcher.register(literal("setblock").requires(2)
+        .then(argument("pos", ArgumentType::BlockPos)
+            .then(argument("block", ArgumentType::BlockState).executes(setblock))));
+    dispatcher.register(literal("give").requires(2)
+        .then(argument("targets", players())
+            .then(argument("item", ArgumentType::ItemStack)
+                .executes(give)
+                .then(argument("count", ArgumentType::Integer {
+                    min: Some(1),
+                    max: Some(MAX_GIVE_COUNT),
+                }).executes(give)))));
+    dispatcher.register(literal("gamemode").requires(2)
+        .then(gamemode_literal("survival", |ctx| gamemode(ctx, GameMode::Survival)))
+        .then(gamemode_literal("creative", |ctx| gamemode(ctx, GameMode::Creative)))
+        .then(gamemode_literal("adventure", |ctx| gamemode(ctx, GameMode::Adventure)))
+        .then(gamemode_literal("spectator", |ctx| gamemode(ctx, GameMode::Spectator))));
+}
+
+fn gamemode_literal(name: &str, executor: Executor) -> CommandBuilder {
+    let players = ArgumentType::Entity { single: false, players_only: true };
+    literal(name)
+        .executes(executor)
+        .then(argument("targets", players).executes(executor))
+}
+
+fn list(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let names = ctx.resources.get::<PlayerList>().unwrap().get_names();
+    let max = ctx.resources.get::<Server>().unwrap().max_players();
+    Ok(Some(format!("There are {} of a max of {} players online: {}",
+        names.len(), max, names.join(", ")).into()))
+}
+
+fn save_all(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    spawn_save_players(ctx.world, ctx.resources);
+    ctx.resources.get::<Chunks>().unwrap().spawn_save_all();
+    Ok(Some("Saving the game (this may take a moment!)".into()))
+}
+
+fn stop(_: &mut CommandContext) -> Result<Option<TextComponent>> {
+    STOP_SIGNAL.store(true, Ordering::Relaxed);
+    Ok(Some("Stopping the server".into()))
+}
+
+fn kick(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let targets = ctx.get_players("targets")?;
+    let reason = if ctx.has("reason") {
+        TextComponent::text(ctx.get_string("reason")?)
+    } else {
+        TextComponent::translate("multiplayer.disconnect.kicked", vec![])
+    };
+    let mut names = vec![];
+    for entity in targets {
+        names.push(ctx.kick(entity, reason.clone())?);
+    }
+    Ok(Some(format!("Kicked {}: {}", names.join(", "), reason.to_plain()).into()))
+}
+
+fn say(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let message = TextComponent::translate("chat.type.announcement", vec![
+        ctx.source_name().into(),
+        ctx.get_string("message")?.into(),
+    ]);
+    ctx.resources.get_mut::<Chat>().unwrap().broadcast(message, ChatPosition::Chat);
+    Ok(None)
+}
+
+fn setblock(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let pos = ctx.get_block_pos("pos")?;
+    let block = ctx.get_block_state("block")?;
+    if pos.y < 0 || pos.y > 255 {
+        return Err(anyhow!("Cannot place blocks outside of the world"));
+    }
+    let chunks = ctx.resources.get::<Chunks>().unwrap();
+    if std::ptr::eq(chunks.get_block(&pos), block) {
+        return Err(anyhow!("Could not set the block"));
+    }
+    chunks.set_block(&pos, block);
+    Ok(Some(format!("Changed the block at {}, {}, {}", pos.x, pos.y, pos.z).into()))
+}
+
+fn give(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let targets = ctx.get_players("targets")?;
+    let stack = ctx.get_item("item")?;
+    let count = if ctx.has("count") { ctx.get_int("count")? } else { 1 };
+    let max_stack = stack.item.max_stack_size() as u32;
+    let mut spawner = ctx.resources.get_mut::<ItemSpawner>().unwrap();
+    let mut names = vec![];
+    for entity in targets {
+        let mut entry = ctx.world.entry_mut(entity)?;
+        let inventory = entry.get_component_mut::<Inventory>()?;
+        let mut left = inventory.add(stack.item, count as u32);
+        let items = inventory.get_window();
+        entry.get_component::<PlayerConnection>()?.send(ClientboundPacket::WindowItems {
+            window: 0,
+            items,
+        });
+        // What doesn't fit is thrown by the player, like vanilla
+        let pos = entry.get_component::<Position>()?.0;
+        let rotation = entry.get_component::<Rotation>()?;
+        while left > 0 {
+            let dropped = left.min(max_stack);
+            let dropped_stack = ItemStack { item: stack.item, count: dropped as u8, nbt: None };
+            spawner.give_to_player(pos, (rotation.0, rotation.1), dropped_stack);
+            left -= dropped;
+        }
+        names.push(entry.get_component::<Name>()?.0.clone());
+    }
+    Ok(Some(format!("Gave {} [{}] to {}", count, stack.item.to_str(), names.join(", ")).into()))
+}
+
+fn gamemode(ctx: &mut CommandContext, mode: GameMode) -> Result<Option<TextComponent>> {
+    let targets = if ctx.has("targets") {
+        ctx.get_players("targets")?
+    } else {
+        match ctx.source {
+            CommandSource::Player(entity) => vec![entity],
+            _ => return Err(anyhow!("A player is required to run this command here")),
+        }
+    };
+    let mut names = vec![];
+    for entity in targets {
+        let mut list = ctx.resources.get_mut::<PlayerList>().unwrap();
+        let mut entry = ctx.world.entry_mut(entity)?;
+        // Players already in that mode don't count, like in vanilla
+        if change_game_mode(&mut entry, &mut list, mode)? {
+            names.push(entry.get_component::<Name>()?.0.clone());
+        }
+    }
+    if names.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!("Set the game mode of {} to {}",
+        names.join(", "), mode.display_name()).into()))
+}
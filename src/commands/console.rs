@@ -0,0 +1,96 @@
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Sender;
+use std::thread;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use crate::STOP_SIGNAL;
+use crate::logging::set_console;
+use super::{CommandRequest, CommandSource};
+
+const PROMPT: &str = "> ";
+
+// Reading stdin blocks, so it gets its own thread. While a line is being
+// typed, the log is printed above it through `logging`
+pub fn start(commands: Sender<CommandRequest>) {
+    terminal::save();
+    thread::Builder::new()
+        .name("console".to_owned())
+        .spawn(move || {
+            let mut editor = match DefaultEditor::new() {
+                Ok(editor) => editor,
+                Err(err) => {
+                    crate::log!("Couldn't start the console: {}", err);
+                    return;
+                }
+            };
+            // There's nothing to redraw when stdin or stdout isn't a terminal
+            if let Ok(printer) = editor.create_external_printer() {
+                set_console(Some(Box::new(printer)));
+            }
+            loop {
+                let line = match editor.readline(PROMPT) {
+                    Ok(line) => line,
+                    // The terminal is in raw mode, so Ctrl + C ends up here
+                    // instead of the ctrlc handler
+                    Err(ReadlineError::Interrupted) => {
+                        STOP_SIGNAL.store(true, Ordering::Relaxed);
+                        continue;
+                    }
+                    // Stdin was closed, like when running in the background
+                    Err(_) => break,
+                };
+                let command = line.trim();
+                if command.is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(command);
+                let request = CommandRequest {
+                    command: command.to_owned(),
+                    source: CommandSource::Console,
+                    reply: None,
+                };
+                if commands.send(request).is_err() {
+                    break;
+                }
+            }
+            set_console(None);
+        })
+        .expect("Couldn't start the console thread");
+}
+
+// The console thread is usually waiting for a line when the server stops,
+// with the terminal still in raw mode
+pub fn stop() {
+    set_console(None);
+    terminal::restore();
+}
+
+#[cfg(unix)]
+mod terminal {
+    use std::sync::Mutex;
+    use lazy_static::lazy_static;
+    use nix::sys::termios::{SetArg, Termios, tcgetattr, tcsetattr};
+
+    const STDIN: i32 = 0;
+
+    lazy_static! {
+        static ref SAVED: Mutex<Option<Termios>> = Mutex::new(None);
+    }
+
+    pub fn save() {
+        *SAVED.lock().unwrap() = tcgetattr(STDIN).ok();
+    }
+
+    pub fn restore() {
+        if let Some(termios) = SAVED.lock().unwrap().as_ref() {
+            let _ = tcsetattr(STDIN, SetArg::TCSANOW, termios);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod terminal {
+    pub fn save() {}
+
+    pub fn restore() {}
+}
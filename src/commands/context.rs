@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use legion::*;
+use nalgebra::{Vector3, vector};
+use crate::blocks::Block;
+use crate::entities::{Name, Position};
+use crate::items::ItemStack;
+use crate::net::{ClientboundPacket, PlayerConnection};
+use crate::players::DisconnectionQueue;
+use crate::text::TextComponent;
+use super::CommandSource;
+use super::arguments::{ArgumentValue, resolve_block_pos};
+
+// What an executor gets to work with, the arguments are looked up by the
+// names they have in the command tree
+pub struct CommandContext<'a> {
+    pub world: &'a mut World,
+    pub resources: &'a Resources,
+    pub source: CommandSource,
+    arguments: HashMap<String, ArgumentValue>,
+}
+
+impl<'a> CommandContext<'a> {
+    pub fn new(world: &'a mut World, resources: &'a Resources, source: CommandSource,
+        arguments: HashMap<String, ArgumentValue>) -> Self
+    {
+        Self { world, resources, source, arguments }
+    }
+
+    // Same names as vanilla uses in messages
+    pub fn source_name(&self) -> String {
+        match self.source {
+            CommandSource::Console => "Server".to_owned(),
+            CommandSource::Rcon => "Rcon".to_owned(),
+            CommandSource::Player(entity) => self.world.entry_ref(entity).ok()
+                .and_then(|entry| entry.get_component::<Name>().ok().map(|name| name.0.clone()))
+                .unwrap_or_default(),
+        }
+    }
+
+    // Where relative coordinates start from
+    pub fn origin(&self) -> Vector3<f64> {
+        match self.source {
+            CommandSource::Player(entity) => self.world.entry_ref(entity).ok()
+                .and_then(|entry| entry.get_component::<Position>().ok().map(|pos| pos.0))
+                .unwrap_or_else(|| vector!(0.0, 0.0, 0.0)),
+            _ => vector!(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.arguments.contains_key(name)
+    }
+
+    fn get(&self, name: &str) -> Result<&ArgumentValue> {
+        self.arguments.get(name)
+            .ok_or_else(|| anyhow!("Missing argument '{}'", name))
+    }
+
+    pub fn get_int(&self, name: &str) -> Result<i32> {
+        match self.get(name)? {
+            ArgumentValue::Integer(value) => Ok(*value),
+            _ => Err(anyhow!("Argument '{}' isn't an integer", name)),
+        }
+    }
+
+    pub fn get_string(&self, name: &str) -> Result<&str> {
+        match self.get(name)? {
+            ArgumentValue::String(value) => Ok(value),
+            _ => Err(anyhow!("Argument '{}' isn't a string", name)),
+        }
+    }
+
+    pub fn get_duration(&self, name: &str) -> Result<Duration> {
+        match self.get(name)? {
+            ArgumentValue::Duration(duration) => Ok(*duration),
+            _ => Err(anyhow!("Argument '{}' isn't a duration", name)),
+        }
+    }
+
+    pub fn get_block_pos(&self, name: &str) -> Result<Vector3<i32>> {
+        match self.get(name)? {
+            ArgumentValue::BlockPos(coords) => Ok(resolve_block_pos(coords, self.origin())),
+            _ => Err(anyhow!("Argument '{}' isn't a block position", name)),
+        }
+    }
+
+    pub fn get_block_state(&self, name: &str) -> Result<&'static Block> {
+        match self.get(name)? {
+            ArgumentValue::BlockState(block) => Ok(*block),
+            _ => Err(anyhow!("Argument '{}' isn't a block state", name)),
+        }
+    }
+
+    pub fn get_item(&self, name: &str) -> Result<ItemStack> {
+        match self.get(name)? {
+            ArgumentValue::ItemStack(stack) => Ok(stack.clone()),
+            _ => Err(anyhow!("Argument '{}' isn't an item", name)),
+        }
+    }
+
+    // Disconnects a player and returns their name
+    pub fn kick(&self, player: Entity, reason: TextComponent) -> Result<String> {
+        let entry = self.world.entry_ref(player)?;
+        entry.get_component::<PlayerConnection>()?.send(ClientboundPacket::Disconnect {
+            reason: reason.clone(),
+        });
+        self.resources.get::<DisconnectionQueue>().unwrap()
+            .send(player, format!("Kicked by {}: {}", self.source_name(), reason.to_plain()));
+        Ok(entry.get_component::<Name>()?.0.clone())
+    }
+
+    // Fails like vanilla when the selector doesn't match anyone
+    pub fn get_players(&self, name: &str) -> Result<Vec<Entity>> {
+        match self.get(name)? {
+            ArgumentValue::Entity(selector) => {
+                let sender = match self.source {
+                    CommandSource::Player(entity) => Some(entity),
+                    _ => None,
+                };
+                let players = selector.resolve(self.world, self.origin(), sender);
+                if players.is_empty() {
+                    return Err(anyhow!("No player was found"));
+                }
+                Ok(players)
+            }
+            _ => Err(anyhow!("Argument '{}' isn't an entity selector", name)),
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+use crate::net::CommandNode;
+use crate::text::TextComponent;
+use super::arguments::{ArgumentType, ArgumentValue};
+use super::context::CommandContext;
+use super::reader::StringReader;
+
+// Returns the feedback for whoever ran the command, if there's any
+pub type Executor = fn(&mut CommandContext) -> Result<Option<TextComponent>>;
+
+enum NodeKind {
+    Root,
+    Literal(String),
+    Argument {
+        name: String,
+        argument: ArgumentType,
+    },
+}
+
+struct Node {
+    kind: NodeKind,
+    children: Vec<usize>,
+    executor: Option<Executor>,
+    permission: u8,
+}
+
+// Builds a command tree to register, like Brigadier's ArgumentBuilder
+pub struct CommandBuilder {
+    kind: NodeKind,
+    children: Vec<CommandBuilder>,
+    executor: Option<Executor>,
+    permission: u8,
+}
+
+pub fn literal(name: &str) -> CommandBuilder {
+    CommandBuilder::new(NodeKind::Literal(name.to_owned()))
+}
+
+pub fn argument(name: &str, argument: ArgumentType) -> CommandBuilder {
+    CommandBuilder::new(NodeKind::Argument { name: name.to_owned(), argument })
+}
+
+impl CommandBuilder {
+    fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            children: vec![],
+            executor: None,
+            permission: 0,
+        }
+    }
+
+    pub fn requires(mut self, permission: u8) -> Self {
+        self.permission = permission;
+        self
+    }
+
+    pub fn executes(mut self, executor: Executor) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    pub fn then(mut self, child: CommandBuilder) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+// A command that parsed successfully and can be run
+pub struct ParsedCommand {
+    pub executor: Executor,
+    pub arguments: HashMap<String, ArgumentValue>,
+}
+
+pub struct Dispatcher {
+    nodes: Vec<Node>,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                kind: NodeKind::Root,
+                children: vec![],
+                executor: None,
+                permission: 0,
+            }],
+        }
+    }
+
+    pub fn register(&mut self, command: CommandBuilder) {
+        self.add_node(0, command);
+    }
+
+    fn add_node(&mut self, parent: usize, builder: CommandBuilder) {
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            kind: builder.kind,
+            children: vec![],
+            executor: builder.executor,
+            permission: builder.permission,
+        });
+        // Literals are tried before arguments, so `kick @a` can't be
+        // taken for a player named `@a` or the like
+        let siblings = &self.nodes[parent].children;
+        let position = if matches!(self.nodes[index].kind, NodeKind::Literal(_)) {
+            siblings.iter()
+                .position(|&child| !matches!(self.nodes[child].kind, NodeKind::Literal(_)))
+                .unwrap_or(siblings.len())
+        } else {
+            siblings.len()
+        };
+        self.nodes[parent].children.insert(position, index);
+        for child in builder.children {
+            self.add_node(index, child);
+        }
+    }
+
+    // `command` is without the leading slash
+    pub fn parse(&self, command: &str, permission: u8) -> Result<ParsedCommand> {
+        let mut reader = StringReader::new(command);
+        let mut arguments = HashMap::new();
+        let mut error = None;
+        match self.parse_children(0, &mut reader, permission, &mut arguments, &mut error) {
+            Some(executor) => Ok(ParsedCommand { executor, arguments }),
+            None => Err(error.map(|(_, error)| error)
+                .unwrap_or_else(|| anyhow!("Unknown or incomplete command"))),
+        }
+    }
+
+    // Tries every child in order, backtracking when one doesn't lead to
+    // an executable node. The error that got the furthest is kept
+    fn parse_children(&self, node: usize, reader: &mut StringReader, permission: u8,
+        arguments: &mut HashMap<String, ArgumentValue>, error: &mut Option<(usize, anyhow::Error)>)
+        -> Option<Executor>
+    {
+        let start = reader.cursor();
+        for &child in &self.nodes[node].children {
+            let child_node = &self.nodes[child];
+            if child_node.permission > permission {
+                continue;
+            }
+            reader.set_cursor(start);
+            let parsed = match &child_node.kind {
+                NodeKind::Root => unreachable!(),
+                NodeKind::Literal(name) => {
+                    if reader.read_word() == name {
+                        None
+                    } else {
+                        continue;
+                    }
+                }
+                NodeKind::Argument { name, argument } => match argument.parse(reader) {
+                    Ok(value) => Some((name, value)),
+                    Err(err) => {
+                        keep_furthest(error, reader.cursor(), err);
+                        continue;
+                    }
+                },
+            };
+            if reader.can_read() && reader.peek() != Some(' ') {
+                keep_furthest(error, reader.cursor(),
+                    anyhow!("Expected whitespace to end one argument, but found trailing data"));
+                continue;
+            }
+            if let Some((name, value)) = &parsed {
+                arguments.insert(name.to_string(), value.clone());
+            }
+            if !reader.can_read() {
+                match child_node.executor {
+                    Some(executor) => return Some(executor),
+                    None => keep_furthest(error, reader.cursor(),
+                        anyhow!("Unknown or incomplete command")),
+                }
+            } else {
+                reader.skip();
+                let executor = self.parse_children(child, reader, permission, arguments, error);
+                if executor.is_some() {
+                    return executor;
+                }
+            }
+            if let Some((name, _)) = parsed {
+                arguments.remove(name);
+            }
+        }
+        None
+    }
+
+    // Returns where the suggestions start in `command` and what could be
+    // put there, `command` being without the leading slash
+    pub fn suggest(&self, command: &str, permission: u8, player_names: &[String])
+        -> (usize, Vec<String>)
+    {
+        let mut reader = StringReader::new(command);
+        let mut suggestions = vec![];
+        self.suggest_children(0, &mut reader, permission, player_names, &mut suggestions);
+        let start = suggestions.first().map_or(command.len(), |(start, _)| *start);
+        let mut matches: Vec<String> = suggestions.into_iter()
+            .filter(|(suggestion_start, _)| *suggestion_start == start)
+            .map(|(_, suggestion)| suggestion)
+            .collect();
+        matches.sort();
+        matches.dedup();
+        (start, matches)
+    }
+
+    fn suggest_children(&self, node: usize, reader: &mut StringReader, permission: u8,
+        player_names: &[String], suggestions: &mut Vec<(usize, String)>)
+    {
+        let start = reader.cursor();
+        let typed = reader.remaining();
+        for &child in &self.nodes[node].children {
+            let child_node = &self.nodes[child];
+            if child_node.permission > permission {
+                continue;
+            }
+            reader.set_cursor(start);
+            let complete = match &child_node.kind {
+                NodeKind::Root => unreachable!(),
+                NodeKind::Literal(name) => reader.read_word() == name,
+                NodeKind::Argument { argument, .. } => argument.parse(reader).is_ok(),
+            };
+            if complete && reader.peek() == Some(' ') {
+                reader.skip();
+                self.suggest_children(child, reader, permission, player_names, suggestions);
+                continue;
+            }
+            let candidates = match &child_node.kind {
+                NodeKind::Root => unreachable!(),
+                NodeKind::Literal(name) => vec![name.clone()],
+                NodeKind::Argument { argument, .. } => argument.suggest(player_names),
+            };
+            let lowercase = typed.to_lowercase();
+            suggestions.extend(candidates.into_iter()
+                .filter(|candidate| candidate.to_lowercase().starts_with(&lowercase))
+                .map(|candidate| (start, candidate)));
+        }
+    }
+
+    // The graph sent to clients, only with the nodes they're allowed to use
+    pub fn declare(&self, permission: u8) -> Vec<CommandNode> {
+        let mut nodes = vec![];
+        self.declare_node(0, permission, &mut nodes);
+        nodes
+    }
+
+    fn declare_node(&self, node: usize, permission: u8, nodes: &mut Vec<CommandNode>) -> u32 {
+        let index = nodes.len();
+        nodes.push(CommandNode::Root { children: vec![] });
+        let children = self.nodes[node].children.iter()
+            .filter(|&&child| self.nodes[child].permission <= permission)
+            .map(|&child| self.declare_node(child, permission, nodes))
+            .collect();
+        let executable = self.nodes[node].executor.is_some();
+        nodes[index] = match &self.nodes[node].kind {
+            NodeKind::Root => CommandNode::Root { children },
+            NodeKind::Literal(name) => CommandNode::Literal {
+                name: name.clone(),
+                executable,
+                children,
+            },
+            NodeKind::Argument { name, argument } => {
+                let (parser, properties) = argument.parser();
+                CommandNode::Argument {
+                    name: name.clone(),
+                    executable,
+                    children,
+                    parser: parser.into(),
+                    properties,
+                    ask_server: argument.asks_server(),
+                }
+            }
+        };
+        index as u32
+    }
+}
+
+fn keep_furthest(error: &mut Option<(usize, anyhow::Error)>, cursor: usize, new: anyhow::Error) {
+    if error.as_ref().is_none_or(|(furthest, _)| cursor > *furthest) {
+        *error = Some((cursor, new));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use crate::commands::arguments::ArgumentType;
+    use crate::commands::context::CommandContext;
+    use crate::net::CommandNode;
+    use crate::text::TextComponent;
+    use super::{Dispatcher, argument, literal};
+
+    fn run(_: &mut CommandContext) -> Result<Option<TextComponent>> {
+        Ok(None)
+    }
+
+    fn dispatcher() -> Dispatcher {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.register(literal("kick").requires(3)
+            .then(argument("targets", ArgumentType::Entity { single: false, players_only: true })
+                .executes(run)
+                .then(argument("reason", ArgumentType::GreedyString).executes(run))));
+        dispatcher.register(literal("list").executes(run));
+        dispatcher
+    }
+
+    #[test]
+    fn parse_test() {
+        let dispatcher = dispatcher();
+        assert!(dispatcher.parse("list", 0).is_ok());
+        assert!(dispatcher.parse("list now", 0).is_err());
+        assert!(dispatcher.parse("kick Notch", 0).is_err());
+        let parsed = dispatcher.parse("kick @a Spamming chat", 3).unwrap();
+        assert!(parsed.arguments.contains_key("targets"));
+        assert!(parsed.arguments.contains_key("reason"));
+        assert!(dispatcher.parse("kick", 3).is_err());
+    }
+
+    #[test]
+    fn suggest_test() {
+        let dispatcher = dispatcher();
+        assert_eq!(dispatcher.suggest("l", 0, &[]), (0, vec!["list".to_owned()]));
+        assert_eq!(dispatcher.suggest("k", 0, &[]).1, Vec::<String>::new());
+        let names = vec!["Notch".to_owned(), "jeb_".to_owned()];
+        assert_eq!(dispatcher.suggest("kick no", 4, &names), (5, vec!["Notch".to_owned()]));
+        let names = vec!["Nobody".to_owned(), "Notch".to_owned(), "Nobody".to_owned()];
+        assert_eq!(dispatcher.suggest("kick no", 4, &names).1, vec!["Nobody", "Notch"]);
+    }
+
+    #[test]
+    fn declare_test() {
+        let nodes = dispatcher().declare(0);
+        assert_eq!(nodes.len(), 2);
+        assert!(matches!(&nodes[0], CommandNode::Root { children } if children == &vec![1]));
+        assert_eq!(dispatcher().declare(4).len(), 5);
+    }
+}
@@ -0,0 +1,140 @@
+mod reader;
+mod selector;
+mod arguments;
+mod dispatcher;
+mod context;
+mod builtin;
+mod moderation;
+mod operators;
+mod console;
+
+use std::sync::Mutex;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use legion::*;
+use tokio::sync::oneshot;
+use crate::config::Config;
+use crate::net::{ChatPosition, ClientboundPacket, PlayerConnection};
+use crate::text::{Color, TextComponent};
+use uuid::Uuid;
+use context::CommandContext;
+
+pub use dispatcher::Dispatcher;
+pub use operators::Operators;
+pub use console::{start as start_console, stop as stop_console};
+
+// Who ran a command, which decides its permissions and where the
+// feedback goes
+#[derive(Clone, Copy, Debug)]
+pub enum CommandSource {
+    Console,
+    Rcon,
+    Player(Entity),
+}
+
+// A command waiting to be run in the main loop. If there's a `reply`,
+// the output is sent back through it instead of to the source
+pub struct CommandRequest {
+    pub command: String,
+    pub source: CommandSource,
+    pub reply: Option<oneshot::Sender<String>>,
+}
+
+pub struct CommandQueue {
+    sender: Mutex<Sender<CommandRequest>>,
+    receiver: Mutex<Receiver<CommandRequest>>,
+}
+
+impl CommandQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender: Mutex::new(sender),
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    pub fn sender(&self) -> Sender<CommandRequest> {
+        self.sender.lock().unwrap().clone()
+    }
+
+    // The receiver is in the queue too, so this can't fail
+    pub fn send(&self, source: CommandSource, command: &str) {
+        self.sender.lock().unwrap().send(CommandRequest {
+            command: command.to_owned(),
+            source,
+            reply: None,
+        }).expect("The command queue has no receiver");
+    }
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Vanilla permission levels, from 0 for everyone to 4 for operators
+#[derive(Clone, Copy, Debug)]
+pub struct PermissionLevel(pub u8);
+
+pub fn register(resources: &mut Resources, config: &Config) {
+    let mut dispatcher = Dispatcher::new();
+    builtin::register(&mut dispatcher);
+    moderation::register(&mut dispatcher);
+    resources.insert(dispatcher);
+    resources.insert(Operators::load(&config.data_dir, &config.operators));
+}
+
+pub fn run_pending(world: &mut World, resources: &mut Resources) {
+    let requests: Vec<_> = resources.get::<CommandQueue>().unwrap()
+        .receiver.lock().unwrap()
+        .try_iter().collect();
+    for CommandRequest { command, source, reply } in requests {
+        let permission = match source {
+            CommandSource::Console | CommandSource::Rcon => 4,
+            CommandSource::Player(entity) => match world.entry_ref(entity) {
+                Ok(entry) => entry.get_component::<PermissionLevel>()
+                    .map_or(0, |level| level.0),
+                // The player left before the command could run
+                Err(_) => continue,
+            },
+        };
+        let command = command.trim().trim_start_matches('/');
+        let parsed = resources.get::<Dispatcher>().unwrap().parse(command, permission);
+        let output = parsed.and_then(|parsed| {
+            let mut context = CommandContext::new(world, resources, source, parsed.arguments);
+            (parsed.executor)(&mut context)
+        });
+        let output = match output {
+            Ok(Some(output)) => output,
+            Ok(None) => TextComponent::text(""),
+            Err(err) => TextComponent::text(err.to_string()).color(Color::Red),
+        };
+        match (source, reply) {
+            (_, Some(reply)) => {
+                let _ = reply.send(output.to_plain());
+            }
+            (CommandSource::Player(entity), None) => {
+                if output.to_plain().is_empty() {
+                    continue;
+                }
+                let Ok(entry) = world.entry_ref(entity) else {
+                    continue;
+                };
+                if let Ok(conn) = entry.get_component::<PlayerConnection>() {
+                    conn.send(ClientboundPacket::ChatMessage {
+                        message: output,
+                        position: ChatPosition::System,
+                        sender: Uuid::nil(),
+                    });
+                }
+            }
+            (_, None) => {
+                let output = output.to_plain();
+                if !output.is_empty() {
+                    log!("{}", output);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::net::IpAddr;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, FixedOffset};
+use legion::*;
+use uuid::Uuid;
+use crate::access::{AccessLists, BanInfo, ban_message, expiry_after, format_date};
+use crate::entities::{Address, Name};
+use crate::net::{ClientboundPacket, PlayerConnection, Server, offline_uuid};
+use crate::text::TextComponent;
+use super::{Operators, PermissionLevel};
+use super::arguments::ArgumentType;
+use super::context::CommandContext;
+use super::dispatcher::{Dispatcher, argument, literal};
+
+pub fn register(dispatcher: &mut Dispatcher) {
+    let reason = || argument("reason", ArgumentType::GreedyString);
+    // `/ban <target> [duration] [reason]`, a reason that doesn't start
+    // with a duration is taken as one
+    let duration = || argument("duration", ArgumentType::Duration);
+    dispatcher.register(literal("ban").requires(3)
+        .then(argument("target", ArgumentType::GameProfile)
+            .executes(ban)
+            .then(duration().executes(ban).then(reason().executes(ban)))
+            .then(reason().executes(ban))));
+    dispatcher.register(literal("ban-ip").requires(3)
+        .then(argument("target", ArgumentType::Word)
+            .executes(ban_ip)
+            .then(duration().executes(ban_ip).then(reason().executes(ban_ip)))
+            .then(reason().executes(ban_ip))));
+    dispatcher.register(literal("pardon").requires(3)
+        .then(argument("target", ArgumentType::Word).executes(pardon)));
+    dispatcher.register(literal("pardon-ip").requires(3)
+        .then(argument("target", ArgumentType::Word).executes(pardon_ip)));
+    dispatcher.register(literal("banlist").requires(3)
+        .executes(|ctx| list_bans(ctx, true, true))
+        .then(literal("players").executes(|ctx| list_bans(ctx, true, false)))
+        .then(literal("ips").executes(|ctx| list_bans(ctx, false, true))));
+    dispatcher.register(literal("op").requires(3)
+        .then(argument("target", ArgumentType::GameProfile).executes(op)));
+    dispatcher.register(literal("deop").requires(3)
+        .then(argument("target", ArgumentType::GameProfile).executes(deop)));
+    dispatcher.register(literal("whitelist").requires(3)
+        .then(literal("on").executes(whitelist_on))
+        .then(literal("off").executes(whitelist_off))
+        .then(literal("list").executes(whitelist_list))
+        .then(literal("reload").executes(reload))
+        .then(literal("add")
+            .then(argument("target", ArgumentType::GameProfile).executes(whitelist_add)))
+        .then(literal("remove")
+            .then(argument("target", ArgumentType::Word).executes(whitelist_remove))));
+}
+
+fn with_access<T>(ctx: &CommandContext, action: impl FnOnce(&mut AccessLists) -> T) -> T {
+    let access = ctx.resources.get::<Server>().unwrap().access();
+    let mut access = access.write().unwrap();
+    action(&mut access)
+}
+
+fn reason(ctx: &CommandContext) -> Result<Option<String>> {
+    if ctx.has("reason") {
+        Ok(Some(ctx.get_string("reason")?.to_owned()))
+    } else {
+        Ok(None)
+    }
+}
+
+// Bans without a duration are forever
+fn expires(ctx: &CommandContext) -> Result<Option<DateTime<FixedOffset>>> {
+    if !ctx.has("duration") {
+        return Ok(None);
+    }
+    expiry_after(ctx.get_duration("duration")?)
+        .map(Some)
+        .ok_or_else(|| anyhow!("That duration is too long"))
+}
+
+// How the ban is described in the command's feedback
+fn describe(info: &BanInfo) -> String {
+    match &info.expires {
+        Some(expires) => format!("{} (until {})", info.reason, format_date(expires)),
+        None => info.reason.clone(),
+    }
+}
+
+fn online_players(world: &World) -> Vec<(Entity, Uuid, String, IpAddr)> {
+    <(Entity, &Uuid, &Name, &Address)>::query().iter(world)
+        .map(|(entity, uuid, name, address)| (*entity, *uuid, name.0.clone(), address.0))
+        .collect()
+}
+
+// Players who never joined don't have a known UUID, so they get the
+// offline one. Bans also match names, so this works in online mode too
+fn find_profile(ctx: &CommandContext, name: &str) -> (Uuid, String) {
+    online_players(ctx.world).into_iter()
+        .find(|(_, _, player, _)| player.eq_ignore_ascii_case(name))
+        .map(|(_, uuid, player, _)| (uuid, player))
+        .unwrap_or_else(|| (offline_uuid(name), name.to_owned()))
+}
+
+fn ban(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let (uuid, name) = find_profile(ctx, ctx.get_string("target")?);
+    let info = BanInfo::new(&ctx.source_name(), reason(ctx)?.as_deref(), expires(ctx)?);
+    if !with_access(ctx, |access| access.ban_player(uuid, &name, info.clone())) {
+        return Err(anyhow!("Nothing changed. The player is already banned"));
+    }
+    let message = ban_message("multiplayer.disconnect.banned.reason", &info);
+    for (entity, ..) in online_players(ctx.world).into_iter().filter(|player| player.1 == uuid) {
+        ctx.kick(entity, message.clone())?;
+    }
+    Ok(Some(format!("Banned {}: {}", name, describe(&info)).into()))
+}
+
+fn ban_ip(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let target = ctx.get_string("target")?;
+    let ip = match target.parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => online_players(ctx.world).into_iter()
+            .find(|(_, _, name, _)| name.eq_ignore_ascii_case(target))
+            .map(|(.., address)| address)
+            .ok_or_else(|| anyhow!("Invalid IP address or unknown player"))?,
+    };
+    let info = BanInfo::new(&ctx.source_name(), reason(ctx)?.as_deref(), expires(ctx)?);
+    if !with_access(ctx, |access| access.ban_ip(ip, info.clone())) {
+        return Err(anyhow!("Nothing changed. That IP is already banned"));
+    }
+    let message = ban_message("multiplayer.disconnect.banned_ip.reason", &info);
+    let mut kicked = vec![];
+    for (entity, ..) in online_players(ctx.world).into_iter().filter(|player| player.3 == ip) {
+        kicked.push(ctx.kick(entity, message.clone())?);
+    }
+    Ok(Some(format!("Banned IP {}: {}, this matched {} players: {}",
+        ip, describe(&info), kicked.len(), kicked.join(", ")).into()))
+}
+
+fn pardon(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let name = ctx.get_string("target")?;
+    if !with_access(ctx, |access| access.pardon_player(name)) {
+        return Err(anyhow!("Nothing changed. The player isn't banned"));
+    }
+    Ok(Some(format!("Unbanned {}", name).into()))
+}
+
+fn pardon_ip(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let ip: IpAddr = ctx.get_string("target")?.parse()
+        .map_err(|_| anyhow!("Invalid IP address"))?;
+    if !with_access(ctx, |access| access.pardon_ip(ip)) {
+        return Err(anyhow!("Nothing changed. That IP isn't banned"));
+    }
+    Ok(Some(format!("Unbanned IP {}", ip).into()))
+}
+
+fn list_bans(ctx: &mut CommandContext, players: bool, ips: bool)
+    -> Result<Option<TextComponent>>
+{
+    let lines = with_access(ctx, |access| {
+        access.remove_expired();
+        let players = access.banned_players().iter()
+            .filter(|_| players)
+            .map(|ban| (ban.name.clone(), &ban.info));
+        let ips = access.banned_ips().iter()
+            .filter(|_| ips)
+            .map(|ban| (ban.ip.to_string(), &ban.info));
+        players.chain(ips)
+            .map(|(target, info)| format!("{} was banned by {}: {}",
+                target, info.source, describe(info)))
+            .collect::<Vec<_>>()
+    });
+    if lines.is_empty() {
+        return Ok(Some("There are no bans".into()));
+    }
+    Ok(Some(format!("There are {} ban(s):\n{}", lines.len(), lines.join("\n")).into()))
+}
+
+fn op(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let (uuid, name) = find_profile(ctx, ctx.get_string("target")?);
+    if !ctx.resources.get_mut::<Operators>().unwrap().add(uuid, &name) {
+        return Err(anyhow!("Nothing changed. The player already is an operator"));
+    }
+    update_permission(ctx, uuid, &name);
+    Ok(Some(format!("Made {} a server operator", name).into()))
+}
+
+fn deop(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let (uuid, name) = find_profile(ctx, ctx.get_string("target")?);
+    if !ctx.resources.get_mut::<Operators>().unwrap().remove(uuid, &name) {
+        return Err(anyhow!("Nothing changed. The player is not an operator"));
+    }
+    update_permission(ctx, uuid, &name);
+    Ok(Some(format!("Made {} no longer a server operator", name).into()))
+}
+
+// The commands an online player can use change with their permission level
+fn update_permission(ctx: &mut CommandContext, uuid: Uuid, name: &str) {
+    let level = ctx.resources.get::<Operators>().unwrap().level(uuid, name);
+    let commands = ctx.resources.get::<Dispatcher>().unwrap().declare(level.0);
+    let online = online_players(ctx.world).into_iter().find(|player| player.1 == uuid);
+    if let Some((entity, ..)) = online {
+        let mut entry = ctx.world.entry_mut(entity).unwrap();
+        if let Ok(permission) = entry.get_component_mut::<PermissionLevel>() {
+            *permission = level;
+        }
+        if let Ok(conn) = entry.get_component::<PlayerConnection>() {
+            conn.send(ClientboundPacket::DeclareCommands(commands));
+        }
+    }
+}
+
+fn whitelist_on(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    if with_access(ctx, |access| access.is_whitelist_enabled()) {
+        return Err(anyhow!("Whitelist is already turned on"));
+    }
+    with_access(ctx, |access| access.set_whitelist_enabled(true));
+    Ok(Some("Whitelist is now turned on".into()))
+}
+
+fn whitelist_off(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    if !with_access(ctx, |access| access.is_whitelist_enabled()) {
+        return Err(anyhow!("Whitelist is already turned off"));
+    }
+    with_access(ctx, |access| access.set_whitelist_enabled(false));
+    Ok(Some("Whitelist is now turned off".into()))
+}
+
+fn whitelist_list(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let names: Vec<String> = with_access(ctx, |access| {
+        access.whitelist().iter().map(|entry| entry.name.clone()).collect()
+    });
+    if names.is_empty() {
+        return Ok(Some("There are no whitelisted players".into()));
+    }
+    Ok(Some(format!("There are {} whitelisted players: {}",
+        names.len(), names.join(", ")).into()))
+}
+
+fn whitelist_add(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let (uuid, name) = find_profile(ctx, ctx.get_string("target")?);
+    if !with_access(ctx, |access| access.whitelist_add(uuid, &name)) {
+        return Err(anyhow!("Player is already whitelisted"));
+    }
+    Ok(Some(format!("Added {} to the whitelist", name).into()))
+}
+
+fn whitelist_remove(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    let name = ctx.get_string("target")?;
+    if !with_access(ctx, |access| access.whitelist_remove(name)) {
+        return Err(anyhow!("Player is not whitelisted"));
+    }
+    Ok(Some(format!("Removed {} from the whitelist", name).into()))
+}
+
+fn reload(ctx: &mut CommandContext) -> Result<Option<TextComponent>> {
+    with_access(ctx, |access| access.reload());
+    Ok(Some("Reloaded the whitelist and ban lists".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::Dispatcher;
+    use super::register;
+
+    #[test]
+    fn ban_duration_test() {
+        let mut dispatcher = Dispatcher::new();
+        register(&mut dispatcher);
+        let parsed = dispatcher.parse("ban Notch 7d Griefing", 3).unwrap();
+        assert!(parsed.arguments.contains_key("duration"));
+        assert!(parsed.arguments.contains_key("reason"));
+        let parsed = dispatcher.parse("ban Notch Griefing for 7d", 3).unwrap();
+        assert!(!parsed.arguments.contains_key("duration"));
+        assert!(parsed.arguments.contains_key("reason"));
+        assert!(dispatcher.parse("ban-ip 127.0.0.1 12h", 3).unwrap()
+            .arguments.contains_key("duration"));
+    }
+}
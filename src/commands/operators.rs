@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::access::{is_same_player, load_list, save_list};
+use crate::net::offline_uuid;
+use super::PermissionLevel;
+
+const OPS_PATH: &str = "ops.json";
+const OPERATOR_LEVEL: u8 = 4;
+
+// Same format as vanilla's ops.json
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OperatorEntry {
+    uuid: Uuid,
+    name: String,
+    level: u8,
+    #[serde(default)]
+    bypasses_player_limit: bool,
+}
+
+// The operators in ops.json. Every change is written to disk right away
+pub struct Operators {
+    path: PathBuf,
+    entries: Vec<OperatorEntry>,
+}
+
+impl Operators {
+    // The ones in the config are added to ops.json if they aren't there yet
+    pub fn load(dir: &Path, config_names: &[String]) -> Self {
+        let path = dir.join(OPS_PATH);
+        let mut operators = Self {
+            entries: load_list(&path),
+            path,
+        };
+        for name in config_names {
+            operators.add(offline_uuid(name), name);
+        }
+        operators
+    }
+
+    pub fn level(&self, uuid: Uuid, name: &str) -> PermissionLevel {
+        PermissionLevel(self.find(uuid, name).map_or(0, |entry| entry.level))
+    }
+
+    // Returns false if the player already was an operator
+    pub fn add(&mut self, uuid: Uuid, name: &str) -> bool {
+        if self.find(uuid, name).is_some() {
+            return false;
+        }
+        self.entries.push(OperatorEntry {
+            uuid,
+            name: name.to_owned(),
+            level: OPERATOR_LEVEL,
+            bypasses_player_limit: false,
+        });
+        save_list(&self.path, &self.entries);
+        true
+    }
+
+    // Returns false if the player wasn't an operator
+    pub fn remove(&mut self, uuid: Uuid, name: &str) -> bool {
+        let count = self.entries.len();
+        self.entries.retain(|entry| !matches(entry, uuid, name));
+        if count == self.entries.len() {
+            return false;
+        }
+        save_list(&self.path, &self.entries);
+        true
+    }
+
+    fn find(&self, uuid: Uuid, name: &str) -> Option<&OperatorEntry> {
+        self.entries.iter().find(|entry| matches(entry, uuid, name))
+    }
+}
+
+// Operators from the config only have a guessed UUID, so their names count
+fn matches(entry: &OperatorEntry, uuid: Uuid, name: &str) -> bool {
+    is_same_player(entry.uuid, &entry.name, uuid, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use super::OperatorEntry;
+
+    #[test]
+    fn format_test() {
+        let json = r#"[{"uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5", "name": "Notch",
+            "level": 4, "bypassesPlayerLimit": false}]"#;
+        let entries: Vec<OperatorEntry> = serde_json::from_str(json).unwrap();
+        assert_eq!(entries[0].uuid,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap());
+        assert_eq!(entries[0].level, 4);
+        let saved = serde_json::to_string(&entries).unwrap();
+        assert!(saved.contains(r#""bypassesPlayerLimit":false"#));
+    }
+}
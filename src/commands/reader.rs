@@ -0,0 +1,83 @@
+use anyhow::{Result, anyhow};
+
+// Cursor over a command line, following Brigadier's StringReader
+#[derive(Clone)]
+pub struct StringReader<'a> {
+    input: &'a str,
+    cursor: usize,
+}
+
+impl<'a> StringReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    pub fn remaining(&self) -> &'a str {
+        &self.input[self.cursor..]
+    }
+
+    pub fn can_read(&self) -> bool {
+        self.cursor < self.input.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    pub fn skip(&mut self) {
+        if let Some(c) = self.peek() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn expect(&mut self, expected: char) -> Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.skip();
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected '{}' at position {}", expected, self.cursor)),
+        }
+    }
+
+    // Reads until the next space, or the end of the input
+    pub fn read_word(&mut self) -> &'a str {
+        let start = self.cursor;
+        let length = self.remaining().find(' ').unwrap_or(self.remaining().len());
+        self.cursor += length;
+        &self.input[start..self.cursor]
+    }
+
+    // Reads while `allowed` matches, like the unquoted strings of Brigadier
+    pub fn read_while(&mut self, allowed: impl Fn(char) -> bool) -> &'a str {
+        let start = self.cursor;
+        let length = self.remaining().find(|c| !allowed(c))
+            .unwrap_or(self.remaining().len());
+        self.cursor += length;
+        &self.input[start..self.cursor]
+    }
+
+    pub fn read_rest(&mut self) -> &'a str {
+        let rest = self.remaining();
+        self.cursor = self.input.len();
+        rest
+    }
+
+    pub fn read_int(&mut self) -> Result<i32> {
+        let number = self.read_while(|c| c.is_ascii_digit() || c == '-');
+        number.parse().map_err(|_| anyhow!("Expected integer, got '{}'", number))
+    }
+
+    pub fn read_double(&mut self) -> Result<f64> {
+        let number = self.read_while(|c| c.is_ascii_digit() || c == '-' || c == '.');
+        number.parse().map_err(|_| anyhow!("Expected number, got '{}'", number))
+    }
+}
@@ -0,0 +1,185 @@
+use anyhow::{Result, anyhow};
+use legion::*;
+use nalgebra::Vector3;
+use rand::seq::SliceRandom;
+use uuid::Uuid;
+use crate::entities::{Name, Position};
+use crate::net::PlayerConnection;
+use super::reader::StringReader;
+
+pub const SELECTORS: [&str; 5] = ["@a", "@e", "@p", "@r", "@s"];
+
+#[derive(Clone, Debug)]
+pub enum EntitySelector {
+    Name(String),
+    Uuid(Uuid),
+    Selector {
+        variable: char,
+        limit: Option<usize>,
+        name: Option<(String, bool)>,
+        distance: (Option<f64>, Option<f64>),
+    },
+}
+
+impl EntitySelector {
+    pub fn parse(reader: &mut StringReader) -> Result<Self> {
+        if reader.peek() != Some('@') {
+            let word = reader.read_while(|c| c != ' ');
+            if word.is_empty() {
+                return Err(anyhow!("Expected a player name or selector"));
+            }
+            return Ok(match Uuid::parse_str(word) {
+                Ok(uuid) if word.contains('-') => Self::Uuid(uuid),
+                _ => Self::Name(word.to_owned()),
+            });
+        }
+        reader.skip();
+        let variable = match reader.peek() {
+            Some(c @ ('a' | 'e' | 'p' | 'r' | 's')) => c,
+            _ => return Err(anyhow!("Unknown selector type")),
+        };
+        reader.skip();
+        let mut limit = None;
+        let mut name = None;
+        let mut distance = (None, None);
+        if reader.peek() == Some('[') {
+            reader.skip();
+            while reader.peek() != Some(']') {
+                let key = reader.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                reader.expect('=')?;
+                match key {
+                    "limit" => {
+                        let value = reader.read_int()?;
+                        if value < 1 {
+                            return Err(anyhow!("Limit must be at least 1"));
+                        }
+                        limit = Some(value as usize);
+                    }
+                    "name" => {
+                        let inverted = reader.peek() == Some('!');
+                        if inverted {
+                            reader.skip();
+                        }
+                        let value = reader.read_while(|c| c != ',' && c != ']');
+                        name = Some((value.to_owned(), inverted));
+                    }
+                    "distance" => distance = read_range(reader)?,
+                    key => return Err(anyhow!("Unknown option '{}'", key)),
+                }
+                if reader.peek() == Some(',') {
+                    reader.skip();
+                } else if reader.peek() != Some(']') {
+                    return Err(anyhow!("Expected end of options"));
+                }
+            }
+            reader.skip();
+        }
+        Ok(Self::Selector { variable, limit, name, distance })
+    }
+
+    pub fn is_single(&self) -> bool {
+        match self {
+            Self::Name(_) | Self::Uuid(_) => true,
+            Self::Selector { variable: 'p' | 'r' | 's', .. } => true,
+            Self::Selector { limit, .. } => *limit == Some(1),
+        }
+    }
+
+    // Only players exist for now, so this only matters for vanilla parity
+    pub fn allows_non_players(&self) -> bool {
+        matches!(self, Self::Selector { variable: 'e', .. })
+    }
+
+    // `origin` and `sender` are where the command was run from and by whom
+    pub fn resolve(&self, world: &World, origin: Vector3<f64>, sender: Option<Entity>)
+        -> Vec<Entity>
+    {
+        let mut query = <(Entity, &Name, &Uuid, &Position)>::query()
+            .filter(component::<PlayerConnection>());
+        let players = query.iter(world)
+            .map(|(entity, name, uuid, pos)| (*entity, name.0.as_str(), *uuid, pos.0));
+        match self {
+            Self::Name(target) => players
+                .filter(|(_, name, _, _)| name.eq_ignore_ascii_case(target))
+                .map(|(entity, ..)| entity)
+                .collect(),
+            Self::Uuid(target) => players
+                .filter(|(_, _, uuid, _)| uuid == target)
+                .map(|(entity, ..)| entity)
+                .collect(),
+            Self::Selector { variable, limit, name, distance } => {
+                let mut matching: Vec<(Entity, f64)> = players
+                    .filter(|(entity, ..)| *variable != 's' || Some(*entity) == sender)
+                    .filter(|(_, player, ..)| match name {
+                        Some((name, inverted)) => (player == name) != *inverted,
+                        None => true,
+                    })
+                    .map(|(entity, _, _, pos)| (entity, (pos - origin).norm()))
+                    .filter(|(_, dist)| {
+                        distance.0.is_none_or(|min| *dist >= min)
+                            && distance.1.is_none_or(|max| *dist <= max)
+                    })
+                    .collect();
+                let default_limit = match variable {
+                    'p' => {
+                        matching.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                        1
+                    }
+                    'r' => {
+                        matching.shuffle(&mut rand::thread_rng());
+                        1
+                    }
+                    _ => usize::MAX,
+                };
+                matching.into_iter()
+                    .take(limit.unwrap_or(default_limit))
+                    .map(|(entity, _)| entity)
+                    .collect()
+            }
+        }
+    }
+}
+
+// Vanilla ranges: `5`, `..5`, `5..` or `1..5`
+fn read_range(reader: &mut StringReader) -> Result<(Option<f64>, Option<f64>)> {
+    let text = reader.read_while(|c| c != ',' && c != ']');
+    let parse = |value: &str| -> Result<Option<f64>> {
+        if value.is_empty() {
+            Ok(None)
+        } else {
+            value.parse().map(Some).map_err(|_| anyhow!("Invalid range '{}'", text))
+        }
+    };
+    match text.split_once("..") {
+        Some((min, max)) => Ok((parse(min)?, parse(max)?)),
+        None => {
+            let exact = parse(text)?;
+            Ok((exact, exact))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::reader::StringReader;
+    use super::EntitySelector;
+
+    #[test]
+    fn parse_test() {
+        let selector = EntitySelector::parse(&mut StringReader::new("@a[limit=1,distance=..10]"))
+            .unwrap();
+        assert!(selector.is_single());
+        match selector {
+            EntitySelector::Selector { variable, limit, distance, .. } => {
+                assert_eq!(variable, 'a');
+                assert_eq!(limit, Some(1));
+                assert_eq!(distance, (None, Some(10.0)));
+            }
+            _ => panic!("Expected a selector"),
+        }
+        assert!(!EntitySelector::parse(&mut StringReader::new("@e")).unwrap().is_single());
+        assert!(matches!(EntitySelector::parse(&mut StringReader::new("Notch")).unwrap(),
+            EntitySelector::Name(name) if name == "Notch"));
+        assert!(EntitySelector::parse(&mut StringReader::new("@x")).is_err());
+    }
+}
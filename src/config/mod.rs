@@ -1,17 +1,61 @@
 use tokio::fs::File;
 use yaml_rust::YamlLoader;
+use std::fs;
 use std::io::ErrorKind::NotFound;
+use std::path::{Path, PathBuf};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use anyhow::Result;
 use thiserror::Error;
+use crate::util::spawn_write;
 
 const CONFIG_PATH: &str = "config.yml";
 const DEFAULT_CONFIG: &str = include_str!("default.yml");
+// Used when an older config.yml doesn't have the key
+const DEFAULT_MAX_PLAYERS: usize = 100;
+const DEFAULT_VIEW_DISTANCE: u8 = 8;
+const DEFAULT_COMPRESSION_THRESHOLD: u32 = 256;
+// 64MB
+const DEFAULT_OUTBOUND_QUEUE_LIMIT: usize = 64 * 1024 * 1024;
+const DEFAULT_PACKET_RATE_LIMIT: u32 = 500;
+const DEFAULT_QUERY_PORT: u16 = 25565;
+const DEFAULT_RCON_PORT: u16 = 25575;
+const DEFAULT_SESSION_SERVER: &str = "https://sessionserver.mojang.com/session/minecraft/hasJoined";
+const DEFAULT_CONNECTION_THROTTLE: u64 = 4000;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub port: u16,
     pub motd: String,
+    pub max_players: usize,
+    pub view_distance: u8,
+    pub hide_player_sample: bool,
+    pub compression_threshold: Option<u32>,
+    pub online_mode: bool,
+    pub session_server: String,
+    pub forwarding: Forwarding,
+    pub proxy_protocol: bool,
+    pub outbound_queue_limit: usize,
+    pub packet_rate_limit: Option<u32>,
+    pub enable_query: bool,
+    pub query_port: u16,
+    pub enable_rcon: bool,
+    pub rcon_port: u16,
+    pub rcon_password: String,
+    pub operators: Vec<String>,
+    pub whitelist: bool,
+    pub connection_throttle: Option<u64>,
+    // Where the world, the player lists and the server icon are. Not in
+    // config.yml, it's the working directory unless tests change it
+    pub data_dir: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub enum Forwarding {
+    None,
+    BungeeCord,
+    Velocity {
+        secret: String,
+    },
 }
 
 impl Config {
@@ -37,10 +81,118 @@ impl Config {
                 .ok_or(MissingField("port"))?,
             motd: yaml["motd"].as_str().map(|motd| String::from(motd))
                 .ok_or(MissingField("motd"))?,
+            max_players: yaml["max_players"].as_i64()
+                .map(|max| max as usize)
+                .unwrap_or(DEFAULT_MAX_PLAYERS),
+            // The client only renders 2 to 32 chunks
+            view_distance: yaml["view_distance"].as_i64()
+                .map(|distance| distance.clamp(2, 32) as u8)
+                .unwrap_or(DEFAULT_VIEW_DISTANCE),
+            hide_player_sample: yaml["hide_player_sample"].as_bool().unwrap_or(false),
+            compression_threshold: yaml["compression_threshold"].as_i64()
+                .map(|threshold| if threshold < 0 { 
+                    None 
+                } else { 
+                    Some(threshold as u32) 
+                })
+                .unwrap_or(Some(DEFAULT_COMPRESSION_THRESHOLD)),
+            online_mode: yaml["online_mode"].as_bool().unwrap_or(false),
+            session_server: yaml["session_server"].as_str()
+                .unwrap_or(DEFAULT_SESSION_SERVER)
+                .to_owned(),
+            forwarding: match yaml["forwarding"].as_str() {
+                None | Some("none") => Forwarding::None,
+                Some("bungeecord") => Forwarding::BungeeCord,
+                // With an empty key anyone could sign the forwarded data
+                Some("velocity") => Forwarding::Velocity {
+                    secret: yaml["velocity_secret"].as_str()
+                        .filter(|secret| !secret.is_empty())
+                        .map(String::from)
+                        .ok_or(MissingField("velocity_secret"))?,
+                },
+                _ => return Err(MissingField("forwarding").into()),
+            },
+            proxy_protocol: yaml["proxy_protocol"].as_bool().unwrap_or(false),
+            outbound_queue_limit: yaml["outbound_queue_limit"].as_i64()
+                .map(|limit| limit as usize)
+                .unwrap_or(DEFAULT_OUTBOUND_QUEUE_LIMIT),
+            packet_rate_limit: yaml["packet_rate_limit"].as_i64()
+                .map(|limit| if limit < 0 {
+                    None
+                } else {
+                    Some(limit as u32)
+                })
+                .unwrap_or(Some(DEFAULT_PACKET_RATE_LIMIT)),
+            enable_query: yaml["enable_query"].as_bool().unwrap_or(false),
+            query_port: yaml["query_port"].as_i64().map(|port| port as u16)
+                .unwrap_or(DEFAULT_QUERY_PORT),
+            enable_rcon: yaml["enable_rcon"].as_bool().unwrap_or(false),
+            rcon_port: yaml["rcon_port"].as_i64().map(|port| port as u16)
+                .unwrap_or(DEFAULT_RCON_PORT),
+            rcon_password: yaml["rcon_password"].as_str()
+                .unwrap_or("")
+                .to_owned(),
+            operators: yaml["operators"].as_vec()
+                .map(|operators| operators.iter()
+                    .filter_map(|name| name.as_str())
+                    .map(String::from)
+                    .collect())
+                .unwrap_or_default(),
+            whitelist: yaml["whitelist"].as_bool().unwrap_or(false),
+            connection_throttle: yaml["connection_throttle"].as_i64()
+                .map(|millis| if millis < 0 {
+                    None
+                } else {
+                    Some(millis as u64)
+                })
+                .unwrap_or(Some(DEFAULT_CONNECTION_THROTTLE)),
+            data_dir: PathBuf::from("."),
         })
     }
 }
 
+impl Default for Config {
+    fn default() -> Self {
+        Self::from_str(DEFAULT_CONFIG).expect("Invalid default config")
+    }
+}
+
+// Changes the whitelist key of the config.yml in `dir`, leaving the rest
+// of it alone
+pub fn save_whitelist(dir: &Path, enabled: bool) {
+    let path = dir.join(CONFIG_PATH);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == NotFound => DEFAULT_CONFIG.to_owned(),
+        Err(err) => {
+            log!("Couldn't save the whitelist setting: {}", err);
+            return;
+        }
+    };
+    let content = set_key(&content, "whitelist", &enabled.to_string());
+    spawn_write(path, content.into_bytes());
+}
+
+// Replaces the line of a top level key, or adds one at the end
+fn set_key(content: &str, key: &str, value: &str) -> String {
+    let line_ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let prefix = format!("{}:", key);
+    let line = format!("{} {}", prefix, value);
+    let mut found = false;
+    let mut lines: Vec<&str> = content.lines()
+        .map(|current| if current.starts_with(&prefix) {
+            found = true;
+            line.as_str()
+        } else {
+            current
+        })
+        .collect();
+    if !found {
+        lines.push(&line);
+    }
+    lines.join(line_ending) + line_ending
+}
+
 async fn create_default_file() -> Result<()> {
     let mut file = File::create(CONFIG_PATH).await?;
     file.write_all(DEFAULT_CONFIG.as_bytes()).await?;
@@ -49,4 +201,27 @@ async fn create_default_file() -> Result<()> {
 
 #[derive(Error, Debug)]
 #[error("missing or invalid field: {0}")]
-struct MissingField(&'static str);
\ No newline at end of file
+struct MissingField(&'static str);
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Forwarding, set_key};
+
+    #[test]
+    fn set_key_test() {
+        let content = "port: 25565\r\n# Comment\r\nwhitelist: false\r\n";
+        assert_eq!(set_key(content, "whitelist", "true"),
+            "port: 25565\r\n# Comment\r\nwhitelist: true\r\n");
+        assert_eq!(set_key("port: 25565\n", "whitelist", "true"),
+            "port: 25565\nwhitelist: true\n");
+    }
+
+    #[test]
+    fn velocity_secret_test() {
+        let config = "port: 25565\nmotd: A server\nforwarding: velocity\n";
+        assert!(Config::from_str(config).is_err());
+        assert!(Config::from_str(&format!("{}velocity_secret: \"\"\n", config)).is_err());
+        let config = Config::from_str(&format!("{}velocity_secret: abc\n", config)).unwrap();
+        assert!(matches!(config.forwarding, Forwarding::Velocity { secret } if secret == "abc"));
+    }
+}
\ No newline at end of file
@@ -1,3 +1,4 @@
+use std::net::IpAddr;
 use nalgebra::Vector3;
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -6,5 +7,16 @@ pub struct Position(pub Vector3<f64>);
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Rotation(pub f32, pub f32);
 
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OnGround(pub bool);
+
+// In blocks per tick
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Velocity(pub Vector3<f64>);
+
 #[derive(Clone, Debug, Default)]
-pub struct Name(pub String);
\ No newline at end of file
+pub struct Name(pub String);
+
+// Where a player connected from
+#[derive(Clone, Copy, Debug)]
+pub struct Address(pub IpAddr);
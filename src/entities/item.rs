@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use legion::*;
+use systems::CommandBuffer;
+use world::SubWorld;
+use nalgebra::{Vector3, vector};
+use rand::Rng;
+use uuid::Uuid;
+use crate::buckets::EntityTracker;
+use crate::buckets::events::{EntityEvent, EntityEventData};
+use crate::chunks::{ChunkCoords, World as ChunkWorld};
+use crate::items::ItemStack;
+use super::{EntityId, EntityIdGenerator, Position, Velocity};
+
+const GRAVITY: f64 = 0.04;
+const DRAG: f64 = 0.98;
+// Of most blocks, slowing items that slide on them
+const GROUND_FRICTION: f64 = 0.6;
+const HALF_WIDTH: f64 = 0.125;
+const HEIGHT: f64 = 0.25;
+// 5 minutes
+const DESPAWN_AGE: u32 = 6000;
+const VOID_Y: f64 = -64.0;
+// Slower than this it stops, so resting items don't keep sending moves
+const SETTLE_SPEED: f64 = 1e-3;
+const BLOCK_PICKUP_DELAY: u32 = 10;
+const THROWN_PICKUP_DELAY: u32 = 40;
+const EYE_HEIGHT: f64 = 1.62;
+// Items merge when their boxes grown by this much horizontally touch
+const MERGE_DISTANCE: f64 = 0.5;
+
+// An item stack lying in the world
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    // Ticks until players can pick it up
+    pickup_delay: u32,
+    age: u32,
+}
+
+impl DroppedItem {
+    fn new(stack: ItemStack, pickup_delay: u32) -> Self {
+        Self {
+            stack,
+            pickup_delay,
+            age: 0,
+        }
+    }
+
+    // Too old or fallen into the void
+    fn should_despawn(&self, pos: &Vector3<f64>) -> bool {
+        self.age >= DESPAWN_AGE || pos.y < VOID_Y
+    }
+
+    pub fn can_pick_up(&self) -> bool {
+        self.pickup_delay == 0
+    }
+
+    // Same item and NBT, and the two fit in one stack
+    fn can_merge(&self, other: &Self) -> bool {
+        self.stack.item.to_numeric() == other.stack.item.to_numeric()
+            && self.stack.nbt == other.stack.nbt
+            && self.stack.count as u32 + other.stack.count as u32
+                <= self.stack.item.max_stack_size() as u32
+    }
+
+    fn absorb(&mut self, other: &Self) {
+        self.stack.count += other.stack.count;
+        self.pickup_delay = self.pickup_delay.max(other.pickup_delay);
+        self.age = self.age.min(other.age);
+    }
+}
+
+// Items dropped while the tick runs, they're added to the world by spawn_items
+pub struct ItemSpawner {
+    pending: Vec<(Position, Velocity, DroppedItem)>,
+}
+
+impl ItemSpawner {
+    pub fn new() -> Self {
+        Self {
+            pending: vec![],
+        }
+    }
+
+    // Somewhere around the middle of the block, popping out of it like vanilla
+    pub fn spawn_at_block(&mut self, block: Vector3<i32>, stack: ItemStack) {
+        let mut rng = rand::thread_rng();
+        let mut offset = || rng.gen_range(0.25..0.75);
+        let pos = block.cast() + vector!(offset(), offset() - HEIGHT / 2.0, offset());
+        let velocity = vector!(rng.gen_range(-0.1..0.1), 0.2, rng.gen_range(-0.1..0.1));
+        self.spawn(pos, velocity, DroppedItem::new(stack, BLOCK_PICKUP_DELAY));
+    }
+
+    // Thrown from the eyes of a player at `pos`, where it's looking
+    pub fn drop_from_player(&mut self, pos: Vector3<f64>, rotation: (f32, f32), stack: ItemStack) {
+        self.throw(pos, rotation, stack, THROWN_PICKUP_DELAY);
+    }
+
+    // What didn't fit in the inventory of a player given items, they
+    // can pick it up right away
+    pub fn give_to_player(&mut self, pos: Vector3<f64>, rotation: (f32, f32), stack: ItemStack) {
+        self.throw(pos, rotation, stack, 0);
+    }
+
+    fn throw(&mut self, pos: Vector3<f64>, rotation: (f32, f32), stack: ItemStack,
+        pickup_delay: u32)
+    {
+        let mut rng = rand::thread_rng();
+        let (yaw, pitch) = ((rotation.0 as f64).to_radians(), (rotation.1 as f64).to_radians());
+        let spread_angle = rng.gen::<f64>() * PI * 2.0;
+        let spread = rng.gen::<f64>() * 0.02;
+        let velocity = vector!(
+            -yaw.sin() * pitch.cos() * 0.3 + spread_angle.cos() * spread,
+            -pitch.sin() * 0.3 + 0.1 + (rng.gen::<f64>() - rng.gen::<f64>()) * 0.1,
+            yaw.cos() * pitch.cos() * 0.3 + spread_angle.sin() * spread
+        );
+        let pos = pos + vector!(0.0, EYE_HEIGHT - 0.3, 0.0);
+        self.spawn(pos, velocity, DroppedItem::new(stack, pickup_delay));
+    }
+
+    fn spawn(&mut self, pos: Vector3<f64>, velocity: Vector3<f64>, item: DroppedItem) {
+        self.pending.push((Position(pos), Velocity(velocity), item));
+    }
+}
+
+#[system]
+pub fn spawn_items(cmd: &mut CommandBuffer, #[resource] spawner: &mut ItemSpawner,
+    #[resource] entity_id_gen: &EntityIdGenerator, #[resource] tracker: &EntityTracker)
+{
+    for (position, velocity, item) in spawner.pending.drain(..) {
+        let id = entity_id_gen.get_new();
+        let pos = position.0;
+        let entity = cmd.push((id, Uuid::new_v4(), position, velocity, item));
+        tracker.add(id.0, entity, &pos);
+    }
+}
+
+// Falls, slides and despawns when it gets too old, like vanilla. Items in
+// chunks that aren't loaded are frozen until they are
+#[system]
+#[read_component(EntityId)]
+#[write_component(Position)]
+#[write_component(Velocity)]
+#[write_component(DroppedItem)]
+pub fn move_items(world: &mut SubWorld, cmd: &mut CommandBuffer,
+    #[resource] chunks: &ChunkWorld, #[resource] tracker: &EntityTracker)
+{
+    let mut query = <(Entity, &EntityId, &mut Position, &mut Velocity, &mut DroppedItem)>::query();
+    for (entity, id, position, velocity, item) in query.iter_mut(world) {
+        if !chunks.is_loaded(ChunkCoords::from_pos(&position.0)) {
+            continue;
+        }
+        item.age += 1;
+        item.pickup_delay = item.pickup_delay.saturating_sub(1);
+        if item.should_despawn(&position.0) {
+            cmd.remove(*entity);
+            tracker.remove(id.0, &position.0);
+            continue;
+        }
+        let solid = |pos: &Vector3<i32>| chunks.get_block(pos).material.blocks_motion;
+        let new_position = physics_tick(position.0, &mut velocity.0, &solid);
+        if new_position != position.0 {
+            tracker.move_entity(id.0, *entity, position.0, new_position);
+            tracker.send_event(&new_position, EntityEvent {
+                id: id.0,
+                data: EntityEventData::Move {
+                    delta: new_position - position.0,
+                },
+            });
+            position.0 = new_position;
+        }
+    }
+}
+
+// Where the item is after one tick of falling and sliding among the blocks
+// that are `solid`
+fn physics_tick<F>(pos: Vector3<f64>, velocity: &mut Vector3<f64>, solid: &F) -> Vector3<f64>
+    where F: Fn(&Vector3<i32>) -> bool
+{
+    velocity.y -= GRAVITY;
+    let (new_position, on_ground) = move_with_collisions(pos, velocity, solid);
+    let friction = if on_ground { GROUND_FRICTION * DRAG } else { DRAG };
+    velocity.component_mul_assign(&vector!(friction, DRAG, friction));
+    velocity.apply(|speed| if speed.abs() < SETTLE_SPEED { 0.0 } else { speed });
+    new_position
+}
+
+// Moves along one axis at a time, stopping at the blocks it hits. Fast items
+// move in small steps so they don't go through blocks.
+// Returns the new position and whether it landed on something
+fn move_with_collisions<F>(pos: Vector3<f64>, velocity: &mut Vector3<f64>, solid: &F)
+    -> (Vector3<f64>, bool)
+    where F: Fn(&Vector3<i32>) -> bool
+{
+    // Stuck inside a block, it goes up until it gets out
+    if collides(pos, solid) {
+        *velocity = vector!(0.0, 0.1, 0.0);
+        return (pos + *velocity, false);
+    }
+    let steps = (velocity.abs().max() / HEIGHT).ceil().max(1.0);
+    let mut step = *velocity / steps;
+    let mut pos = pos;
+    let mut on_ground = false;
+    for _ in 0..steps as u32 {
+        for axis in [1, 0, 2].iter().copied() {
+            let mut moved = pos;
+            moved[axis] += step[axis];
+            if !collides(moved, solid) {
+                pos = moved;
+                continue;
+            }
+            if axis == 1 && step.y < 0.0 {
+                on_ground = true;
+                pos.y = moved.y.floor() + 1.0;
+            }
+            step[axis] = 0.0;
+            velocity[axis] = 0.0;
+        }
+    }
+    (pos, on_ground)
+}
+
+// Every block is treated as a full cube
+fn collides<F>(pos: Vector3<f64>, solid: &F) -> bool
+    where F: Fn(&Vector3<i32>) -> bool
+{
+    let min = pos - vector!(HALF_WIDTH, 0.0, HALF_WIDTH);
+    let max = pos + vector!(HALF_WIDTH, HEIGHT, HALF_WIDTH);
+    let (min, max) = (min.map(|coord| coord.floor() as i32), max.map(|coord| coord.floor() as i32));
+    (min.x..=max.x).any(|x| (min.y..=max.y).any(|y| (min.z..=max.z).any(|z|
+        solid(&vector!(x, y, z)))))
+}
+
+// Joins nearby stacks of the same item into the biggest one
+#[system]
+#[read_component(EntityId)]
+#[read_component(Position)]
+#[write_component(DroppedItem)]
+pub fn merge_items(world: &mut SubWorld, cmd: &mut CommandBuffer,
+    #[resource] tracker: &EntityTracker)
+{
+    let mut query = <(Entity, &EntityId, &Position, &mut DroppedItem)>::query();
+    let mut items: Vec<_> = query.iter_mut(world).collect();
+    let mut cells: HashMap<Vector3<i32>, Vec<usize>> = HashMap::new();
+    for (index, (_, _, position, _)) in items.iter().enumerate() {
+        cells.entry(cell(position.0)).or_default().push(index);
+    }
+    // The ones that despawned this tick are still there
+    let mut removed: Vec<bool> = items.iter()
+        .map(|(_, _, position, item)| item.should_despawn(&position.0))
+        .collect();
+    let mut changed = vec![false; items.len()];
+    for first in 0..items.len() {
+        let first_cell = cell(items[first].2 .0);
+        let neighbours: Vec<usize> = neighbour_cells(first_cell)
+            .filter_map(|cell| cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|second| *second > first)
+            .collect();
+        for second in neighbours {
+            if removed[first] {
+                break;
+            }
+            if removed[second] {
+                continue;
+            }
+            let (left, right) = items.split_at_mut(second);
+            let (a, b) = (&mut left[first], &mut right[0]);
+            let delta = (a.2 .0 - b.2 .0).abs();
+            let reach = 2.0 * HALF_WIDTH + MERGE_DISTANCE;
+            if delta.x > reach || delta.z > reach || delta.y > HEIGHT || !a.3.can_merge(b.3) {
+                continue;
+            }
+            let (target, source, target_index, source_index) =
+                if a.3.stack.count >= b.3.stack.count {
+                    (a, b, first, second)
+                } else {
+                    (b, a, second, first)
+                };
+            target.3.absorb(source.3);
+            cmd.remove(*source.0);
+            tracker.remove(source.1 .0, &source.2 .0);
+            removed[source_index] = true;
+            changed[target_index] = true;
+        }
+    }
+    for (index, (_, id, position, item)) in items.iter().enumerate() {
+        if changed[index] && !removed[index] {
+            tracker.send_event(&position.0, EntityEvent {
+                id: id.0,
+                data: EntityEventData::SetItem {
+                    stack: item.stack.clone(),
+                },
+            });
+        }
+    }
+}
+
+fn cell(pos: Vector3<f64>) -> Vector3<i32> {
+    pos.map(|coord| coord.floor() as i32)
+}
+
+fn neighbour_cells(center: Vector3<i32>) -> impl Iterator<Item = Vector3<i32>> {
+    (-1..=1).flat_map(move |x| (-1..=1).flat_map(move |y| (-1..=1)
+        .map(move |z| center + vector!(x, y, z))))
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Vector3, vector};
+    use crate::items::{ItemStack, ItemType};
+    use super::{DroppedItem, physics_tick};
+
+    fn item(name: &str, count: u8, pickup_delay: u32) -> DroppedItem {
+        let stack = ItemStack { item: ItemType::from_name(name).unwrap(), count, nbt: None };
+        DroppedItem::new(stack, pickup_delay)
+    }
+
+    #[test]
+    fn merge_test() {
+        let mut stone = item("minecraft:stone", 40, 0);
+        assert!(!stone.can_merge(&item("minecraft:dirt", 1, 0)));
+        assert!(!stone.can_merge(&item("minecraft:stone", 30, 0)));
+        let other = item("minecraft:stone", 24, 10);
+        assert!(stone.can_merge(&other));
+        stone.absorb(&other);
+        assert_eq!(stone.stack.count, 64);
+        assert_eq!(stone.pickup_delay, 10);
+        assert!(!stone.can_pick_up());
+    }
+
+    #[test]
+    fn landing_test() {
+        // Only the blocks below y = 64 are solid
+        let solid = |pos: &Vector3<i32>| pos.y < 64;
+        let mut pos = vector!(0.5, 70.0, 0.5);
+        let mut velocity = vector!(0.1, 0.0, 0.0);
+        pos = physics_tick(pos, &mut velocity, &solid);
+        assert!(pos.y < 70.0 && velocity.y < 0.0);
+        for _ in 0..100 {
+            pos = physics_tick(pos, &mut velocity, &solid);
+        }
+        assert_eq!(pos.y, 64.0);
+        assert_eq!(velocity, Vector3::zeros());
+        let resting = pos;
+        assert_eq!(physics_tick(pos, &mut velocity, &solid), resting);
+    }
+}
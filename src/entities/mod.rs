@@ -1,12 +1,22 @@
 mod components;
 mod entity_id;
+mod item;
 
 use legion::*;
 use systems::Builder;
+use item::{merge_items_system, move_items_system, spawn_items_system};
 
-pub use components::{Position, Rotation, Name};
+pub use components::{Position, Rotation, OnGround, Velocity, Name, Address};
 pub use entity_id::{EntityId, EntityIdGenerator};
+pub use item::{DroppedItem, ItemSpawner};
 
 pub fn register(schedule: &mut Builder, resources: &mut Resources) {
+    // Flushed so the items are in the world when the players are told about them
+    schedule
+        .add_system(move_items_system())
+        .add_system(merge_items_system())
+        .add_system(spawn_items_system())
+        .flush();
     resources.insert(EntityIdGenerator::new());
+    resources.insert(ItemSpawner::new());
 }
\ No newline at end of file
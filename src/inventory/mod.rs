@@ -1,7 +1,9 @@
 mod serialization;
 
-use crate::items::ItemStack;
+use nbt::Value as Nbt;
+use crate::items::{ItemStack, ItemType};
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use serde::{Serialize, Deserialize};
 use serialization::ItemStackPlayerData;
 
@@ -35,6 +37,53 @@ impl Inventory {
         self.held_slot = slot;
     }
 
+    // Fills stacks of the same item first, then empty slots with the
+    // hotbar first, like vanilla. Returns how many items didn't fit
+    pub fn add(&mut self, item: &'static ItemType, count: u32) -> u32 {
+        self.add_with_nbt(item, &None, count)
+    }
+
+    // Like add, keeping the NBT of the stack
+    pub fn insert(&mut self, stack: &ItemStack) -> u8 {
+        self.add_with_nbt(stack.item, &stack.nbt, stack.count as u32) as u8
+    }
+
+    // Takes up to `count` items from the held slot
+    pub fn take_held(&mut self, count: u8) -> Option<ItemStack> {
+        let held = self.slots.get_mut(&self.held_slot)?;
+        if held.count <= count {
+            return self.slots.remove(&self.held_slot);
+        }
+        held.count -= count;
+        Some(ItemStack { count, ..held.clone() })
+    }
+
+    fn add_with_nbt(&mut self, item: &'static ItemType, nbt: &Option<Nbt>, mut count: u32)
+        -> u32
+    {
+        let max = item.max_stack_size() as u32;
+        for index in 0..36 {
+            if let Some(stack) = self.slots.get_mut(&SlotIndex(index)) {
+                if stack.item.to_numeric() == item.to_numeric() && stack.nbt == *nbt {
+                    let added = count.min(max.saturating_sub(stack.count as u32));
+                    stack.count += added as u8;
+                    count -= added;
+                }
+            }
+        }
+        for index in 0..36 {
+            if count == 0 {
+                break;
+            }
+            if let Entry::Vacant(slot) = self.slots.entry(SlotIndex(index)) {
+                let added = count.min(max);
+                slot.insert(ItemStack { item, count: added as u8, nbt: nbt.clone() });
+                count -= added;
+            }
+        }
+        count
+    }
+
     pub fn get_held(&self) -> Option<&ItemStack> {
         self.slots.get(&self.held_slot)
     }
@@ -80,3 +129,34 @@ impl SlotIndex {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::items::ItemType;
+    use super::{Inventory, SlotIndex};
+
+    #[test]
+    fn add_test() {
+        let stone = ItemType::from_name("minecraft:stone").unwrap();
+        let mut inventory = Inventory::new();
+        assert_eq!(inventory.add(stone, 100), 0);
+        assert_eq!(inventory.get_window()[SlotIndex::from_hotbar(0).to_network() as usize]
+            .as_ref().unwrap().count, 64);
+        assert_eq!(inventory.add(stone, 30), 0);
+        assert_eq!(inventory.get_window()[SlotIndex::from_hotbar(1).to_network() as usize]
+            .as_ref().unwrap().count, 64);
+        assert_eq!(inventory.add(stone, 64 * 36), 130);
+    }
+
+    #[test]
+    fn take_held_test() {
+        let stone = ItemType::from_name("minecraft:stone").unwrap();
+        let mut inventory = Inventory::new();
+        inventory.add(stone, 10);
+        assert_eq!(inventory.take_held(1).unwrap().count, 1);
+        assert_eq!(inventory.get_held().unwrap().count, 9);
+        assert_eq!(inventory.take_held(64).unwrap().count, 9);
+        assert!(inventory.get_held().is_none());
+        assert!(inventory.take_held(1).is_none());
+    }
+}
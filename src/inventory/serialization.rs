@@ -22,7 +22,7 @@ impl TryFrom<Vec<ItemStackPlayerData>> for Inventory {
         let mut slots = HashMap::new();
         for stack in items {
             let index = SlotIndex::from_file(stack.slot);
-            let item = ItemType::from_str(stack.item.as_str())?;
+            let item = ItemType::from_name(stack.item.as_str())?;
             slots.insert(index, 
                 ItemStack {
                     item,
@@ -19,6 +19,7 @@ lazy_static! {
 pub struct ItemType {
     id: u16,
     block: Option<u16>,
+    max_stack_size: u8,
 }
 
 impl ItemType {
@@ -28,7 +29,7 @@ impl ItemType {
         Ok(TYPE_MAP.get(id).unwrap())
     }
 
-    pub fn from_str(id: &str) -> Result<&'static Self> {
+    pub fn from_name(id: &str) -> Result<&'static Self> {
         TYPE_MAP.get(id)
             .ok_or(anyhow!(format!("Invalid item id: {}", id)))
     }
@@ -41,6 +42,10 @@ impl ItemType {
         self.id
     }
 
+    pub fn max_stack_size(&self) -> u8 {
+        self.max_stack_size
+    }
+
     pub fn get_block(&self) -> Option<&BlockType> {
         self.block.map(|block| BlockType::from_id(block).unwrap())
     }
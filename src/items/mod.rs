@@ -1,7 +1,9 @@
 mod item_type;
+mod tool;
 
 use nbt::Value as Nbt;
 pub use item_type::ItemType;
+pub use tool::{Tool, ToolKind, ToolTier};
 
 #[derive(Clone, Debug)]
 pub struct ItemStack {
@@ -9,3 +11,29 @@ pub struct ItemStack {
     pub count: u8,
     pub nbt: Option<Nbt>,
 }
+
+impl ItemStack {
+    // 0 when it doesn't have it, `id` is namespaced like "minecraft:efficiency"
+    pub fn enchantment_level(&self, id: &str) -> u16 {
+        let enchantments = match &self.nbt {
+            Some(Nbt::Compound(tag)) => match tag.get("Enchantments") {
+                Some(Nbt::List(enchantments)) => enchantments,
+                _ => return 0,
+            },
+            _ => return 0,
+        };
+        enchantments.iter()
+            .filter_map(|enchantment| match enchantment {
+                Nbt::Compound(enchantment) => Some(enchantment),
+                _ => None,
+            })
+            .find(|enchantment| matches!(enchantment.get("id"),
+                Some(Nbt::String(name)) if name == id))
+            .and_then(|enchantment| match enchantment.get("lvl") {
+                Some(Nbt::Short(level)) => Some((*level).max(0) as u16),
+                Some(Nbt::Int(level)) => Some((*level).max(0) as u16),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+}
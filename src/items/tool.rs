@@ -0,0 +1,184 @@
+use crate::blocks::Block;
+use super::ItemType;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToolKind {
+    Pickaxe,
+    Axe,
+    Shovel,
+    Hoe,
+    Sword,
+    Shears,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToolTier {
+    Wood,
+    Stone,
+    Iron,
+    Diamond,
+    Netherite,
+    Gold,
+}
+
+impl ToolTier {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wooden" => Some(Self::Wood),
+            "stone" => Some(Self::Stone),
+            "iron" => Some(Self::Iron),
+            "diamond" => Some(Self::Diamond),
+            "netherite" => Some(Self::Netherite),
+            "golden" => Some(Self::Gold),
+            _ => None,
+        }
+    }
+
+    // Which ores it can mine, gold is as weak as wood
+    fn level(self) -> u8 {
+        match self {
+            Self::Wood | Self::Gold => 0,
+            Self::Stone => 1,
+            Self::Iron => 2,
+            Self::Diamond => 3,
+            Self::Netherite => 4,
+        }
+    }
+
+    fn speed(self) -> f32 {
+        match self {
+            Self::Wood => 2.0,
+            Self::Stone => 4.0,
+            Self::Iron => 6.0,
+            Self::Diamond => 8.0,
+            Self::Netherite => 9.0,
+            Self::Gold => 12.0,
+        }
+    }
+}
+
+// Blocks that aren't made of a material the tool is good at, but it's
+// still faster with it
+const PICKAXE_BLOCKS: &[&str] = &["minecraft:ice", "minecraft:packed_ice", "minecraft:blue_ice",
+    "minecraft:rail", "minecraft:powered_rail", "minecraft:detector_rail",
+    "minecraft:activator_rail", "minecraft:stone_button", "minecraft:polished_blackstone_button",
+    "minecraft:piston", "minecraft:sticky_piston", "minecraft:piston_head"];
+const AXE_BLOCKS: &[&str] = &["minecraft:ladder", "minecraft:scaffolding"];
+const SHOVEL_BLOCKS: &[&str] = &["minecraft:clay", "minecraft:dirt", "minecraft:coarse_dirt",
+    "minecraft:podzol", "minecraft:farmland", "minecraft:grass_block", "minecraft:gravel",
+    "minecraft:mycelium", "minecraft:sand", "minecraft:red_sand", "minecraft:snow_block",
+    "minecraft:snow", "minecraft:soul_sand", "minecraft:grass_path", "minecraft:soul_soil"];
+const HOE_BLOCKS: &[&str] = &["minecraft:nether_wart_block", "minecraft:warped_wart_block",
+    "minecraft:hay_block", "minecraft:dried_kelp_block", "minecraft:target",
+    "minecraft:shroomlight", "minecraft:sponge", "minecraft:wet_sponge"];
+// Ores and blocks that need more than a wooden pickaxe to drop
+const TIER_REQUIREMENTS: &[(&str, u8)] = &[
+    ("minecraft:obsidian", 3), ("minecraft:crying_obsidian", 3),
+    ("minecraft:netherite_block", 3), ("minecraft:respawn_anchor", 3),
+    ("minecraft:ancient_debris", 3),
+    ("minecraft:diamond_block", 2), ("minecraft:diamond_ore", 2),
+    ("minecraft:emerald_ore", 2), ("minecraft:emerald_block", 2),
+    ("minecraft:gold_block", 2), ("minecraft:gold_ore", 2), ("minecraft:redstone_ore", 2),
+    ("minecraft:iron_block", 1), ("minecraft:iron_ore", 1),
+    ("minecraft:lapis_block", 1), ("minecraft:lapis_ore", 1),
+];
+
+#[derive(Clone, Copy, Debug)]
+pub struct Tool {
+    pub kind: ToolKind,
+    // Shears don't have one
+    pub tier: Option<ToolTier>,
+}
+
+impl Tool {
+    pub fn from_item(item: &ItemType) -> Option<Self> {
+        let name = item.to_str().strip_prefix("minecraft:")?;
+        if name == "shears" {
+            return Some(Self { kind: ToolKind::Shears, tier: None });
+        }
+        let (tier, kind) = name.split_at(name.find('_')?);
+        let kind = match kind {
+            "_pickaxe" => ToolKind::Pickaxe,
+            "_axe" => ToolKind::Axe,
+            "_shovel" => ToolKind::Shovel,
+            "_hoe" => ToolKind::Hoe,
+            "_sword" => ToolKind::Sword,
+            _ => return None,
+        };
+        Some(Self { kind, tier: Some(ToolTier::from_name(tier)?) })
+    }
+
+    // How many times faster than by hand it breaks the block
+    pub fn destroy_speed(&self, block: &Block) -> f32 {
+        let name = block.btype.name.as_str();
+        let material = block.material.name.as_str();
+        let tier_speed = self.tier.map(ToolTier::speed).unwrap_or(1.0);
+        match self.kind {
+            ToolKind::Pickaxe if matches!(material,
+                "minecraft:stone" | "minecraft:metal" | "minecraft:repair_station")
+                || PICKAXE_BLOCKS.contains(&name) => tier_speed,
+            ToolKind::Axe if matches!(material,
+                "minecraft:wood" | "minecraft:nether_wood" | "minecraft:plant"
+                | "minecraft:replaceable_plant" | "minecraft:bamboo" | "minecraft:gourd")
+                || AXE_BLOCKS.contains(&name) => tier_speed,
+            ToolKind::Shovel if SHOVEL_BLOCKS.contains(&name)
+                || material == "minecraft:snow_layer" => tier_speed,
+            ToolKind::Hoe if HOE_BLOCKS.contains(&name)
+                || material == "minecraft:leaves" => tier_speed,
+            ToolKind::Sword if name == "minecraft:cobweb" => 15.0,
+            ToolKind::Sword if matches!(material,
+                "minecraft:plant" | "minecraft:replaceable_plant" | "minecraft:leaves"
+                | "minecraft:gourd") => 1.5,
+            ToolKind::Shears if name == "minecraft:cobweb"
+                || material == "minecraft:leaves" => 15.0,
+            ToolKind::Shears if material == "minecraft:wool" => 5.0,
+            _ => 1.0,
+        }
+    }
+
+    // Blocks that require a tool only drop when broken with one of these
+    pub fn is_correct_for_drops(&self, block: &Block) -> bool {
+        let name = block.btype.name.as_str();
+        match self.kind {
+            ToolKind::Pickaxe => {
+                let level = self.tier.map(ToolTier::level).unwrap_or(0);
+                match TIER_REQUIREMENTS.iter().find(|(block, _)| *block == name) {
+                    Some((_, required)) => level >= *required,
+                    None => matches!(block.material.name.as_str(),
+                        "minecraft:stone" | "minecraft:metal" | "minecraft:repair_station"),
+                }
+            }
+            ToolKind::Shovel => matches!(name, "minecraft:snow" | "minecraft:snow_block"),
+            ToolKind::Sword => name == "minecraft:cobweb",
+            ToolKind::Shears => matches!(name,
+                "minecraft:cobweb" | "minecraft:redstone_wire" | "minecraft:tripwire"),
+            ToolKind::Axe | ToolKind::Hoe => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::items::ItemType;
+    use super::{Tool, ToolKind, ToolTier};
+
+    fn tool(name: &str) -> Option<Tool> {
+        Tool::from_item(ItemType::from_name(name).unwrap())
+    }
+
+    #[test]
+    fn from_item_test() {
+        let pickaxe = tool("minecraft:diamond_pickaxe").unwrap();
+        assert_eq!(pickaxe.kind, ToolKind::Pickaxe);
+        assert_eq!(pickaxe.tier, Some(ToolTier::Diamond));
+        let axe = tool("minecraft:golden_axe").unwrap();
+        assert_eq!(axe.kind, ToolKind::Axe);
+        assert_eq!(axe.tier, Some(ToolTier::Gold));
+        let shears = tool("minecraft:shears").unwrap();
+        assert_eq!(shears.kind, ToolKind::Shears);
+        assert_eq!(shears.tier, None);
+        assert!(tool("minecraft:stone").is_none());
+        assert!(tool("minecraft:stone_bricks").is_none());
+        assert!(tool("minecraft:diamond_block").is_none());
+    }
+}
@@ -0,0 +1,51 @@
+#[macro_use]
+pub mod logging;
+pub mod net;
+pub mod players;
+pub mod chunks;
+pub mod config;
+mod util;
+mod entities;
+mod buckets;
+mod inventory;
+pub mod items;
+mod loot;
+mod serialization;
+mod tags;
+pub mod blocks;
+pub mod commands;
+mod text;
+mod access;
+
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+use config::Config;
+use legion::*;
+use net::Server;
+
+// Set by Ctrl + C or /stop, the tick loop saves everything and exits
+pub static STOP_SIGNAL: AtomicBool = AtomicBool::new(false);
+
+// The systems and resources of the game, the server has to be started first
+pub fn build_game(config: &Config, server: Server, command_queue: commands::CommandQueue)
+    -> (Schedule, Resources)
+{
+    let mut resources = Resources::default();
+    resources.insert(server);
+    resources.insert(command_queue);
+    commands::register(&mut resources, config);
+    let mut schedule = Schedule::builder();
+    players::register_early(&mut schedule, &mut resources, config);
+    chunks::register(&mut schedule, &mut resources, config);
+    entities::register(&mut schedule, &mut resources);
+    buckets::register(&mut schedule, &mut resources);
+    players::register_late(&mut schedule, &mut resources);
+    (schedule.build(), resources)
+}
+
+pub fn tick(schedule: &mut Schedule, world: &mut World, resources: &mut Resources) {
+    let start = Instant::now();
+    schedule.execute(world, resources);
+    commands::run_pending(world, resources);
+    players::record_tick_time(resources, start.elapsed());
+}
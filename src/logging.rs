@@ -0,0 +1,33 @@
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+use rustyline::ExternalPrinter;
+
+lazy_static! {
+    // Set by the console while it reads commands from a terminal
+    static ref CONSOLE: Mutex<Option<Box<dyn ExternalPrinter + Send>>> = Mutex::new(None);
+}
+
+// Prints a line of the log. Every line goes through here, so they don't
+// get mixed up with each other or with the command being typed
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::logging::write_line(format!($($arg)*))
+    };
+}
+
+pub fn write_line(line: String) {
+    let mut console = CONSOLE.lock().unwrap();
+    // The console prints it above the prompt, and redraws it with what
+    // was typed so far
+    if let Some(console) = console.as_mut() {
+        if console.print(format!("{}\n", line)).is_ok() {
+            return;
+        }
+    }
+    println!("{}", line);
+}
+
+pub fn set_console(printer: Option<Box<dyn ExternalPrinter + Send>>) {
+    *CONSOLE.lock().unwrap() = printer;
+}
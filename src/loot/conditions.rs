@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use rand::Rng;
+use serde::Deserialize;
+use super::LootContext;
+
+#[derive(Deserialize)]
+#[serde(tag = "condition")]
+pub enum Condition {
+    // Always passes unless the block was blown up
+    #[serde(rename = "minecraft:survives_explosion")]
+    SurvivesExplosion,
+    #[serde(rename = "minecraft:match_tool")]
+    MatchTool {
+        predicate: ItemPredicate,
+    },
+    #[serde(rename = "minecraft:inverted")]
+    Inverted {
+        term: Box<Condition>,
+    },
+    #[serde(rename = "minecraft:alternative")]
+    Alternative {
+        terms: Vec<Condition>,
+    },
+    #[serde(rename = "minecraft:block_state_property")]
+    BlockStateProperty {
+        block: String,
+        #[serde(default)]
+        properties: HashMap<String, String>,
+    },
+    // The chance is picked by the level of the enchantment, fortune on block tables
+    #[serde(rename = "minecraft:table_bonus")]
+    TableBonus {
+        enchantment: String,
+        chances: Vec<f32>,
+    },
+    #[serde(rename = "minecraft:random_chance")]
+    RandomChance {
+        chance: f32,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct ItemPredicate {
+    item: Option<String>,
+    #[serde(default)]
+    enchantments: Vec<EnchantmentPredicate>,
+}
+
+#[derive(Deserialize)]
+struct EnchantmentPredicate {
+    enchantment: String,
+    #[serde(default)]
+    levels: Levels,
+}
+
+#[derive(Default, Deserialize)]
+struct Levels {
+    min: Option<u16>,
+    max: Option<u16>,
+}
+
+pub fn all(conditions: &[Condition], ctx: &LootContext, rng: &mut impl Rng) -> bool {
+    conditions.iter().all(|condition| condition.test(ctx, rng))
+}
+
+impl Condition {
+    pub fn test(&self, ctx: &LootContext, rng: &mut impl Rng) -> bool {
+        match self {
+            Self::SurvivesExplosion => match ctx.explosion_radius {
+                Some(radius) => rng.gen::<f32>() <= 1.0 / radius,
+                None => true,
+            },
+            Self::MatchTool { predicate } => predicate.test(ctx),
+            Self::Inverted { term } => !term.test(ctx, rng),
+            Self::Alternative { terms } => terms.iter().any(|term| term.test(ctx, rng)),
+            Self::BlockStateProperty { block, properties } => {
+                ctx.block.btype.name == *block && properties.iter()
+                    .all(|(name, value)| ctx.block.props.get(name) == Some(value))
+            }
+            Self::TableBonus { enchantment, chances } => {
+                let level = ctx.tool
+                    .map(|tool| tool.enchantment_level(enchantment))
+                    .unwrap_or(0) as usize;
+                let chance = chances.get(level).or_else(|| chances.last()).unwrap_or(&0.0);
+                rng.gen::<f32>() < *chance
+            }
+            Self::RandomChance { chance } => rng.gen::<f32>() < *chance,
+        }
+    }
+}
+
+impl ItemPredicate {
+    fn test(&self, ctx: &LootContext) -> bool {
+        let tool = match ctx.tool {
+            Some(tool) => tool,
+            None => return false,
+        };
+        if let Some(item) = &self.item {
+            if tool.item.to_str() != item {
+                return false;
+            }
+        }
+        self.enchantments.iter().all(|predicate| {
+            let level = tool.enchantment_level(&predicate.enchantment);
+            level >= predicate.levels.min.unwrap_or(1)
+                && level <= predicate.levels.max.unwrap_or(u16::MAX)
+        })
+    }
+}
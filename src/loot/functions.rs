@@ -0,0 +1,112 @@
+use rand::Rng;
+use serde::Deserialize;
+use super::{LootContext, NumberProvider};
+use super::conditions::{self, Condition};
+
+#[derive(Deserialize)]
+#[serde(tag = "function")]
+pub enum Function {
+    #[serde(rename = "minecraft:set_count")]
+    SetCount {
+        count: NumberProvider,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+    },
+    // Fortune on block tables
+    #[serde(rename = "minecraft:apply_bonus")]
+    ApplyBonus {
+        enchantment: String,
+        formula: String,
+        #[serde(default)]
+        parameters: BonusParameters,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+    },
+    // Each item has a chance of 1 / radius of surviving an explosion
+    #[serde(rename = "minecraft:explosion_decay")]
+    ExplosionDecay {
+        #[serde(default)]
+        conditions: Vec<Condition>,
+    },
+    #[serde(rename = "minecraft:limit_count")]
+    LimitCount {
+        limit: Limit,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+    },
+    // Copying names, NBT and such, they don't change what drops
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Default, Deserialize)]
+pub struct BonusParameters {
+    #[serde(rename = "bonusMultiplier")]
+    bonus_multiplier: Option<f32>,
+    extra: Option<u32>,
+    probability: Option<f32>,
+}
+
+#[derive(Deserialize)]
+pub struct Limit {
+    min: Option<f32>,
+    max: Option<f32>,
+}
+
+impl Function {
+    // Returns the new count of the stack
+    pub fn apply(&self, count: i32, ctx: &LootContext, rng: &mut impl Rng) -> i32 {
+        if !conditions::all(self.conditions(), ctx, rng) {
+            return count;
+        }
+        match self {
+            Self::SetCount { count, .. } => count.get(rng),
+            Self::ApplyBonus { enchantment, formula, parameters, .. } => {
+                let level = ctx.tool
+                    .map(|tool| tool.enchantment_level(enchantment))
+                    .unwrap_or(0) as i32;
+                apply_bonus(count, level, formula, parameters, rng)
+            }
+            Self::ExplosionDecay { .. } => match ctx.explosion_radius {
+                Some(radius) => (0..count).filter(|_| rng.gen::<f32>() <= 1.0 / radius).count() as i32,
+                None => count,
+            },
+            Self::LimitCount { limit, .. } => {
+                let count = limit.min.map(|min| count.max(min as i32)).unwrap_or(count);
+                limit.max.map(|max| count.min(max as i32)).unwrap_or(count)
+            }
+            Self::Unsupported => count,
+        }
+    }
+
+    fn conditions(&self) -> &[Condition] {
+        match self {
+            Self::SetCount { conditions, .. }
+            | Self::ApplyBonus { conditions, .. }
+            | Self::ExplosionDecay { conditions }
+            | Self::LimitCount { conditions, .. } => conditions,
+            Self::Unsupported => &[],
+        }
+    }
+}
+
+fn apply_bonus(count: i32, level: i32, formula: &str, parameters: &BonusParameters,
+    rng: &mut impl Rng) -> i32
+{
+    match formula {
+        "minecraft:ore_drops" if level > 0 => {
+            let bonus = (rng.gen_range(0..level + 2) - 1).max(0);
+            count * (bonus + 1)
+        }
+        "minecraft:uniform_bonus_count" => {
+            let multiplier = parameters.bonus_multiplier.unwrap_or(1.0);
+            count + rng.gen_range(0..=(multiplier * level as f32).round() as i32)
+        }
+        "minecraft:binomial_with_bonus_count" => {
+            let tries = parameters.extra.unwrap_or(0) as i32 + level;
+            let probability = parameters.probability.unwrap_or(0.0);
+            count + (0..tries).filter(|_| rng.gen::<f32>() < probability).count() as i32
+        }
+        _ => count,
+    }
+}
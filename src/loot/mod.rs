@@ -0,0 +1,268 @@
+mod conditions;
+mod functions;
+
+use std::collections::HashMap;
+use lazy_static::lazy_static;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::blocks::Block;
+use crate::items::{ItemStack, ItemType};
+use conditions::Condition;
+use functions::Function;
+
+const BLOCKS_JSON: &str = include_str!("blocks.json");
+
+lazy_static! {
+    static ref BLOCK_TABLES: HashMap<String, LootTable> =
+        serde_json::from_str(BLOCKS_JSON).unwrap();
+}
+
+// What the table is rolled for, conditions and functions look at it
+pub struct LootContext<'a> {
+    pub block: &'a Block,
+    pub tool: Option<&'a ItemStack>,
+    // Blown up blocks only drop some of their items
+    pub explosion_radius: Option<f32>,
+}
+
+// What a block drops when broken with `tool`, blocks without a table drop nothing
+pub fn block_drops(block: &Block, tool: Option<&ItemStack>) -> Vec<ItemStack> {
+    let ctx = LootContext { block, tool, explosion_radius: None };
+    match BLOCK_TABLES.get(&block.btype.name) {
+        Some(table) => table.roll(&ctx, &mut rand::thread_rng()),
+        None => vec![],
+    }
+}
+
+// Vanilla's data pack format, only what block tables use
+#[derive(Deserialize)]
+pub struct LootTable {
+    #[serde(default)]
+    pools: Vec<Pool>,
+    #[serde(default)]
+    functions: Vec<Function>,
+}
+
+#[derive(Deserialize)]
+struct Pool {
+    rolls: NumberProvider,
+    entries: Vec<Entry>,
+    #[serde(default)]
+    conditions: Vec<Condition>,
+    #[serde(default)]
+    functions: Vec<Function>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Entry {
+    #[serde(rename = "minecraft:item")]
+    Item {
+        name: String,
+        #[serde(default = "default_weight")]
+        weight: u32,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+        #[serde(default)]
+        functions: Vec<Function>,
+    },
+    // The first child that passes its conditions
+    #[serde(rename = "minecraft:alternatives")]
+    Alternatives {
+        children: Vec<Entry>,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+    },
+    #[serde(rename = "minecraft:empty")]
+    Empty {
+        #[serde(default = "default_weight")]
+        weight: u32,
+        #[serde(default)]
+        conditions: Vec<Condition>,
+    },
+    // Dynamic contents, tags and other tables, nothing drops from them here
+    #[serde(other)]
+    Unsupported,
+}
+
+// An entry's weight and the item it drops, if any
+type Choice<'a> = (u32, Option<(&'a str, &'a [Function])>);
+
+fn default_weight() -> u32 {
+    1
+}
+
+// A constant, a range or the successes of n tries
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberProvider {
+    Constant(f32),
+    Uniform { min: f32, max: f32 },
+    Binomial { n: u32, p: f32 },
+}
+
+impl NumberProvider {
+    fn get(&self, rng: &mut impl Rng) -> i32 {
+        match self {
+            Self::Constant(value) => value.round() as i32,
+            Self::Uniform { min, max } => {
+                let (min, max) = (min.floor() as i32, max.floor() as i32);
+                if min >= max { min } else { rng.gen_range(min..=max) }
+            }
+            Self::Binomial { n, p } => (0..*n).filter(|_| rng.gen::<f32>() < *p).count() as i32,
+        }
+    }
+}
+
+impl LootTable {
+    pub fn roll(&self, ctx: &LootContext, rng: &mut impl Rng) -> Vec<ItemStack> {
+        let mut stacks = vec![];
+        for pool in &self.pools {
+            if !conditions::all(&pool.conditions, ctx, rng) {
+                continue;
+            }
+            for _ in 0..pool.rolls.get(rng) {
+                let mut chosen = vec![];
+                for entry in &pool.entries {
+                    entry.expand(ctx, rng, &mut chosen);
+                }
+                let (name, functions) = match chosen.choose_weighted(rng, |(weight, _)| *weight) {
+                    Ok((_, Some(item))) => *item,
+                    _ => continue,
+                };
+                let mut count = 1;
+                for function in functions.iter().chain(&pool.functions).chain(&self.functions) {
+                    count = function.apply(count, ctx, rng);
+                }
+                push_stacks(&mut stacks, name, count);
+            }
+        }
+        stacks
+    }
+}
+
+impl Entry {
+    // Adds the items it can drop with their weights, None drops nothing
+    fn expand<'a>(&'a self, ctx: &LootContext, rng: &mut impl Rng,
+        chosen: &mut Vec<Choice<'a>>) -> bool
+    {
+        match self {
+            Self::Item { name, weight, conditions, functions } => {
+                if !conditions::all(conditions, ctx, rng) {
+                    return false;
+                }
+                chosen.push((*weight, Some((name.as_str(), functions.as_slice()))));
+            }
+            Self::Alternatives { children, conditions } => {
+                if !conditions::all(conditions, ctx, rng) {
+                    return false;
+                }
+                return children.iter().any(|child| child.expand(ctx, rng, chosen));
+            }
+            Self::Empty { weight, conditions } => {
+                if !conditions::all(conditions, ctx, rng) {
+                    return false;
+                }
+                chosen.push((*weight, None));
+            }
+            Self::Unsupported => return false,
+        }
+        true
+    }
+}
+
+// Splits counts bigger than what fits in a stack
+fn push_stacks(stacks: &mut Vec<ItemStack>, name: &str, count: i32) {
+    let item = match ItemType::from_name(name) {
+        Ok(item) => item,
+        Err(_) => return,
+    };
+    let mut left = count.max(0) as u32;
+    while left > 0 {
+        let count = left.min(item.max_stack_size() as u32);
+        stacks.push(ItemStack { item, count: count as u8, nbt: None });
+        left -= count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use nbt::Value as Nbt;
+    use rand::{SeedableRng, rngs::StdRng};
+    use crate::blocks::{Block, BlockType};
+    use crate::items::{ItemStack, ItemType};
+    use super::{BLOCK_TABLES, LootContext, LootTable};
+
+    fn block(name: &str) -> &'static Block {
+        let btype = BlockType::from_name(name).unwrap();
+        btype.with_props(&btype.default_state).unwrap()
+    }
+
+    fn stack(name: &str, enchantment: Option<(&str, i16)>) -> ItemStack {
+        let nbt = enchantment.map(|(id, level)| {
+            let mut tag = HashMap::new();
+            tag.insert("id".to_owned(), Nbt::String(id.to_owned()));
+            tag.insert("lvl".to_owned(), Nbt::Short(level));
+            let mut root = HashMap::new();
+            root.insert("Enchantments".to_owned(), Nbt::List(vec![Nbt::Compound(tag)]));
+            Nbt::Compound(root)
+        });
+        ItemStack { item: ItemType::from_name(name).unwrap(), count: 1, nbt }
+    }
+
+    fn roll(block: &Block, tool: Option<&ItemStack>) -> Vec<(&'static str, u8)> {
+        let ctx = LootContext { block, tool, explosion_radius: None };
+        let mut rng = StdRng::seed_from_u64(0);
+        BLOCK_TABLES[&block.btype.name].roll(&ctx, &mut rng).iter()
+            .map(|stack| (stack.item.to_str(), stack.count))
+            .collect()
+    }
+
+    #[test]
+    fn silk_touch_test() {
+        let stone = block("minecraft:stone");
+        assert_eq!(roll(stone, None), vec![("minecraft:cobblestone", 1)]);
+        let pickaxe = stack("minecraft:iron_pickaxe", Some(("minecraft:silk_touch", 1)));
+        assert_eq!(roll(stone, Some(&pickaxe)), vec![("minecraft:stone", 1)]);
+        assert!(roll(block("minecraft:glass"), None).is_empty());
+    }
+
+    #[test]
+    fn fortune_test() {
+        let ore = block("minecraft:diamond_ore");
+        let pickaxe = stack("minecraft:diamond_pickaxe", None);
+        assert_eq!(roll(ore, Some(&pickaxe)), vec![("minecraft:diamond", 1)]);
+        let fortune = stack("minecraft:diamond_pickaxe", Some(("minecraft:fortune", 3)));
+        let ctx = LootContext { block: ore, tool: Some(&fortune), explosion_radius: None };
+        let mut rng = StdRng::seed_from_u64(0);
+        let total: u32 = (0..100)
+            .map(|_| BLOCK_TABLES["minecraft:diamond_ore"].roll(&ctx, &mut rng)[0].count as u32)
+            .sum();
+        assert!(total > 150 && total < 300);
+    }
+
+    #[test]
+    fn unsupported_test() {
+        let shulker_box = block("minecraft:shulker_box");
+        assert_eq!(roll(shulker_box, None), vec![("minecraft:shulker_box", 1)]);
+        let json = r#"{"pools": [{"rolls": 1.0, "entries": [
+            {"type": "minecraft:dynamic", "name": "minecraft:contents"}]}]}"#;
+        let table: LootTable = serde_json::from_str(json).unwrap();
+        let ctx = LootContext { block: shulker_box, tool: None, explosion_radius: None };
+        assert!(table.roll(&ctx, &mut StdRng::seed_from_u64(0)).is_empty());
+    }
+
+    #[test]
+    fn explosion_test() {
+        let ctx = LootContext {
+            block: block("minecraft:dirt"),
+            tool: None,
+            explosion_radius: Some(f32::INFINITY),
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(BLOCK_TABLES["minecraft:dirt"].roll(&ctx, &mut rng).is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 use nalgebra::Vector3;
 use nbt::{Value, to_writer};
+use crate::items::ItemStack;
 use crate::serialization::push_varint;
 
 pub struct PacketBuilder {
@@ -55,10 +56,29 @@ impl PacketBuilder {
         self
     }
 
+    pub fn add_slot(&mut self, slot: &Option<ItemStack>) -> &mut Self {
+        self.add_bytes(&[slot.is_some() as u8]);
+        if let Some(stack) = slot {
+            self.add_varint(stack.item.to_numeric() as u32)
+                .add_bytes(&[stack.count]);
+            match &stack.nbt {
+                Some(nbt) => self.add_nbt(nbt),
+                None => self.add_bytes(&[0]),
+            };
+        }
+        self
+    }
+
+    // Velocities are sent in 1/8000 of a block per tick
+    pub fn add_velocity(&mut self, velocity: &Vector3<f64>) -> &mut Self {
+        for coord in velocity.iter() {
+            let coord = (coord.clamp(-3.9, 3.9) * 8000.0) as i16;
+            self.add_bytes(&coord.to_be_bytes());
+        }
+        self
+    }
+
     pub fn build(&self) -> Vec<u8> {
-        let mut packet = Vec::with_capacity(self.bytes.len() + 5);
-        push_varint(self.bytes.len() as u32, &mut packet);
-        packet.extend(self.bytes.iter());
-        packet
+        self.bytes.clone()
     }
 }
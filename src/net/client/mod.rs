@@ -0,0 +1,357 @@
+mod reader;
+mod writer;
+
+use std::net::SocketAddr;
+use anyhow::{Result, anyhow};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use uuid::Uuid;
+use crate::text::TextComponent;
+use super::{ClientboundPacket, PROTOCOL_VERSION, ServerboundPacket};
+use super::builder::PacketBuilder;
+use super::framing::{frame, read_frame};
+use super::value_readers::{MAX_STRING_LENGTH, read_str, read_varint};
+
+// A bare offline mode client that talks to the server over a real socket,
+// decoding everything it receives into `ClientboundPacket`s
+pub struct Client {
+    conn: TcpStream,
+    address: SocketAddr,
+    compression: Option<u32>,
+}
+
+impl Client {
+    pub async fn connect(address: SocketAddr) -> Result<Self> {
+        let conn = TcpStream::connect(address).await?;
+        conn.set_nodelay(true)?;
+        Ok(Self {
+            conn,
+            address,
+            compression: None,
+        })
+    }
+
+    // Returns the status JSON, the connection can only be used to ping after this
+    pub async fn status(&mut self) -> Result<String> {
+        self.handshake(1).await?;
+        self.write(&PacketBuilder::new(0x00).build()).await?;
+        let packet = self.read().await?;
+        let mut payload = packet.as_slice();
+        match read_varint(&mut payload).await? {
+            0x00 => read_str(&mut payload, MAX_STRING_LENGTH).await,
+            id => Err(anyhow!("Expected a status response, got packet {}", id)),
+        }
+    }
+
+    pub async fn ping(&mut self, time: u64) -> Result<u64> {
+        self.write(&PacketBuilder::new(0x01).add_bytes(&time.to_be_bytes()).build()).await?;
+        let packet = self.read().await?;
+        let mut payload = packet.as_slice();
+        match read_varint(&mut payload).await? {
+            0x01 => Ok(payload.read_u64().await?),
+            id => Err(anyhow!("Expected a pong, got packet {}", id)),
+        }
+    }
+
+    // Goes through login until the server switches to play, returns the UUID
+    // it gave the player
+    pub async fn login(&mut self, name: &str) -> Result<Uuid> {
+        self.handshake(2).await?;
+        self.write(&PacketBuilder::new(0x00).add_str(name).build()).await?;
+        loop {
+            let packet = self.read().await?;
+            let mut payload = packet.as_slice();
+            match read_varint(&mut payload).await? {
+                0x00 => {
+                    let reason = read_str(&mut payload, MAX_STRING_LENGTH).await?;
+                    let reason = TextComponent::from_json(&reason)?;
+                    return Err(anyhow!("Disconnected: {}", reason.to_plain()));
+                }
+                0x01 => return Err(anyhow!("Online mode isn't supported")),
+                0x02 => {
+                    let mut uuid = [0; 16];
+                    payload.read_exact(&mut uuid).await?;
+                    return Ok(Uuid::from_bytes(uuid));
+                }
+                0x03 => {
+                    self.compression = Some(read_varint(&mut payload).await?);
+                }
+                0x04 => {
+                    // Plugin requests only get answered when they're understood
+                    let message_id = read_varint(&mut payload).await?;
+                    self.write(&PacketBuilder::new(0x02)
+                        .add_varint(message_id)
+                        .add_bytes(&[0])
+                        .build()).await?;
+                }
+                id => return Err(anyhow!("Unexpected login packet {}", id)),
+            }
+        }
+    }
+
+    pub async fn send(&mut self, packet: &ServerboundPacket) -> Result<()> {
+        packet.send(&mut self.conn, self.compression).await
+    }
+
+    pub async fn recv(&mut self) -> Result<ClientboundPacket> {
+        ClientboundPacket::read(&mut self.conn, self.compression).await
+    }
+
+    // So packets can be sent while waiting for others, once in play
+    pub fn into_split(self) -> (ClientReader, ClientWriter) {
+        let (read, write) = self.conn.into_split();
+        (
+            ClientReader {
+                conn: BufReader::new(read),
+                compression: self.compression,
+            },
+            ClientWriter {
+                conn: write,
+                compression: self.compression,
+            },
+        )
+    }
+
+    async fn handshake(&mut self, intent: u32) -> Result<()> {
+        let packet = PacketBuilder::new(0x00)
+            .add_varint(PROTOCOL_VERSION)
+            .add_str(&self.address.ip().to_string())
+            .add_bytes(&self.address.port().to_be_bytes())
+            .add_varint(intent)
+            .build();
+        self.write(&packet).await
+    }
+
+    async fn write(&mut self, packet: &[u8]) -> Result<()> {
+        self.conn.write_all(&frame(packet, self.compression)).await?;
+        Ok(())
+    }
+
+    async fn read(&mut self) -> Result<Vec<u8>> {
+        read_frame(&mut self.conn, self.compression).await
+    }
+}
+
+pub struct ClientReader {
+    conn: BufReader<OwnedReadHalf>,
+    compression: Option<u32>,
+}
+
+impl ClientReader {
+    pub async fn recv(&mut self) -> Result<ClientboundPacket> {
+        ClientboundPacket::read(&mut self.conn, self.compression).await
+    }
+}
+
+pub struct ClientWriter {
+    conn: OwnedWriteHalf,
+    compression: Option<u32>,
+}
+
+impl ClientWriter {
+    pub async fn send(&mut self, packet: &ServerboundPacket) -> Result<()> {
+        packet.send(&mut self.conn, self.compression).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+    use legion::{IntoQuery, Resources, Schedule, World};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc::unbounded_channel;
+    use tokio::time::sleep;
+    use uuid::Uuid;
+    use crate::commands::CommandQueue;
+    use crate::config::Config;
+    use crate::entities::EntityId;
+    use crate::net::{BRAND, ClientboundPacket, Server, ServerboundPacket, VERSION_NAME};
+    use crate::net::offline_uuid;
+    use super::Client;
+
+    struct Game {
+        schedule: Schedule,
+        world: World,
+        resources: Resources,
+        data_dir: PathBuf,
+    }
+
+    impl Game {
+        fn tick(&mut self) {
+            crate::tick(&mut self.schedule, &mut self.world, &mut self.resources);
+        }
+    }
+
+    impl Drop for Game {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.data_dir);
+        }
+    }
+
+    const VIEW_DISTANCE: u8 = 4;
+    const OPERATOR: &str = "Operator";
+
+    async fn start_server() -> (SocketAddr, Game) {
+        start_server_with(|_| ()).await
+    }
+
+    // Every server starts from an empty directory, so nothing is left from
+    // other tests or earlier runs
+    async fn start_server_with(change: impl FnOnce(&mut Config)) -> (SocketAddr, Game) {
+        let data_dir = std::env::temp_dir().join(format!("glow-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let mut config = Config {
+            online_mode: false,
+            connection_throttle: None,
+            view_distance: VIEW_DISTANCE,
+            operators: vec![OPERATOR.into()],
+            data_dir: data_dir.clone(),
+            ..Config::default()
+        };
+        change(&mut config);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Server::with_listener(&config, listener);
+        let (schedule, resources) = crate::build_game(&config, server, CommandQueue::new());
+        (address, Game { schedule, world: World::default(), resources, data_dir })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn status_test() {
+        let (address, _game) = start_server().await;
+        let mut client = Client::connect(address).await.unwrap();
+        let status: serde_json::Value = serde_json::from_str(&client.status().await.unwrap())
+            .unwrap();
+        assert_eq!(status["version"]["name"], VERSION_NAME);
+        assert_eq!(client.ping(1234).await.unwrap(), 1234);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn join_test() {
+        let (address, mut game) = start_server().await;
+        let mut client = Client::connect(address).await.unwrap();
+        assert_eq!(client.login("Tester").await.unwrap(), offline_uuid("Tester"));
+        client.send(&ServerboundPacket::ChatMessage { message: "Hello".into() }).await.unwrap();
+        let (packet_send, mut packet_recv) = unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok(packet) = client.recv().await {
+                if packet_send.send(packet).is_err() {
+                    break;
+                }
+            }
+        });
+        // Runs the game until the spawn position, sent after the first
+        // chunks, and the chat message get back
+        let mut packets = vec![];
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !packets.iter().any(|packet| kind(packet) == "SpawnPosition")
+            || !packets.iter().any(is_hello)
+        {
+            assert!(Instant::now() < deadline, "No spawn position or chat were sent");
+            game.tick();
+            sleep(Duration::from_millis(10)).await;
+            while let Ok(packet) = packet_recv.try_recv() {
+                packets.push(packet);
+            }
+        }
+
+        let ids: Vec<u32> = <&EntityId>::query().iter(&game.world).map(|id| id.0).collect();
+        match &packets[..2] {
+            [ClientboundPacket::JoinGame {
+                entity_id, gamemode, world_names, current_world, view_distance, ..
+            }, ClientboundPacket::PluginMessage { channel, content }] => {
+                assert_eq!(ids, vec![*entity_id]);
+                assert_eq!(*gamemode, 1);
+                assert_eq!(*view_distance, VIEW_DISTANCE);
+                assert!(world_names.contains(current_world));
+                assert_eq!(channel, "minecraft:brand");
+                assert!(content.ends_with(BRAND.as_bytes()));
+            }
+            _ => panic!("Expected JoinGame and the brand first"),
+        }
+        let kinds: Vec<String> = packets.iter().map(kind).collect();
+        let expected = ["JoinGame", "PluginMessage", "PlayerAbilities", "Tags", "DeclareCommands",
+            "PlayerPositionAndLook", "PlayerInfoAddPlayers", "WindowItems", "UpdateViewPosition",
+            "ChunkData", "SpawnPosition"];
+        // Anything else can come in between, like keep alives
+        let mut rest = kinds.iter();
+        for name in &expected {
+            assert!(rest.any(|kind| kind == name),
+                "{} is missing or out of order in {:?}", name, kinds);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn full_server_test() {
+        let (address, _game) = start_server_with(|config| config.max_players = 0).await;
+        let mut client = Client::connect(address).await.unwrap();
+        let error = client.login("Tester").await.unwrap_err();
+        assert_eq!(error.to_string(), "Disconnected: The server is full!");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gamemode_test() {
+        let (address, mut game) = start_server().await;
+        let mut client = Client::connect(address).await.unwrap();
+        let uuid = client.login(OPERATOR).await.unwrap();
+        // New players start in creative, the data directory is always empty
+        for command in &["/gamemode spectator", "/gamemode survival"] {
+            client.send(&ServerboundPacket::ChatMessage { message: command.to_string() })
+                .await.unwrap();
+        }
+        let (packet_send, mut packet_recv) = unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok(packet) = client.recv().await {
+                if packet_send.send(packet).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut packets = vec![];
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !packets.iter().any(|packet| matches!(packet,
+            ClientboundPacket::ChangeGameState { reason: 3, value } if *value == 0.0))
+        {
+            assert!(Instant::now() < deadline, "The gamemode didn't change");
+            game.tick();
+            sleep(Duration::from_millis(10)).await;
+            while let Ok(packet) = packet_recv.try_recv() {
+                packets.push(packet);
+            }
+        }
+        // The tab list is updated on the next tick
+        game.tick();
+        sleep(Duration::from_millis(100)).await;
+        while let Ok(packet) = packet_recv.try_recv() {
+            packets.push(packet);
+        }
+
+        let abilities = packets.iter().rev().find_map(|packet| match packet {
+            ClientboundPacket::PlayerAbilities { flags, .. } => Some(*flags),
+            _ => None,
+        });
+        assert_eq!(abilities, Some(0));
+        let changes: Vec<f32> = packets.iter()
+            .filter_map(|packet| match packet {
+                ClientboundPacket::ChangeGameState { reason: 3, value } => Some(*value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changes, [3.0, 0.0]);
+        assert!(packets.iter().any(|packet| matches!(packet,
+            ClientboundPacket::PlayerInfoUpdateGamemode(updates) if updates == &[(uuid, 0)])));
+    }
+
+    fn is_hello(packet: &ClientboundPacket) -> bool {
+        matches!(packet, ClientboundPacket::ChatMessage { message, .. }
+            if message.to_plain().contains("Hello"))
+    }
+
+    // The variant name, taken from the Debug output
+    fn kind(packet: &ClientboundPacket) -> String {
+        format!("{:?}", packet).split(|c: char| !c.is_alphanumeric()).next().unwrap().to_owned()
+    }
+}
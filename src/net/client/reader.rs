@@ -0,0 +1,513 @@
+use std::io::Cursor;
+
+use anyhow::{Result, anyhow};
+use nalgebra::Vector3;
+use nbt::Value as Nbt;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use uuid::Uuid;
+use crate::items::{ItemStack, ItemType};
+use crate::text::TextComponent;
+use crate::net::framing::read_frame;
+use crate::net::play::errors::UnknownPacket;
+use crate::net::value_readers::{MAX_STRING_LENGTH, read_block_pos, read_str, read_varint};
+use crate::net::{ChatPosition, ClientboundPacket, CommandNode, MetadataValue, PlayerInfo, PlayerInfoProperty};
+
+// The client side of `ClientboundPacket::send`, every packet the server
+// writes has to be readable here
+impl ClientboundPacket {
+    pub async fn read<R>(reader: &mut R, compression: Option<u32>) -> Result<Self>
+        where R: AsyncRead + Unpin
+    {
+        let buffer = read_frame(reader, compression).await?;
+        let mut payload = Cursor::new(buffer);
+        match payload.read_u8().await? {
+            0x00 => {
+                let entity_id = read_varint(&mut payload).await?;
+                let uuid = read_uuid(&mut payload).await?;
+                let entity_type = read_varint(&mut payload).await?;
+                let x = payload.read_f64().await?;
+                let y = payload.read_f64().await?;
+                let z = payload.read_f64().await?;
+                let pitch = read_angle(&mut payload).await?;
+                let yaw = read_angle(&mut payload).await?;
+                let data = payload.read_i32().await?;
+                let velocity = read_velocity(&mut payload).await?;
+                Ok(Self::SpawnEntity {
+                    entity_id, uuid, entity_type, x, y, z, pitch, yaw, data, velocity
+                })
+            }
+            0x04 => {
+                let entity_id = read_varint(&mut payload).await?;
+                let uuid = read_uuid(&mut payload).await?;
+                let x = payload.read_f64().await?;
+                let y = payload.read_f64().await?;
+                let z = payload.read_f64().await?;
+                let yaw = read_angle(&mut payload).await?;
+                let pitch = read_angle(&mut payload).await?;
+                Ok(Self::SpawnPlayer {
+                    entity_id, uuid, x, y, z, yaw, pitch
+                })
+            }
+            0x07 => {
+                let pos = read_block_pos(&mut payload).await?;
+                let block_state = read_varint(&mut payload).await?;
+                let status = read_varint(&mut payload).await? as u8;
+                let successful = read_bool(&mut payload).await?;
+                Ok(Self::AcknowledgePlayerDigging {
+                    pos, block_state, status, successful
+                })
+            }
+            0x08 => {
+                let entity_id = read_varint(&mut payload).await?;
+                let pos = read_block_pos(&mut payload).await?;
+                let stage = payload.read_i8().await?;
+                Ok(Self::BlockBreakAnimation {
+                    entity_id, pos, stage
+                })
+            }
+            0x0B => {
+                let pos = read_block_pos(&mut payload).await?;
+                let block_state = read_varint(&mut payload).await?;
+                Ok(Self::BlockChange {
+                    pos, block_state
+                })
+            }
+            0x0E => {
+                let message = read_text(&mut payload).await?;
+                let position = match payload.read_u8().await? {
+                    0 => ChatPosition::Chat,
+                    1 => ChatPosition::System,
+                    2 => ChatPosition::GameInfo,
+                    position => return Err(anyhow!("Invalid chat position: {}", position)),
+                };
+                let sender = read_uuid(&mut payload).await?;
+                Ok(Self::ChatMessage {
+                    message, position, sender
+                })
+            }
+            0x0F => {
+                let id = read_varint(&mut payload).await?;
+                let start = read_varint(&mut payload).await? as usize;
+                let length = read_varint(&mut payload).await? as usize;
+                let count = read_varint(&mut payload).await?;
+                let mut matches = vec![];
+                for _ in 0..count {
+                    matches.push(read_str(&mut payload, MAX_STRING_LENGTH).await?);
+                    if read_bool(&mut payload).await? {
+                        read_str(&mut payload, MAX_STRING_LENGTH).await?; // Tooltip
+                    }
+                }
+                Ok(Self::TabComplete {
+                    id, start, length, matches
+                })
+            }
+            0x10 => {
+                let count = read_varint(&mut payload).await?;
+                let mut nodes = vec![];
+                for _ in 0..count {
+                    nodes.push(read_command_node(&mut payload).await?);
+                }
+                let root = read_varint(&mut payload).await?;
+                if root != 0 {
+                    return Err(anyhow!("The root node isn't the first one"));
+                }
+                Ok(Self::DeclareCommands(nodes))
+            }
+            0x13 => {
+                let window = payload.read_u8().await?;
+                let count = payload.read_u16().await?;
+                let mut items = vec![];
+                for _ in 0..count {
+                    items.push(read_slot(&mut payload).await?);
+                }
+                Ok(Self::WindowItems {
+                    window, items
+                })
+            }
+            0x17 => {
+                let channel = read_str(&mut payload, MAX_STRING_LENGTH).await?;
+                let mut content = vec![];
+                payload.read_to_end(&mut content).await?;
+                Ok(Self::PluginMessage {
+                    channel, content
+                })
+            }
+            0x19 => {
+                let reason = read_text(&mut payload).await?;
+                Ok(Self::Disconnect {
+                    reason,
+                })
+            }
+            0x1C => {
+                let x = payload.read_i32().await?;
+                let z = payload.read_i32().await?;
+                Ok(Self::UnloadChunk(x, z))
+            }
+            0x1D => {
+                let reason = payload.read_u8().await?;
+                let value = payload.read_f32().await?;
+                Ok(Self::ChangeGameState {
+                    reason, value
+                })
+            }
+            0x1F => {
+                Ok(Self::KeepAlive(payload.read_u64().await?))
+            }
+            0x20 => {
+                let x = payload.read_i32().await?;
+                let z = payload.read_i32().await?;
+                let full = read_bool(&mut payload).await?;
+                let bitmask = read_varint(&mut payload).await? as u16;
+                let heightmap = read_nbt(&mut payload).await?
+                    .ok_or_else(|| anyhow!("Missing heightmap"))?;
+                let biomes = if full {
+                    let count = read_varint(&mut payload).await?;
+                    let mut biomes = vec![];
+                    for _ in 0..count {
+                        biomes.push(read_varint(&mut payload).await? as u16);
+                    }
+                    Some(biomes)
+                } else {
+                    None
+                };
+                let length = read_varint(&mut payload).await? as usize;
+                let mut data = vec![0; length];
+                payload.read_exact(&mut data).await?;
+                let count = read_varint(&mut payload).await?;
+                let mut block_entities = vec![];
+                for _ in 0..count {
+                    block_entities.push(read_nbt(&mut payload).await?
+                        .ok_or_else(|| anyhow!("Missing block entity"))?);
+                }
+                Ok(Self::ChunkData {
+                    x, z, full, bitmask, heightmap, biomes, data, block_entities
+                })
+            }
+            0x23 => {
+                let x = read_varint(&mut payload).await? as i32;
+                let z = read_varint(&mut payload).await? as i32;
+                let trust_edges = read_bool(&mut payload).await?;
+                let sky_mask = read_varint(&mut payload).await?;
+                let block_mask = read_varint(&mut payload).await?;
+                let empty_sky_mask = read_varint(&mut payload).await?;
+                let empty_block_mask = read_varint(&mut payload).await?;
+                let mut sky_light = vec![];
+                for _ in 0..sky_mask.count_ones() {
+                    sky_light.push(read_light_array(&mut payload).await?);
+                }
+                let mut block_light = vec![];
+                for _ in 0..block_mask.count_ones() {
+                    block_light.push(read_light_array(&mut payload).await?);
+                }
+                Ok(Self::UpdateLight {
+                    x, z, trust_edges, sky_mask, block_mask,
+                    empty_sky_mask, empty_block_mask, sky_light, block_light,
+                })
+            }
+            0x24 => {
+                let entity_id = payload.read_u32().await?;
+                payload.read_u8().await?; // Is hardcore
+                let gamemode = payload.read_u8().await?;
+                payload.read_u8().await?; // Prev gamemode
+                let count = read_varint(&mut payload).await?;
+                let mut world_names = vec![];
+                for _ in 0..count {
+                    world_names.push(read_str(&mut payload, MAX_STRING_LENGTH).await?);
+                }
+                let dimension_codec = read_raw_nbt(&mut payload).await?;
+                let dimension = read_raw_nbt(&mut payload).await?;
+                let current_world = read_str(&mut payload, MAX_STRING_LENGTH).await?;
+                payload.read_u64().await?; // Hashed seed
+                read_varint(&mut payload).await?; // Max players
+                let view_distance = read_varint(&mut payload).await? as u8;
+                Ok(Self::JoinGame {
+                    entity_id, gamemode, world_names,
+                    dimension_codec: dimension_codec.into(),
+                    dimension: dimension.into(),
+                    current_world, view_distance,
+                })
+            }
+            0x27 => {
+                let id = read_varint(&mut payload).await?;
+                let delta_x = read_position_delta(&mut payload).await?;
+                let delta_y = read_position_delta(&mut payload).await?;
+                let delta_z = read_position_delta(&mut payload).await?;
+                let on_ground = read_bool(&mut payload).await?;
+                Ok(Self::EntityPosition {
+                    id, delta_x, delta_y, delta_z, on_ground
+                })
+            }
+            0x28 => {
+                let id = read_varint(&mut payload).await?;
+                let delta_x = read_position_delta(&mut payload).await?;
+                let delta_y = read_position_delta(&mut payload).await?;
+                let delta_z = read_position_delta(&mut payload).await?;
+                let yaw = read_angle(&mut payload).await?;
+                let pitch = read_angle(&mut payload).await?;
+                let on_ground = read_bool(&mut payload).await?;
+                Ok(Self::EntityPositionAndRotation {
+                    id, delta_x, delta_y, delta_z, yaw, pitch, on_ground
+                })
+            }
+            0x29 => {
+                let id = read_varint(&mut payload).await?;
+                let yaw = read_angle(&mut payload).await?;
+                let pitch = read_angle(&mut payload).await?;
+                let on_ground = read_bool(&mut payload).await?;
+                Ok(Self::EntityRotation {
+                    id, yaw, pitch, on_ground
+                })
+            }
+            0x30 => {
+                let flags = payload.read_u8().await?;
+                let flying_speed = payload.read_f32().await?;
+                let fov_modifier = payload.read_f32().await?;
+                Ok(Self::PlayerAbilities {
+                    flags, flying_speed, fov_modifier
+                })
+            }
+            0x32 => read_player_info(&mut payload).await,
+            0x34 => {
+                let x = payload.read_f64().await?;
+                let y = payload.read_f64().await?;
+                let z = payload.read_f64().await?;
+                let yaw = payload.read_f32().await?;
+                let pitch = payload.read_f32().await?;
+                let _flags = payload.read_u8().await?;
+                let teleport_id = read_varint(&mut payload).await?;
+                Ok(Self::PlayerPositionAndLook {
+                    x, y, z, yaw, pitch, teleport_id
+                })
+            }
+            0x36 => {
+                let count = read_varint(&mut payload).await?;
+                let mut entities = vec![];
+                for _ in 0..count {
+                    entities.push(read_varint(&mut payload).await?);
+                }
+                Ok(Self::DestroyEntities(entities))
+            }
+            0x3A => {
+                let id = read_varint(&mut payload).await?;
+                let yaw = read_angle(&mut payload).await?;
+                Ok(Self::EntityHeadLook {
+                    id, yaw
+                })
+            }
+            0x40 => {
+                let x = read_varint(&mut payload).await? as i32;
+                let z = read_varint(&mut payload).await? as i32;
+                Ok(Self::UpdateViewPosition(x, z))
+            }
+            0x42 => Ok(Self::SpawnPosition(read_block_pos(&mut payload).await?)),
+            0x44 => {
+                let entity_id = read_varint(&mut payload).await?;
+                let mut metadata = vec![];
+                loop {
+                    let index = payload.read_u8().await?;
+                    if index == 0xFF {
+                        break;
+                    }
+                    let value = match read_varint(&mut payload).await? {
+                        6 => MetadataValue::Slot(read_slot(&mut payload).await?),
+                        kind => return Err(anyhow!("Unknown metadata type: {}", kind)),
+                    };
+                    metadata.push((index, value));
+                }
+                Ok(Self::EntityMetadata {
+                    entity_id, metadata
+                })
+            }
+            0x55 => {
+                let collected = read_varint(&mut payload).await?;
+                let collector = read_varint(&mut payload).await?;
+                let count = read_varint(&mut payload).await? as u8;
+                Ok(Self::CollectItem {
+                    collected, collector, count
+                })
+            }
+            0x56 => {
+                let id = read_varint(&mut payload).await?;
+                let x = payload.read_f64().await?;
+                let y = payload.read_f64().await?;
+                let z = payload.read_f64().await?;
+                let yaw = read_angle(&mut payload).await?;
+                let pitch = read_angle(&mut payload).await?;
+                let on_ground = read_bool(&mut payload).await?;
+                Ok(Self::EntityTeleport {
+                    id, x, y, z, yaw, pitch, on_ground
+                })
+            }
+            0x5B => {
+                let mut raw = vec![];
+                payload.read_to_end(&mut raw).await?;
+                Ok(Self::Tags {
+                    raw: raw.into(),
+                })
+            }
+            id => Err(UnknownPacket(id).into()),
+        }
+    }
+}
+
+async fn read_player_info(payload: &mut Cursor<Vec<u8>>) -> Result<ClientboundPacket> {
+    let action = read_varint(payload).await?;
+    let count = read_varint(payload).await?;
+    let mut uuids = vec![];
+    let mut added = vec![];
+    let mut gamemodes = vec![];
+    let mut latencies = vec![];
+    for _ in 0..count {
+        let uuid = read_uuid(payload).await?;
+        uuids.push(uuid);
+        match action {
+            0 => {
+                let name = read_str(payload, MAX_STRING_LENGTH).await?;
+                let mut properties = vec![];
+                for _ in 0..read_varint(payload).await? {
+                    let name = read_str(payload, MAX_STRING_LENGTH).await?;
+                    let value = read_str(payload, MAX_STRING_LENGTH).await?;
+                    let signature = match read_bool(payload).await? {
+                        true => Some(read_str(payload, MAX_STRING_LENGTH).await?),
+                        false => None,
+                    };
+                    properties.push(PlayerInfoProperty { name, value, signature });
+                }
+                let gamemode = read_varint(payload).await? as u8;
+                let ping = read_varint(payload).await?;
+                let display_name = match read_bool(payload).await? {
+                    true => Some(read_str(payload, MAX_STRING_LENGTH).await?),
+                    false => None,
+                };
+                added.push((uuid, PlayerInfo { name, properties, gamemode, ping, display_name }));
+            }
+            1 => gamemodes.push((uuid, read_varint(payload).await? as u8)),
+            2 => latencies.push((uuid, read_varint(payload).await? as u16)),
+            4 => (),
+            _ => return Err(anyhow!("Unknown player info action: {}", action)),
+        }
+    }
+    Ok(match action {
+        0 => ClientboundPacket::PlayerInfoAddPlayers(added),
+        1 => ClientboundPacket::PlayerInfoUpdateGamemode(gamemodes),
+        2 => ClientboundPacket::PlayerInfoUpdateLatency(latencies),
+        _ => ClientboundPacket::PlayerInfoRemovePlayers(uuids),
+    })
+}
+
+async fn read_command_node(payload: &mut Cursor<Vec<u8>>) -> Result<CommandNode> {
+    let flags = payload.read_u8().await?;
+    let executable = flags & 0x04 != 0;
+    let mut children = vec![];
+    for _ in 0..read_varint(payload).await? {
+        children.push(read_varint(payload).await?);
+    }
+    if flags & 0x08 != 0 {
+        read_varint(payload).await?; // Redirect, the server never sends them
+    }
+    match flags & 0x03 {
+        0 => Ok(CommandNode::Root { children }),
+        1 => {
+            let name = read_str(payload, MAX_STRING_LENGTH).await?;
+            Ok(CommandNode::Literal { name, executable, children })
+        }
+        2 => {
+            let name = read_str(payload, MAX_STRING_LENGTH).await?;
+            let parser = read_str(payload, MAX_STRING_LENGTH).await?;
+            let mut properties = vec![0; parser_properties_length(&parser, payload).await?];
+            payload.read_exact(&mut properties).await?;
+            let ask_server = flags & 0x10 != 0;
+            if ask_server {
+                read_str(payload, MAX_STRING_LENGTH).await?; // Suggestions type
+            }
+            Ok(CommandNode::Argument {
+                name, executable, children, ask_server,
+                parser: parser.into(),
+                properties,
+            })
+        }
+        _ => Err(anyhow!("Invalid command node type")),
+    }
+}
+
+// The properties have no length prefix, it depends on the parser and the
+// flags at their start. Doesn't consume anything
+async fn parser_properties_length(parser: &str, payload: &mut Cursor<Vec<u8>>)
+    -> Result<usize>
+{
+    let bound_size = match parser {
+        "brigadier:integer" | "brigadier:float" => 4,
+        "brigadier:long" | "brigadier:double" => 8,
+        "brigadier:string" | "minecraft:entity" | "minecraft:score_holder"
+            | "minecraft:range" => return Ok(1),
+        _ => return Ok(0),
+    };
+    let start = payload.position();
+    let flags = payload.read_u8().await?;
+    payload.set_position(start);
+    Ok(1 + bound_size * (flags & 0x03).count_ones() as usize)
+}
+
+async fn read_slot(payload: &mut Cursor<Vec<u8>>) -> Result<Option<ItemStack>> {
+    if !read_bool(payload).await? {
+        return Ok(None);
+    }
+    let item = ItemType::from_numeric(read_varint(payload).await? as u16)?;
+    let count = payload.read_u8().await?;
+    let nbt = read_nbt(payload).await?;
+    Ok(Some(ItemStack { item, count, nbt }))
+}
+
+async fn read_light_array(payload: &mut Cursor<Vec<u8>>) -> Result<Vec<u8>> {
+    let mut array = vec![0; read_varint(payload).await? as usize];
+    payload.read_exact(&mut array).await?;
+    Ok(array)
+}
+
+// A named root tag, or None if it's just TAG_End
+async fn read_nbt(payload: &mut Cursor<Vec<u8>>) -> Result<Option<Nbt>> {
+    let id = payload.read_u8().await?;
+    if id == 0 {
+        return Ok(None);
+    }
+    let name_length = payload.read_u16().await?;
+    payload.set_position(payload.position() + name_length as u64);
+    Ok(Some(Nbt::from_reader(id, payload)?))
+}
+
+// For the NBT that's sent as it is, like the dimension codec
+async fn read_raw_nbt(payload: &mut Cursor<Vec<u8>>) -> Result<Vec<u8>> {
+    let start = payload.position() as usize;
+    read_nbt(payload).await?;
+    Ok(payload.get_ref()[start..payload.position() as usize].to_vec())
+}
+
+async fn read_text(payload: &mut Cursor<Vec<u8>>) -> Result<TextComponent> {
+    let json = read_str(payload, MAX_STRING_LENGTH).await?;
+    Ok(TextComponent::from_json(&json)?)
+}
+
+async fn read_uuid(payload: &mut Cursor<Vec<u8>>) -> Result<Uuid> {
+    let mut bytes = [0; 16];
+    payload.read_exact(&mut bytes).await?;
+    Ok(Uuid::from_bytes(bytes))
+}
+
+async fn read_bool(payload: &mut Cursor<Vec<u8>>) -> Result<bool> {
+    Ok(payload.read_u8().await? != 0)
+}
+
+async fn read_angle(payload: &mut Cursor<Vec<u8>>) -> Result<f32> {
+    Ok(payload.read_u8().await? as f32 / 256.0 * 360.0)
+}
+
+async fn read_velocity(payload: &mut Cursor<Vec<u8>>) -> Result<Vector3<f64>> {
+    let x = payload.read_i16().await?;
+    let y = payload.read_i16().await?;
+    let z = payload.read_i16().await?;
+    Ok(Vector3::new(x, y, z).cast::<f64>() / 8000.0)
+}
+
+async fn read_position_delta(payload: &mut Cursor<Vec<u8>>) -> Result<f64> {
+    Ok(payload.read_i16().await? as f64 / 4096.0)
+}
@@ -0,0 +1,106 @@
+use anyhow::{Result, anyhow};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::net::ServerboundPacket;
+use crate::net::builder::PacketBuilder;
+use crate::net::framing::frame;
+
+// The client side of `ServerboundPacket::read`
+impl ServerboundPacket {
+    pub async fn send<W>(&self, writer: &mut W, compression: Option<u32>)
+        -> Result<()> where W: AsyncWrite + Unpin
+    {
+        let bytes = match self {
+            Self::TeleportConfirm { teleport_id } => {
+                PacketBuilder::new(0x00)
+                    .add_varint(*teleport_id)
+                    .build()
+            }
+            Self::ChatMessage { message } => {
+                PacketBuilder::new(0x03)
+                    .add_str(message)
+                    .build()
+            }
+            Self::TabComplete { transaction_id, text } => {
+                PacketBuilder::new(0x06)
+                    .add_varint(*transaction_id)
+                    .add_str(text)
+                    .build()
+            }
+            Self::PluginMessage { channel, data } => {
+                PacketBuilder::new(0x0B)
+                    .add_str(channel)
+                    .add_bytes(data)
+                    .build()
+            }
+            Self::KeepAlive { id } => {
+                PacketBuilder::new(0x10)
+                    .add_bytes(&id.to_be_bytes())
+                    .build()
+            }
+            Self::PlayerPosition { x, y, z, on_ground } => {
+                PacketBuilder::new(0x12)
+                    .add_bytes(&x.to_be_bytes())
+                    .add_bytes(&y.to_be_bytes())
+                    .add_bytes(&z.to_be_bytes())
+                    .add_bytes(&[*on_ground as u8])
+                    .build()
+            }
+            Self::PlayerPositionAndRotation { x, y, z, yaw, pitch, on_ground } => {
+                PacketBuilder::new(0x13)
+                    .add_bytes(&x.to_be_bytes())
+                    .add_bytes(&y.to_be_bytes())
+                    .add_bytes(&z.to_be_bytes())
+                    .add_bytes(&yaw.to_be_bytes())
+                    .add_bytes(&pitch.to_be_bytes())
+                    .add_bytes(&[*on_ground as u8])
+                    .build()
+            }
+            Self::PlayerRotation { yaw, pitch, on_ground } => {
+                PacketBuilder::new(0x14)
+                    .add_bytes(&yaw.to_be_bytes())
+                    .add_bytes(&pitch.to_be_bytes())
+                    .add_bytes(&[*on_ground as u8])
+                    .build()
+            }
+            Self::PlayerDigging { status, position, face } => {
+                PacketBuilder::new(0x1B)
+                    .add_bytes(&[*status])
+                    .add_block_position(position)
+                    .add_bytes(&[*face])
+                    .build()
+            }
+            Self::HeldItemChange { slot } => {
+                PacketBuilder::new(0x25)
+                    .add_bytes(&slot.to_be_bytes())
+                    .build()
+            }
+            Self::CreativeInventoryAction { slot, stack } => {
+                let mut pack = PacketBuilder::new(0x28);
+                pack.add_bytes(&slot.to_be_bytes())
+                    .add_bytes(&[stack.is_some() as u8]);
+                if let Some(stack) = stack {
+                    pack.add_varint(stack.item.to_numeric() as u32)
+                        .add_bytes(&[stack.count])
+                        .add_bytes(&[0]); // No NBT
+                }
+                pack.build()
+            }
+            Self::PlayerBlockPlacement { hand, location, face, cursor_position, inside_block } => {
+                PacketBuilder::new(0x2E)
+                    .add_varint(*hand as u32)
+                    .add_block_position(location)
+                    .add_varint(*face as u32)
+                    .add_bytes(&cursor_position.x.to_be_bytes())
+                    .add_bytes(&cursor_position.y.to_be_bytes())
+                    .add_bytes(&cursor_position.z.to_be_bytes())
+                    .add_bytes(&[*inside_block as u8])
+                    .build()
+            }
+            // Made up by the connection when it ends, it never goes over the wire
+            Self::Disconnect { .. } => return Err(anyhow!("Disconnect can't be sent")),
+        };
+        writer.write_all(&frame(&bytes, compression)).await?;
+        Ok(())
+    }
+}
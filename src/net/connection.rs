@@ -1,47 +1,49 @@
 use std::sync::mpsc::TryIter;
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use std::sync::Mutex;
 use std::sync::mpsc::{Sender, Receiver, channel};
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
 use super::play::{ServerboundPacket, ClientboundPacket};
+use super::queue::{PacketReceiver, PacketSender, packet_queue};
 
 pub struct PlayerConnection {
     receiver: Mutex<Receiver<ServerboundPacket>>,
-    sender: UnboundedSender<ClientboundPacket>,
+    sender: PacketSender,
 }
 
 impl PlayerConnection {
     pub fn send(&self, ev: ClientboundPacket) -> Result<()> {
-        self.sender.send(ev).map_err(|_| {
-            anyhow!("Tried to send a packet to a closed connection")
-        })
+        self.sender.send(ev)
     }
 
-    pub fn get_sender(&self) -> UnboundedSender<ClientboundPacket> {
+    pub fn get_sender(&self) -> PacketSender {
         self.sender.clone()
     }
 
+    pub fn is_overflowed(&self) -> bool {
+        self.sender.is_overflowed()
+    }
+
     pub fn receive(&mut self) -> TryIter<'_, ServerboundPacket> {
         self.receiver.get_mut().unwrap().try_iter()
     }
 }
 
 pub struct GameConnection {
-    receiver: UnboundedReceiver<ClientboundPacket>,
+    receiver: PacketReceiver,
     sender: Sender<ServerboundPacket>,
 }
 
 impl GameConnection {
     pub fn into_split(self)
-        -> (UnboundedReceiver<ClientboundPacket>, Sender<ServerboundPacket>) 
+        -> (PacketReceiver, Sender<ServerboundPacket>) 
     {
         (self.receiver, self.sender)
     }
 }
 
-pub fn connection() -> (PlayerConnection, GameConnection) {
-    let (game_send, game_recv) = unbounded_channel();
+pub fn connection(max_queued_bytes: usize) -> (PlayerConnection, GameConnection) {
+    let (game_send, game_recv) = packet_queue(max_queued_bytes);
     let (player_send, player_recv) = channel();
     (
         PlayerConnection {
@@ -0,0 +1,165 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use aes::Aes128;
+use anyhow::{Result, anyhow};
+use cfb8::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use cfb8::cipher::inout::InOutBuf;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+pub type Encryptor = cfb8::Encryptor<Aes128>;
+pub type Decryptor = cfb8::Decryptor<Aes128>;
+
+pub struct Encryption {
+    pub encryptor: Encryptor,
+    pub decryptor: Decryptor,
+}
+
+impl Encryption {
+    pub fn new(shared_secret: &[u8]) -> Result<Self> {
+        // The shared secret is used both as key and IV
+        let invalid = |_| anyhow!("Invalid shared secret length");
+        Ok(Self {
+            encryptor: Encryptor::new_from_slices(shared_secret, shared_secret)
+                .map_err(invalid)?,
+            decryptor: Decryptor::new_from_slices(shared_secret, shared_secret)
+                .map_err(invalid)?,
+        })
+    }
+
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        encrypt(&mut self.encryptor, data);
+    }
+}
+
+fn encrypt(encryptor: &mut Encryptor, data: &mut [u8]) {
+    let (blocks, _) = InOutBuf::from(data).into_chunks();
+    encryptor.encrypt_blocks_inout_mut(blocks);
+}
+
+pub struct EncryptedReader<R> {
+    inner: R,
+    decryptor: Option<Decryptor>,
+}
+
+impl<R> EncryptedReader<R> {
+    pub fn new(inner: R, decryptor: Option<Decryptor>) -> Self {
+        Self { inner, decryptor }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EncryptedReader<R> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>)
+        -> Poll<io::Result<()>>
+    {
+        let this = self.get_mut();
+        let already_filled = buf.filled().len();
+        match Pin::new(&mut this.inner).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) => {
+                if let Some(decryptor) = &mut this.decryptor {
+                    let (blocks, _) = InOutBuf::from(&mut buf.filled_mut()[already_filled..])
+                        .into_chunks();
+                    decryptor.decrypt_blocks_inout_mut(blocks);
+                }
+                Poll::Ready(Ok(()))
+            }
+            other => other,
+        }
+    }
+}
+
+// Writes are encrypted as soon as they're accepted, so any bytes the inner
+// writer couldn't take yet are kept until the next write or flush
+pub struct EncryptedWriter<W> {
+    inner: W,
+    encryptor: Option<Encryptor>,
+    pending: Vec<u8>,
+}
+
+impl<W> EncryptedWriter<W> {
+    pub fn new(inner: W, encryptor: Option<Encryptor>) -> Self {
+        Self { inner, encryptor, pending: vec![] }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> EncryptedWriter<W> {
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            match Pin::new(&mut self.inner).poll_write(cx, &self.pending) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                }
+                Poll::Ready(Ok(written)) => {
+                    self.pending.drain(..written);
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EncryptedWriter<W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8])
+        -> Poll<io::Result<usize>>
+    {
+        let this = self.get_mut();
+        if this.encryptor.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+        if let Poll::Ready(Err(e)) = this.poll_pending(cx) {
+            return Poll::Ready(Err(e));
+        }
+        if !this.pending.is_empty() {
+            return Poll::Pending;
+        }
+        let mut encrypted = buf.to_vec();
+        encrypt(this.encryptor.as_mut().unwrap(), &mut encrypted);
+        this.pending = encrypted;
+        if let Poll::Ready(Err(e)) = this.poll_pending(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_pending(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+            other => other,
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_pending(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_shutdown(cx),
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use super::{Encryption, EncryptedReader, EncryptedWriter};
+
+    #[tokio::test]
+    async fn round_trip_test() {
+        let secret = [7; 16];
+        let message = b"Some packet bytes, split in two writes";
+        let mut writer = EncryptedWriter::new(vec![],
+            Some(Encryption::new(&secret).unwrap().encryptor));
+        writer.write_all(&message[..10]).await.unwrap();
+        writer.write_all(&message[10..]).await.unwrap();
+        writer.flush().await.unwrap();
+        let encrypted = writer.inner;
+        assert_ne!(encrypted.as_slice(), &message[..]);
+        let mut reader = EncryptedReader::new(encrypted.as_slice(),
+            Some(Encryption::new(&secret).unwrap().decryptor));
+        let mut decrypted = vec![];
+        reader.read_to_end(&mut decrypted).await.unwrap();
+        assert_eq!(decrypted.as_slice(), &message[..]);
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{Read, Write};
+use anyhow::{Result, anyhow};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use crate::serialization::push_varint;
+use super::value_readers::read_varint;
+
+// Biggest length that fits in the 3 byte varint vanilla uses for frames
+pub const MAX_FRAME_LENGTH: usize = 2097151;
+// Vanilla's limit on the uncompressed size of a compressed packet
+pub const MAX_PACKET_LENGTH: usize = 2097152;
+
+// `compression` is the threshold sent in Set Compression, packets smaller
+// than it are sent uncompressed. None means compression isn't enabled yet
+pub fn frame(packet: &[u8], compression: Option<u32>) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(packet.len() + 10);
+    match compression {
+        None => {
+            push_varint(packet.len() as u32, &mut frame);
+            frame.extend_from_slice(packet);
+        }
+        Some(threshold) if packet.len() < threshold as usize => {
+            push_varint(packet.len() as u32 + 1, &mut frame);
+            frame.push(0); // Data length of 0 marks an uncompressed packet
+            frame.extend_from_slice(packet);
+        }
+        Some(_) => {
+            let mut body = vec![];
+            push_varint(packet.len() as u32, &mut body);
+            let mut encoder = ZlibEncoder::new(body, Compression::default());
+            encoder.write_all(packet).unwrap();
+            let body = encoder.finish().unwrap();
+            push_varint(body.len() as u32, &mut frame);
+            frame.extend(body);
+        }
+    }
+    frame
+}
+
+pub async fn read_frame<R>(reader: &mut R, compression: Option<u32>)
+    -> Result<Vec<u8>> where R: AsyncRead + Unpin
+{
+    let length = read_varint(reader).await? as usize;
+    if length == 0 {
+        return Err(anyhow!("Packet length cannot be 0"));
+    }
+    if length > MAX_FRAME_LENGTH {
+        return Err(anyhow!("Packet is too long ({} > {})", length, MAX_FRAME_LENGTH));
+    }
+    let mut buffer = vec![0; length];
+    reader.read_exact(buffer.as_mut()).await?;
+    let threshold = match compression {
+        Some(threshold) => threshold as usize,
+        None => return Ok(buffer),
+    };
+    let mut body = buffer.as_slice();
+    let data_length = read_varint(&mut body).await? as usize;
+    if data_length == 0 {
+        return Ok(body.to_vec());
+    }
+    if data_length < threshold {
+        return Err(anyhow!("Compressed packet is below the threshold ({} < {})",
+            data_length, threshold));
+    }
+    if data_length > MAX_PACKET_LENGTH {
+        return Err(anyhow!("Packet is too long ({} > {})", data_length, MAX_PACKET_LENGTH));
+    }
+    let mut packet = Vec::with_capacity(data_length);
+    // Never inflate more than announced, in case it's a zip bomb
+    ZlibDecoder::new(body).take(data_length as u64 + 1).read_to_end(&mut packet)?;
+    if packet.len() != data_length {
+        return Err(anyhow!("Uncompressed packet length doesn't match"));
+    }
+    Ok(packet)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::serialization::push_varint;
+    use super::{frame, read_frame};
+
+    #[tokio::test]
+    async fn uncompressed_test() {
+        let packet = vec![0x1F, 1, 2, 3];
+        let framed = frame(&packet, None);
+        assert_eq!(framed, vec![4, 0x1F, 1, 2, 3]);
+        assert_eq!(read_frame(&mut framed.as_slice(), None).await.unwrap(), packet);
+    }
+
+    #[tokio::test]
+    async fn below_threshold_test() {
+        let packet = vec![0x1F, 1, 2, 3];
+        let framed = frame(&packet, Some(256));
+        assert_eq!(framed, vec![5, 0, 0x1F, 1, 2, 3]);
+        assert_eq!(read_frame(&mut framed.as_slice(), Some(256)).await.unwrap(), packet);
+    }
+
+    #[tokio::test]
+    async fn compressed_test() {
+        let packet = vec![0x20; 1000];
+        let framed = frame(&packet, Some(256));
+        assert!(framed.len() < packet.len());
+        assert_eq!(read_frame(&mut framed.as_slice(), Some(256)).await.unwrap(), packet);
+    }
+
+    #[tokio::test]
+    async fn too_long_test() {
+        let framed = vec![0x80, 0x80, 0x80, 0x01];
+        assert!(read_frame(&mut framed.as_slice(), None).await.is_err());
+        // Claims to inflate to 16MB
+        let mut body = vec![];
+        push_varint(16 * 1024 * 1024, &mut body);
+        body.extend(&frame(&[0x20; 1000], Some(256))[3..]);
+        let mut framed = vec![];
+        push_varint(body.len() as u32, &mut framed);
+        framed.extend(body);
+        assert!(read_frame(&mut framed.as_slice(), Some(256)).await.is_err());
+    }
+}
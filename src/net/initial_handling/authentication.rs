@@ -0,0 +1,147 @@
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use rand::rngs::OsRng;
+use reqwest::StatusCode;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use rsa::pkcs8::EncodePublicKey;
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
+use crate::net::PlayerInfoProperty;
+
+const KEY_BITS: usize = 1024;
+// A session server that hangs would keep the login open forever
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Authenticator {
+    key: RsaPrivateKey,
+    public_key: Vec<u8>,
+    session_server: String,
+    http: reqwest::Client,
+}
+
+impl Authenticator {
+    pub fn new(session_server: &str) -> Self {
+        let key = RsaPrivateKey::new(&mut OsRng, KEY_BITS)
+            .expect("Couldn't generate the server's key pair");
+        let public_key = RsaPublicKey::from(&key)
+            .to_public_key_der().unwrap()
+            .as_bytes().to_vec();
+        Self {
+            key,
+            public_key,
+            session_server: session_server.to_owned(),
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Couldn't create the session server client"),
+        }
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.key.decrypt(Pkcs1v15Encrypt, data)?)
+    }
+
+    pub async fn has_joined(&self, name: &str, shared_secret: &[u8])
+        -> Result<(Uuid, String, Vec<PlayerInfoProperty>)>
+    {
+        let server_hash = server_hash(shared_secret, &self.public_key);
+        let response = self.http.get(&self.session_server)
+            .query(&[("username", name), ("serverId", server_hash.as_str())])
+            .send().await?;
+        if response.status() != StatusCode::OK {
+            return Err(anyhow!("Session server couldn't verify {}", name));
+        }
+        let profile: Profile = response.json().await?;
+        Ok((Uuid::parse_str(&profile.id)?, profile.name, profile.properties))
+    }
+}
+
+#[derive(Deserialize)]
+struct Profile {
+    id: String,
+    name: String,
+    #[serde(default)]
+    properties: Vec<PlayerInfoProperty>,
+}
+
+fn server_hash(shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(b""); // Server ID, always empty since 1.7
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    minecraft_hex_digest(hasher.finalize().into())
+}
+
+// Minecraft prints the hash as a signed big endian number,
+// with a minus sign instead of two's complement
+fn minecraft_hex_digest(mut digest: [u8; 20]) -> String {
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            *byte = !*byte;
+            if carry {
+                let (sum, overflow) = byte.overflowing_add(1);
+                *byte = sum;
+                carry = overflow;
+            }
+        }
+    }
+    let hex: String = digest.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let hex = hex.trim_start_matches('0');
+    if negative {
+        format!("-{}", hex)
+    } else {
+        hex.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sha1::{Digest, Sha1};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use uuid::Uuid;
+    use super::{Authenticator, minecraft_hex_digest};
+
+    fn digest_of(name: &str) -> String {
+        minecraft_hex_digest(Sha1::digest(name.as_bytes()).into())
+    }
+
+    #[test]
+    fn hex_digest_test() {
+        assert_eq!(digest_of("Notch"), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(digest_of("jeb_"), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(digest_of("simon"), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+
+    #[tokio::test]
+    async fn has_joined_test() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hasJoined", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 1024];
+            let len = conn.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..len]).to_string();
+            assert!(request.starts_with("GET /hasJoined?username=Notch&serverId="));
+            let body = r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[]}"#;
+            let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                Content-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+            conn.write_all(response.as_bytes()).await.unwrap();
+        });
+        let auth = Authenticator::new(&url);
+        let (uuid, name, _) = auth.has_joined("Notch", &[1; 16]).await.unwrap();
+        assert_eq!(uuid, Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap());
+        assert_eq!(name, "Notch");
+    }
+}
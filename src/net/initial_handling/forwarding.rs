@@ -0,0 +1,109 @@
+use std::net::IpAddr;
+use anyhow::{Result, anyhow};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+use crate::net::PlayerInfoProperty;
+use crate::net::value_readers::{read_str, read_varint, MAX_STRING_LENGTH};
+
+use super::NewPlayer;
+
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+pub const VELOCITY_VERSION: u8 = 1;
+
+// BungeeCord appends the player's address, UUID and properties to 
+// the host name in the handshake, separated by null characters
+pub fn read_bungeecord(host_name: &str, name: String) -> Result<NewPlayer> {
+    let parts: Vec<&str> = host_name.split('\0').collect();
+    if parts.len() < 3 {
+        return Err(anyhow!("No player info was forwarded by BungeeCord"));
+    }
+    let properties = match parts.get(3) {
+        Some(properties) => serde_json::from_str(properties)?,
+        None => vec![],
+    };
+    Ok(NewPlayer {
+        uuid: Uuid::parse_str(parts[2])?,
+        name,
+        properties,
+        address: parts[1].parse()?,
+    })
+}
+
+// Velocity sends the player's info as a response to a login plugin request,
+// signed with the secret it shares with the server
+pub async fn read_velocity(data: &[u8], secret: &str) -> Result<NewPlayer> {
+    if data.len() < 32 {
+        return Err(anyhow!("Forwarded player info is too short"));
+    }
+    let (signature, mut data) = data.split_at(32);
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(data);
+    mac.verify_slice(signature)
+        .map_err(|_| anyhow!("Forwarded player info has an invalid signature"))?;
+    let version = read_varint(&mut data).await?;
+    if version != VELOCITY_VERSION as u32 {
+        return Err(anyhow!("Unsupported forwarding version: {}", version));
+    }
+    let address: IpAddr = read_str(&mut data, MAX_STRING_LENGTH).await?.parse()?;
+    let uuid = Uuid::from_u128(data.read_u128().await?);
+    let name = read_str(&mut data, MAX_STRING_LENGTH).await?;
+    let property_count = read_varint(&mut data).await?;
+    let mut properties = vec![];
+    for _ in 0..property_count {
+        let name = read_str(&mut data, MAX_STRING_LENGTH).await?;
+        let value = read_str(&mut data, MAX_STRING_LENGTH).await?;
+        let signature = if data.read_u8().await? != 0 {
+            Some(read_str(&mut data, MAX_STRING_LENGTH).await?)
+        } else {
+            None
+        };
+        properties.push(PlayerInfoProperty { name, value, signature });
+    }
+    Ok(NewPlayer { uuid, name, properties, address })
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use uuid::Uuid;
+    use crate::serialization::push_varint;
+    use super::{read_bungeecord, read_velocity};
+
+    const UUID: &str = "069a79f444e94726a5befca90e38aaf5";
+
+    #[test]
+    fn bungeecord_test() {
+        let host = format!("localhost\u{0}10.0.0.7\u{0}{}\u{0}\
+            [{{\"name\":\"textures\",\"value\":\"abc\",\"signature\":\"def\"}}]", UUID);
+        let player = read_bungeecord(&host, "Notch".into()).unwrap();
+        assert_eq!(player.uuid, Uuid::parse_str(UUID).unwrap());
+        assert_eq!(player.address.to_string(), "10.0.0.7");
+        assert_eq!(player.properties[0].value, "abc");
+        assert_eq!(player.properties[0].signature.as_deref(), Some("def"));
+        assert!(read_bungeecord("localhost", "Notch".into()).is_err());
+    }
+
+    #[tokio::test]
+    async fn velocity_test() {
+        let mut data = vec![];
+        push_varint(1, &mut data);
+        push_varint(8, &mut data);
+        data.extend_from_slice(b"10.0.0.7");
+        data.extend_from_slice(Uuid::parse_str(UUID).unwrap().as_bytes());
+        push_varint(5, &mut data);
+        data.extend_from_slice(b"Notch");
+        push_varint(0, &mut data);
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(&data);
+        let mut signed = mac.finalize().into_bytes().to_vec();
+        signed.extend(data);
+        let player = read_velocity(&signed, "secret").await.unwrap();
+        assert_eq!(player.name, "Notch");
+        assert_eq!(player.uuid, Uuid::parse_str(UUID).unwrap());
+        assert_eq!(player.address.to_string(), "10.0.0.7");
+        assert!(read_velocity(&signed, "wrong secret").await.is_err());
+    }
+}
@@ -1,21 +1,37 @@
 use tokio::net::TcpStream;
 use anyhow::{Result, anyhow};
-use crate::net::value_readers::{read_varint, read_str};
+use crate::config::Forwarding;
+use crate::net::value_readers::{read_varint, read_str, MAX_STRING_LENGTH};
 use tokio::io::{AsyncRead, AsyncReadExt};
 
 pub enum Intent {
     Login, Status
 }
 
-pub async fn handshaking(conn: &mut TcpStream) -> Result<Intent> {
-    let packet = read_packet(conn).await?;
+pub struct Handshake {
+    pub proto_version: u32,
+    pub intent: Intent,
+    pub host_name: String,
+}
+
+// Vanilla's limit on the server address
+const MAX_HOST_LENGTH: usize = 255;
+
+pub async fn handshaking(conn: &mut TcpStream, forwarding: &Forwarding) -> Result<Handshake> {
+    // BungeeCord appends the forwarded player info to the address
+    let max_host_length = match forwarding {
+        Forwarding::BungeeCord => MAX_STRING_LENGTH,
+        _ => MAX_HOST_LENGTH,
+    };
+    let packet = read_packet(conn, max_host_length).await?;
     match packet {
-        ServerboundPacket::Handshake { intent, .. } => {
-            match intent {
-                1 => Ok(Intent::Status),
-                2 => Ok(Intent::Login),
-                _ => Err(anyhow!("Invalid packet")),
-            }
+        ServerboundPacket::Handshake { proto_version, intent, host_name, .. } => {
+            let intent = match intent {
+                1 => Intent::Status,
+                2 => Intent::Login,
+                _ => return Err(anyhow!("Invalid packet")),
+            };
+            Ok(Handshake { proto_version, intent, host_name })
         },
     }
 }
@@ -29,7 +45,7 @@ pub enum ServerboundPacket {
     }
 }
 
-pub async fn read_packet<R>(reader: &mut R) -> Result<ServerboundPacket>
+pub async fn read_packet<R>(reader: &mut R, max_host_length: usize) -> Result<ServerboundPacket>
     where R: AsyncRead + Unpin
 {
     let _len = read_varint(reader).await?;
@@ -38,7 +54,7 @@ pub async fn read_packet<R>(reader: &mut R) -> Result<ServerboundPacket>
         0x00 => Ok(
             ServerboundPacket::Handshake {
                 proto_version: read_varint(reader).await?,
-                host_name: read_str(reader).await?,
+                host_name: read_str(reader, max_host_length).await?,
                 port: reader.read_u16().await?,
                 intent: read_varint(reader).await?,
             }),
@@ -0,0 +1,145 @@
+use std::time::Duration;
+use anyhow::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{Instant, sleep, timeout};
+
+// How long to wait for the bytes after 0xFE, old clients may send nothing else
+const LEGACY_WAIT: Duration = Duration::from_millis(100);
+const PEEK_INTERVAL: Duration = Duration::from_millis(10);
+// For the rest of MC|PingHost once its 0xFA arrived
+const PING_HOST_TIMEOUT: Duration = Duration::from_secs(1);
+const PLUGIN_MESSAGE_ID: u8 = 0xFA;
+
+// Pre-1.7 clients start with 0xFE, but so does a modern handshake whose
+// length varint begins with that byte, like a 254 byte one (FE 01 00 ...).
+// Legacy pings are a lone 0xFE, or 0xFE 0x01 alone or followed by the
+// 0xFA of the MC|PingHost plugin message. Nothing is consumed
+pub async fn is_legacy_ping(conn: &mut TcpStream) -> Result<bool> {
+    let mut start = [0; 3];
+    let mut read = conn.peek(&mut start).await?;
+    if read == 0 || start[0] != 0xFE {
+        return Ok(false);
+    }
+    // Peeking doesn't wait for more bytes than are already there
+    let deadline = Instant::now() + LEGACY_WAIT;
+    while read < start.len() && Instant::now() < deadline {
+        sleep(PEEK_INTERVAL).await;
+        read = conn.peek(&mut start).await?;
+    }
+    Ok(matches!(start[..read], [0xFE] | [0xFE, 0x01] | [0xFE, 0x01, 0xFA]))
+}
+
+pub enum LegacyFormat {
+    // Beta 1.8 to 1.3, just 0xFE
+    Beta,
+    // 1.4 to 1.6, 0xFE01 optionally followed by a MC|PingHost plugin message
+    Extended,
+}
+
+pub async fn read_legacy_ping(conn: &mut TcpStream) -> Result<LegacyFormat> {
+    conn.read_u8().await?;
+    // Beta clients send nothing after 0xFE, so don't wait for them forever
+    match timeout(LEGACY_WAIT, conn.read_u8()).await {
+        Ok(Ok(0x01)) => (),
+        _ => return Ok(LegacyFormat::Beta),
+    }
+    // 1.6 clients follow with MC|PingHost. It has to be read, closing the
+    // socket with unread data resets the connection and the response may
+    // never arrive
+    if let Ok(Ok(PLUGIN_MESSAGE_ID)) = timeout(LEGACY_WAIT, conn.read_u8()).await {
+        let _ = timeout(PING_HOST_TIMEOUT, skip_ping_host(conn)).await;
+    }
+    Ok(LegacyFormat::Extended)
+}
+
+// The channel name in UTF-16 code units and the data in bytes, both
+// prefixed by their length as a short
+async fn skip_ping_host(conn: &mut TcpStream) -> Result<()> {
+    let name_length = conn.read_u16().await? as usize;
+    skip(conn, name_length * 2).await?;
+    let data_length = conn.read_u16().await? as usize;
+    skip(conn, data_length).await
+}
+
+async fn skip(conn: &mut TcpStream, length: usize) -> Result<()> {
+    let mut buffer = vec![0; length];
+    conn.read_exact(&mut buffer).await?;
+    Ok(())
+}
+
+pub async fn send_legacy_response(conn: &mut TcpStream, response: &str) -> Result<()> {
+    conn.write_all(&kick_packet(response)).await?;
+    conn.flush().await?;
+    Ok(())
+}
+
+// Kick packet: 0xFF, the string length in UTF-16 code units and the UTF-16BE string
+fn kick_packet(message: &str) -> Vec<u8> {
+    let chars: Vec<u16> = message.encode_utf16().collect();
+    let mut bytes = Vec::with_capacity(3 + chars.len() * 2);
+    bytes.push(0xFF);
+    bytes.extend_from_slice(&(chars.len() as u16).to_be_bytes());
+    for c in chars {
+        bytes.extend_from_slice(&c.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use super::{LegacyFormat, is_legacy_ping, kick_packet, read_legacy_ping};
+
+    #[test]
+    fn kick_packet_test() {
+        assert_eq!(kick_packet("§1\0"), vec![0xFF, 0, 3, 0x00, 0xA7, 0x00, 0x31, 0x00, 0x00]);
+    }
+
+    async fn detect(sent: &[u8]) -> bool {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        client.write_all(sent).await.unwrap();
+        let (mut conn, _) = listener.accept().await.unwrap();
+        is_legacy_ping(&mut conn).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn legacy_ping_test() {
+        assert!(detect(&[0xFE]).await);
+        assert!(detect(&[0xFE, 0x01]).await);
+        assert!(detect(&[0xFE, 0x01, 0xFA, 0x00, 0x0B]).await);
+        assert!(!detect(&[0x10, 0x00]).await);
+        // A 254 byte handshake, its length is FE 01 as a varint
+        let mut handshake = vec![0xFE, 0x01, 0x00];
+        handshake.resize(2 + 254, 0);
+        assert!(!detect(&handshake).await);
+    }
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|c| c.to_be_bytes()).collect()
+    }
+
+    #[tokio::test]
+    async fn ping_host_test() {
+        // What a 1.6.4 client sends to localhost:25565
+        let mut sent = vec![0xFE, 0x01, 0xFA, 0x00, 0x0B];
+        sent.extend(utf16("MC|PingHost"));
+        let host = utf16("localhost");
+        sent.extend_from_slice(&(7 + host.len() as u16).to_be_bytes());
+        sent.push(78);
+        sent.extend_from_slice(&(host.len() as u16 / 2).to_be_bytes());
+        sent.extend(host);
+        sent.extend_from_slice(&25565i32.to_be_bytes());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        client.write_all(&sent).await.unwrap();
+        client.shutdown().await.unwrap();
+        let (mut conn, _) = listener.accept().await.unwrap();
+        let format = read_legacy_ping(&mut conn).await.unwrap();
+        assert!(matches!(format, LegacyFormat::Extended));
+        // Everything was read
+        assert_eq!(conn.read(&mut [0; 1]).await.unwrap(), 0);
+    }
+}
@@ -1,62 +1,286 @@
+use std::net::IpAddr;
+use std::sync::RwLock;
 use tokio::{io::AsyncWriteExt, net::TcpStream};
-use uuid::Uuid;
+use uuid::{Builder, Uuid, Variant, Version};
+use crate::config::Forwarding;
+use crate::text::TextComponent;
+use crate::access::AccessLists;
+use crate::net::server_info::ServerInfo;
 use crate::net::{
+    PROTOCOL_VERSION, VERSION_NAME,
     builder::PacketBuilder, 
-    value_readers::{read_str, read_varint}};
-use tokio::io::{AsyncRead, AsyncWrite};
+    encryption::Encryption,
+    framing::{frame, read_frame},
+    settings::ConnectionSettings,
+    value_readers::{read_bytes, read_str, read_varint}};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
 use anyhow::{anyhow, Result};
 
-const UUID_NAMESPACE: &Uuid = &Uuid::nil();
+use super::NewPlayer;
+use super::authentication::Authenticator;
+use super::handshaking::Handshake;
+use super::forwarding::{
+    VELOCITY_CHANNEL, VELOCITY_VERSION, 
+    read_bungeecord, read_velocity};
 
-pub async fn login(conn: &mut TcpStream) -> Result<(Uuid, String)> {
-    let packet = read_pack(conn).await?;
-    match packet {
-        ServerboundPacket::Login { name } => {
-            let uuid = Uuid::new_v3(UUID_NAMESPACE, name.as_bytes());
-            let response = ClientboundPacket::Success(uuid, name.clone());
-            response.send(conn).await?;
-            Ok((uuid, name))
+const MAX_NAME_LENGTH: usize = 16;
+// Encrypted with the server's 1024 bit key, so 128 bytes in practice
+const MAX_ENCRYPTED_LENGTH: usize = 256;
+
+pub async fn login(conn: &mut TcpStream, address: IpAddr, handshake: &Handshake,
+    settings: &ConnectionSettings, access: &RwLock<AccessLists>, info: &RwLock<ServerInfo>)
+    -> Result<(NewPlayer, Option<Encryption>)> 
+{
+    // Read before answering, like vanilla does. Closing the socket with
+    // Login Start still unread would reset the connection, and the client
+    // would show that instead of the reason. Other versions may have
+    // changed the packet, so it only has to be valid if the version is ours
+    let login_start = read_pack(conn).await;
+    if handshake.proto_version != PROTOCOL_VERSION {
+        let reason = if handshake.proto_version < PROTOCOL_VERSION {
+            format!("Outdated client! Please use {}", VERSION_NAME)
+        } else {
+            format!("Outdated server! I'm still on {}", VERSION_NAME)
+        };
+        ClientboundPacket::Disconnect(reason.into())
+            .send(conn, None).await?;
+        return Err(anyhow!("Unsupported protocol version: {}", 
+            handshake.proto_version));
+    }
+    let name = match login_start? {
+        ServerboundPacket::Login { name } => name,
+        _ => return Err(anyhow!("Expected login start")),
+    };
+    let full = info.read().unwrap().is_full();
+    if full {
+        return refuse(conn, &mut None, &name, "The server is full!".into()).await;
+    }
+    // Behind a proxy the real address is only known after forwarding
+    let forwarded = !matches!(settings.forwarding, Forwarding::None);
+    if !forwarded && is_throttled(settings, address) {
+        return refuse(conn, &mut None, &address.to_string(), throttled_message()).await;
+    }
+    let (player, mut encryption) = match (&settings.forwarding, &settings.auth) {
+        (Forwarding::BungeeCord, _) => {
+            (read_bungeecord(&handshake.host_name, name)?, None)
+        }
+        (Forwarding::Velocity { secret }, _) => {
+            (velocity_forwarding(conn, secret).await?, None)
+        }
+        (Forwarding::None, Some(auth)) => {
+            let (player, encryption) = authenticate(conn, auth, name, address).await?;
+            (player, Some(encryption))
+        }
+        (Forwarding::None, None) => {
+            let player = NewPlayer {
+                uuid: offline_uuid(&name),
+                name,
+                properties: vec![],
+                address,
+            };
+            (player, None)
+        }
+    };
+    if forwarded && is_throttled(settings, player.address) {
+        return refuse(conn, &mut encryption, &player.name, throttled_message()).await;
+    }
+    let refusal = access.read().unwrap()
+        .check(player.uuid, &player.name, player.address);
+    if let Some(reason) = refusal {
+        return refuse(conn, &mut encryption, &player.name, reason).await;
+    }
+    let compression = settings.compression;
+    let mut response = vec![];
+    if let Some(threshold) = compression {
+        ClientboundPacket::SetCompression(threshold)
+            .send(&mut response, None).await?;
+    }
+    ClientboundPacket::Success(player.uuid, player.name.clone())
+        .send(&mut response, compression).await?;
+    if let Some(encryption) = &mut encryption {
+        encryption.encrypt(&mut response);
+    }
+    conn.write_all(&response).await?;
+    Ok((player, encryption))
+}
+
+async fn authenticate(conn: &mut TcpStream, auth: &Authenticator, name: String,
+    address: IpAddr) -> Result<(NewPlayer, Encryption)>
+{
+    let verify_token: [u8; 4] = rand::random();
+    ClientboundPacket::EncryptionRequest {
+        public_key: auth.public_key().to_vec(),
+        verify_token: verify_token.to_vec(),
+    }.send(conn, None).await?;
+    match read_pack(conn).await? {
+        ServerboundPacket::EncryptionResponse { shared_secret, verify_token: token } => {
+            if auth.decrypt(&token)? != verify_token {
+                return Err(anyhow!("Verify token doesn't match"));
+            }
+            let shared_secret = auth.decrypt(&shared_secret)?;
+            let encryption = Encryption::new(&shared_secret)?;
+            let (uuid, name, properties) = auth.has_joined(&name, &shared_secret).await?;
+            Ok((NewPlayer { uuid, name, properties, address }, encryption))
         }
+        _ => Err(anyhow!("Expected encryption response")),
     }
 }
 
+async fn velocity_forwarding(conn: &mut TcpStream, secret: &str) -> Result<NewPlayer> {
+    let message_id = rand::random::<u16>() as u32;
+    ClientboundPacket::LoginPluginRequest {
+        message_id,
+        channel: VELOCITY_CHANNEL.into(),
+        data: vec![VELOCITY_VERSION],
+    }.send(conn, None).await?;
+    match read_pack(conn).await? {
+        ServerboundPacket::LoginPluginResponse { message_id: id, data: Some(data) } 
+            if id == message_id => read_velocity(&data, secret).await,
+        _ => Err(anyhow!("This server only accepts connections through Velocity")),
+    }
+}
+
+fn is_throttled(settings: &ConnectionSettings, address: IpAddr) -> bool {
+    settings.throttle.as_ref()
+        .is_some_and(|throttle| !throttle.try_connect(address))
+}
+
+fn throttled_message() -> TextComponent {
+    "Connection throttled! Please wait before reconnecting.".into()
+}
+
+// Sends a login Disconnect, encrypted if encryption was already enabled
+async fn refuse<T>(conn: &mut TcpStream, encryption: &mut Option<Encryption>, who: &str,
+    reason: TextComponent) -> Result<T>
+{
+    let mut packet = vec![];
+    ClientboundPacket::Disconnect(reason.clone())
+        .send(&mut packet, None).await?;
+    if let Some(encryption) = encryption {
+        encryption.encrypt(&mut packet);
+    }
+    conn.write_all(&packet).await?;
+    log!("Refused {}: {}", who, reason.to_plain());
+    Err(anyhow!("Refused {}", who))
+}
+
+// Same UUIDs vanilla gives to offline players, so player files can be shared
+pub fn offline_uuid(name: &str) -> Uuid {
+    let digest = md5::compute(format!("OfflinePlayer:{}", name));
+    Builder::from_bytes(digest.0)
+        .set_variant(Variant::RFC4122)
+        .set_version(Version::Md5)
+        .build()
+}
+
 pub enum ServerboundPacket {
     Login {
         name: String,
-    }
+    },
+    EncryptionResponse {
+        shared_secret: Vec<u8>,
+        verify_token: Vec<u8>,
+    },
+    LoginPluginResponse {
+        message_id: u32,
+        data: Option<Vec<u8>>,
+    },
 }
 
 pub async fn read_pack<R>(reader: &mut R) -> Result<ServerboundPacket>
     where R: AsyncRead + Unpin
 {
-    let _length = read_varint(reader).await?;
-    let id = read_varint(reader).await?;
+    let packet = read_frame(reader, None).await?;
+    let mut payload = packet.as_slice();
+    let id = read_varint(&mut payload).await?;
     match id {
         0x00 => Ok(
             ServerboundPacket::Login {
-                name: read_str(reader).await?,
+                name: read_str(&mut payload, MAX_NAME_LENGTH).await?,
             }),
+        0x01 => Ok(
+            ServerboundPacket::EncryptionResponse {
+                shared_secret: read_bytes(&mut payload, MAX_ENCRYPTED_LENGTH).await?,
+                verify_token: read_bytes(&mut payload, MAX_ENCRYPTED_LENGTH).await?,
+            }),
+        0x02 => {
+            let message_id = read_varint(&mut payload).await?;
+            let successful = payload.read_u8().await? != 0;
+            Ok(ServerboundPacket::LoginPluginResponse {
+                message_id,
+                data: if successful { Some(payload.to_vec()) } else { None },
+            })
+        }
         _ => Err(anyhow!("Invalid packet"))
     }
 }
 
 pub enum ClientboundPacket {
+    Disconnect(TextComponent),
+    EncryptionRequest {
+        public_key: Vec<u8>,
+        verify_token: Vec<u8>,
+    },
     Success(Uuid, String),
+    SetCompression(u32),
+    LoginPluginRequest {
+        message_id: u32,
+        channel: String,
+        data: Vec<u8>,
+    },
 }
 
 impl ClientboundPacket {
-    pub async fn send<W>(&self, writer: &mut W) -> Result<()>
-        where W: AsyncWrite + Unpin
+    pub async fn send<W>(&self, writer: &mut W, compression: Option<u32>) 
+        -> Result<()> where W: AsyncWrite + Unpin
     {
-        match self {
+        let bytes = match self {
+            ClientboundPacket::Disconnect(reason) => {
+                PacketBuilder::new(0x00)
+                    .add_str(&reason.to_json())
+                    .build()
+            }
+            ClientboundPacket::EncryptionRequest { public_key, verify_token } => {
+                PacketBuilder::new(0x01)
+                    .add_str("") // Server ID, unused
+                    .add_varint(public_key.len() as u32)
+                    .add_bytes(public_key)
+                    .add_varint(verify_token.len() as u32)
+                    .add_bytes(verify_token)
+                    .build()
+            }
             ClientboundPacket::Success(uuid, name) => {
-                let bytes = PacketBuilder::new(0x02)
+                PacketBuilder::new(0x02)
                     .add_bytes(uuid.as_bytes())
                     .add_str(name.as_str())
-                    .build();
-                writer.write_all(&bytes).await?;
-                Ok(())
+                    .build()
             }
-        }
+            ClientboundPacket::SetCompression(threshold) => {
+                PacketBuilder::new(0x03)
+                    .add_varint(*threshold)
+                    .build()
+            }
+            ClientboundPacket::LoginPluginRequest { message_id, channel, data } => {
+                PacketBuilder::new(0x04)
+                    .add_varint(*message_id)
+                    .add_str(channel)
+                    .add_bytes(data)
+                    .build()
+            }
+        };
+        writer.write_all(&frame(&bytes, compression)).await?;
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use super::offline_uuid;
+
+    #[test]
+    fn offline_uuid_test() {
+        assert_eq!(offline_uuid("Notch"), 
+            Uuid::parse_str("b50ad385-829d-3141-a216-7e7d7539ba7f").unwrap());
+    }
+}
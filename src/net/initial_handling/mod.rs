@@ -1,26 +1,56 @@
+use std::net::IpAddr;
+use std::sync::RwLock;
 use tokio::net::TcpStream;
 use uuid::Uuid;
 
+mod authentication;
+mod forwarding;
 mod login;
 mod handshaking;
-mod status_gen;
+mod legacy_ping;
 mod status;
 
 use handshaking::{handshaking, Intent};
+use legacy_ping::{is_legacy_ping, read_legacy_ping, send_legacy_response};
 use login::login;
 use status::handle_status;
+use super::encryption::Encryption;
+use super::server_info::ServerInfo;
+use super::settings::ConnectionSettings;
+use super::PlayerInfoProperty;
+use crate::access::AccessLists;
 
-pub async fn initial_handling(conn: &mut TcpStream, status: String)
-    -> Option<(Uuid, String)>
+pub use authentication::Authenticator;
+pub use legacy_ping::LegacyFormat;
+pub use login::offline_uuid;
+
+pub struct NewPlayer {
+    pub uuid: Uuid,
+    pub name: String,
+    pub properties: Vec<PlayerInfoProperty>,
+    pub address: IpAddr,
+}
+
+pub async fn initial_handling(conn: &mut TcpStream, address: IpAddr, info: &RwLock<ServerInfo>,
+    access: &RwLock<AccessLists>, settings: &ConnectionSettings) -> Option<(NewPlayer, Option<Encryption>)>
 {
-    match handshaking(conn).await.ok()? {
+    if is_legacy_ping(conn).await.ok()? {
+        let format = read_legacy_ping(conn).await.ok()?;
+        let response = info.read().unwrap().to_legacy_str(format);
+        send_legacy_response(conn, &response).await.ok()?;
+        return None;
+    }
+    let handshake = handshaking(conn, &settings.forwarding).await.ok()?;
+    match handshake.intent {
         Intent::Login => {
-            let player = login(conn).await.ok()?;
+            let player = login(conn, address, &handshake, settings, access, info)
+                .await.ok()?;
             Some(player)
         },
         Intent::Status => {
+            let status = info.read().unwrap().to_status_str();
             handle_status(conn, status).await.ok()?;
             None
         },
     }
-}
\ No newline at end of file
+}
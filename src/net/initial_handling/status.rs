@@ -3,7 +3,8 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use anyhow::{anyhow, Result};
 use crate::net::{
     value_readers::read_varint,
-    builder::PacketBuilder};
+    builder::PacketBuilder,
+    framing::frame};
 
 pub async fn handle_status(conn: &mut TcpStream, status: String) -> Result<()> {
     loop {
@@ -54,14 +55,14 @@ impl ClientboundPacket {
                 let bytes = PacketBuilder::new(0)
                     .add_str(status.as_str())
                     .build();
-                writer.write_all(&bytes).await?;
+                writer.write_all(&frame(&bytes, None)).await?;
                 Ok(())
             }
             ClientboundPacket::Pong(time) => {
                 let bytes = PacketBuilder::new(1)
                     .add_bytes(&time.to_be_bytes())
                     .build();
-                writer.write_all(&bytes).await?;
+                writer.write_all(&frame(&bytes, None)).await?;
                 Ok(())
             }
         }
@@ -3,9 +3,26 @@ mod play;
 mod server;
 mod connection;
 mod builder;
+mod encryption;
+mod framing;
+mod queue;
+mod rate_limit;
+mod proxy_protocol;
+mod throttle;
+mod query;
+mod rcon;
 mod value_readers;
+mod client;
 mod server_info;
+mod settings;
 
 pub use server::Server;
+pub use client::{Client, ClientReader, ClientWriter};
 pub use connection::PlayerConnection;
-pub use play::{ClientboundPacket, ServerboundPacket, PlayerInfo};
+pub use queue::PacketSender;
+pub use initial_handling::{NewPlayer, offline_uuid};
+pub use play::{ChatPosition, ClientboundPacket, CommandNode, MetadataValue, ServerboundPacket, PlayerInfo, PlayerInfoProperty};
+
+pub const PROTOCOL_VERSION: u32 = 754;
+pub const VERSION_NAME: &str = "1.16.5";
+pub const BRAND: &str = "Glow";
@@ -1,11 +1,18 @@
+use std::borrow::Cow;
 use nalgebra::Vector3;
 use nbt::Value as Nbt;
-use serde_json::Value as Json;
+use serde::Deserialize;
 use uuid::Uuid;
 
 use crate::items::ItemStack;
+use crate::net::BRAND;
+use crate::serialization::push_varint;
+use crate::text::TextComponent;
 
-#[derive(Clone)]
+const DIM_CODEC: &[u8] = include_bytes!("./dimension_codec.nbt");
+const DIM_NBT: &[u8] = include_bytes!("./dimension.nbt");
+
+#[derive(Clone, Debug)]
 pub struct PlayerInfo {
     pub name: String,
     pub properties: Vec<PlayerInfoProperty>,
@@ -14,27 +21,64 @@ pub struct PlayerInfo {
     pub display_name: Option<String>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct PlayerInfoProperty {
     pub name: String,
     pub value: String,
     pub signature: Option<String>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatPosition {
+    Chat = 0,
+    System = 1,
+    // Shown above the hotbar
+    GameInfo = 2,
+}
+
+// A node of the Brigadier command graph, children are indices in the
+// list sent with Declare Commands
+#[derive(Clone, Debug)]
+pub enum CommandNode {
+    Root {
+        children: Vec<u32>,
+    },
+    Literal {
+        name: String,
+        executable: bool,
+        children: Vec<u32>,
+    },
+    Argument {
+        name: String,
+        executable: bool,
+        children: Vec<u32>,
+        parser: Cow<'static, str>,
+        properties: Vec<u8>,
+        // Whether the client should send Tab-Complete for this argument
+        ask_server: bool,
+    },
+}
+
+// The types of Entity Metadata fields that are sent so far
+#[derive(Clone, Debug)]
+pub enum MetadataValue {
+    Slot(Option<ItemStack>),
+}
+
+#[derive(Clone, Debug)]
 pub enum ClientboundPacket {
     JoinGame {
         entity_id: u32,
         gamemode: u8,
         world_names: Vec<String>,
-        dimension_codec: &'static[u8],
-        dimension: &'static[u8],
+        dimension_codec: Cow<'static, [u8]>,
+        dimension: Cow<'static, [u8]>,
         current_world: String,
         view_distance: u8,
     },
     PluginMessage {
         channel: String,
-        content: String,
+        content: Vec<u8>,
     },
     ChunkData {
         x: i32,
@@ -58,7 +102,27 @@ pub enum ClientboundPacket {
         block_light: Vec<Vec<u8>>,
     },
     KeepAlive(u64),
-    PlayerPosition(f64, f64, f64),
+    // Absolute, the client answers with Teleport Confirm
+    PlayerPositionAndLook {
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f32,
+        pitch: f32,
+        teleport_id: u32,
+    },
+    // Reason 3 changes the gamemode to `value`
+    ChangeGameState {
+        reason: u8,
+        value: f32,
+    },
+    PlayerAbilities {
+        flags: u8,
+        flying_speed: f32,
+        fov_modifier: f32,
+    },
+    // Where the compass points
+    SpawnPosition(Vector3<i32>),
     UpdateViewPosition(i32, i32),
     PlayerInfoAddPlayers(Vec<(Uuid, PlayerInfo)>),
     PlayerInfoUpdateGamemode(Vec<(Uuid, u8)>),
@@ -100,6 +164,23 @@ pub enum ClientboundPacket {
         yaw: f32,
     },
     DestroyEntities(Vec<u32>),
+    // Everything that isn't a player or a mob, `data` depends on the type
+    SpawnEntity {
+        entity_id: u32,
+        uuid: Uuid,
+        entity_type: u32,
+        x: f64,
+        y: f64,
+        z: f64,
+        pitch: f32,
+        yaw: f32,
+        data: i32,
+        velocity: Vector3<f64>,
+    },
+    EntityMetadata {
+        entity_id: u32,
+        metadata: Vec<(u8, MetadataValue)>,
+    },
     SpawnPlayer {
        entity_id: u32,
        uuid: Uuid,
@@ -109,19 +190,76 @@ pub enum ClientboundPacket {
        yaw: f32,
        pitch: f32,
     },
+    // Shows `collected` flying into `collector`, it doesn't remove it
+    CollectItem {
+        collected: u32,
+        collector: u32,
+        count: u8,
+    },
     BlockChange {
         pos: Vector3<i32>,
         block_state: u32,
     },
+    // Answers Player Digging, the client sets the block to `block_state`
+    AcknowledgePlayerDigging {
+        pos: Vector3<i32>,
+        block_state: u32,
+        status: u8,
+        successful: bool,
+    },
+    // Stages go from 0 to 9, anything else removes the cracks
+    BlockBreakAnimation {
+        entity_id: u32,
+        pos: Vector3<i32>,
+        stage: i8,
+    },
     WindowItems {
         window: u8,
         items: Vec<Option<ItemStack>>,
     },
     UnloadChunk(i32, i32),
     Disconnect {
-        reason: Json,
+        reason: TextComponent,
+    },
+    ChatMessage {
+        message: TextComponent,
+        position: ChatPosition,
+        sender: Uuid,
     },
     Tags {
-        raw: &'static [u8],
+        raw: Cow<'static, [u8]>,
+    },
+    // The root node must be the first one
+    DeclareCommands(Vec<CommandNode>),
+    TabComplete {
+        id: u32,
+        start: usize,
+        length: usize,
+        matches: Vec<String>,
+    },
+}
+
+impl ClientboundPacket {
+    // The first packet of play, everything else about the world comes after it
+    pub fn join_game(entity_id: u32, gamemode: u8, view_distance: u8) -> Self {
+        Self::JoinGame {
+            entity_id,
+            gamemode,
+            world_names: vec!["overworld".into(), "nether".into(), "the_end".into()],
+            dimension_codec: DIM_CODEC.into(),
+            dimension: DIM_NBT.into(),
+            current_world: "overworld".into(),
+            view_distance,
+        }
+    }
+
+    pub fn brand() -> Self {
+        let mut content = vec![];
+        push_varint(BRAND.len() as u32, &mut content);
+        content.extend_from_slice(BRAND.as_bytes());
+        Self::PluginMessage {
+            channel: "minecraft:brand".into(),
+            content,
+        }
     }
 }
@@ -2,39 +2,59 @@ mod writer;
 mod reader;
 mod clientbound;
 mod serverbound;
-mod errors;
+pub(super) mod errors;
 
-pub use clientbound::{ClientboundPacket, PlayerInfo, PlayerInfoProperty};
+pub use clientbound::{ChatPosition, ClientboundPacket, CommandNode, MetadataValue, PlayerInfo, PlayerInfoProperty};
 pub use serverbound::ServerboundPacket;
 
 use tokio::net::TcpStream;
 use anyhow::Result;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, BufReader};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 use std::sync::mpsc::Sender;
-use tokio::sync::mpsc::UnboundedReceiver;
-use crate::tags::TAG_PACKET;
 
 use super::connection::GameConnection;
+use super::queue::PacketReceiver;
+use super::rate_limit::RateLimiter;
+use super::encryption::{Encryption, EncryptedReader, EncryptedWriter};
 use errors::UnknownPacket;
 
-const BRAND: &str = "Glow";
-const DIM_CODEC: &[u8] = include_bytes!("./dimension_codec.nbt");
-const DIM_NBT: &[u8] = include_bytes!("./dimension.nbt");
-
-pub async fn play(conn: TcpStream, game: GameConnection) -> Result<()> {
+pub async fn play(conn: TcpStream, game: GameConnection, compression: Option<u32>,
+    packet_rate_limit: Option<u32>, encryption: Option<Encryption>) -> Result<()> 
+{
     let (game_recv, mut game_send) = game.into_split();
     let (tcp_read, tcp_write) = conn.into_split();
-    tokio::spawn(game_to_client(game_recv, tcp_write));
-    client_to_game(tcp_read, &mut game_send).await;
+    let (encryptor, decryptor) = match encryption {
+        Some(Encryption { encryptor, decryptor }) => (Some(encryptor), Some(decryptor)),
+        None => (None, None),
+    };
+    let tcp_read = EncryptedReader::new(tcp_read, decryptor);
+    let tcp_write = EncryptedWriter::new(tcp_write, encryptor);
+    // The writer keeps going after the client stops being read, so it can
+    // still be told why it was kicked, but a stalled writer stops everything
+    let writer = tokio::spawn(game_to_client(game_recv, tcp_write, compression));
+    tokio::select! {
+        _ = writer => (),
+        _ = client_to_game(tcp_read, &mut game_send, compression, packet_rate_limit) => (),
+    }
     Ok(())
 }
 
-async fn client_to_game<R>(tcp: R, sender: &mut Sender<ServerboundPacket>)
-    where R: AsyncRead + Unpin
+async fn client_to_game<R>(tcp: R, sender: &mut Sender<ServerboundPacket>, 
+    compression: Option<u32>, packet_rate_limit: Option<u32>) where R: AsyncRead + Unpin
 {
     let mut tcp = BufReader::with_capacity(256, tcp);
+    let mut limiter = packet_rate_limit.map(RateLimiter::new);
     loop {
-        match ServerboundPacket::read(&mut tcp).await {
+        let packet = ServerboundPacket::read(&mut tcp, compression).await;
+        if let Some(limiter) = &mut limiter {
+            if !limiter.try_acquire() {
+                sender.send(ServerboundPacket::Disconnect {
+                    reason: "Sending packets too fast".into(),
+                });
+                break;
+            }
+        }
+        match packet {
             Ok(packet) => {
                 sender.send(packet);
             }
@@ -50,34 +70,24 @@ async fn client_to_game<R>(tcp: R, sender: &mut Sender<ServerboundPacket>)
     }
 }
 
-async fn game_to_client<W>(mut game: UnboundedReceiver<ClientboundPacket>, mut tcp: W) 
-    -> Result<()> where W: AsyncWrite + Unpin
+// Packets are encoded and compressed here, in the connection's own task,
+// so the tick loop only has to queue them
+async fn game_to_client<W>(mut game: PacketReceiver, mut tcp: W,
+    compression: Option<u32>) -> Result<()> where W: AsyncWrite + Unpin
 {
-    send_initial_packets(&mut tcp).await?;
     while let Some(packet) = game.recv().await {
-        packet.send(&mut tcp).await?;
+        tokio::select! {
+            result = write_packet(&packet, &mut tcp, compression) => result?,
+            _ = game.overflowed() => break,
+        }
     }
     Ok(())
 }
 
-async fn send_initial_packets<W>(writer: &mut W) -> Result<()>
-    where W: AsyncWrite + Unpin 
+async fn write_packet<W>(packet: &ClientboundPacket, tcp: &mut W, 
+    compression: Option<u32>) -> Result<()> where W: AsyncWrite + Unpin
 {
-    ClientboundPacket::JoinGame {
-        entity_id: 999,
-        gamemode: 1,
-        world_names: vec!["overworld".into(), "nether".into(), "the_end".into()],
-        dimension_codec: DIM_CODEC,
-        dimension: DIM_NBT,
-        current_world: "overworld".into(),
-        view_distance: 6,
-    }.send(writer).await?;
-    ClientboundPacket::PluginMessage {
-        channel: "minecraft:brand".into(),
-        content: BRAND.into(),
-    }.send(writer).await?;
-    ClientboundPacket::Tags {
-        raw: TAG_PACKET,
-    }.send(writer).await?;
+    packet.send(tcp, compression).await?;
+    tcp.flush().await?;
     Ok(())
 }
@@ -8,20 +8,59 @@ use num_traits::FromPrimitive;
 
 use super::errors::UnknownPacket;
 use super::serverbound::ServerboundPacket;
-use crate::net::value_readers::{read_block_pos, read_varint};
+use crate::net::value_readers::{MAX_STRING_LENGTH, read_block_pos, read_str, read_varint};
+use crate::net::framing::read_frame;
+
+// Vanilla's limit on chat messages
+const MAX_CHAT_LENGTH: usize = 256;
+const MAX_TAB_COMPLETE_LENGTH: usize = 32500;
+const MAX_PLUGIN_MESSAGE_LENGTH: usize = 32767;
 
 impl ServerboundPacket {
-    pub async fn read<R>(reader: &mut R) -> Result<Self> 
+    pub async fn read<R>(reader: &mut R, compression: Option<u32>) -> Result<Self> 
     where R: AsyncRead + Unpin
     {
-        let length = read_varint(reader).await? as usize;
-        if length == 0 {
-            return Err(anyhow!("Packet length cannot be 0"));
-        }
-        let mut buffer = vec![0; length];
-        reader.read_exact(buffer.as_mut()).await?;
+        let buffer = read_frame(reader, compression).await?;
         let mut payload = Cursor::new(buffer);
         match payload.read_u8().await? {
+            0x00 => {
+                let teleport_id = read_varint(&mut payload).await?;
+                Ok(Self::TeleportConfirm {
+                    teleport_id,
+                })
+            }
+            0x03 => {
+                let message = read_str(&mut payload, MAX_CHAT_LENGTH).await?;
+                Ok(Self::ChatMessage {
+                    message,
+                })
+            }
+            0x06 => {
+                let transaction_id = read_varint(&mut payload).await?;
+                let text = read_str(&mut payload, MAX_TAB_COMPLETE_LENGTH).await?;
+                Ok(Self::TabComplete {
+                    transaction_id, text
+                })
+            }
+            0x0B => {
+                let channel = read_str(&mut payload, MAX_STRING_LENGTH).await?;
+                // The data takes the rest of the packet, without a length
+                let mut data = vec![];
+                payload.read_to_end(&mut data).await?;
+                if data.len() > MAX_PLUGIN_MESSAGE_LENGTH {
+                    return Err(anyhow!("Plugin message is too long ({} > {})",
+                        data.len(), MAX_PLUGIN_MESSAGE_LENGTH));
+                }
+                Ok(Self::PluginMessage {
+                    channel, data
+                })
+            }
+            0x10 => {
+                let id = payload.read_u64().await?;
+                Ok(Self::KeepAlive {
+                    id,
+                })
+            }
             0x12 => {
                 let x = f64::from_bits(payload.read_u64().await?);
                 let y = f64::from_bits(payload.read_u64().await?);
@@ -3,6 +3,20 @@ use crate::items::ItemStack;
 use crate::blocks::BlockFace;
 
 pub enum ServerboundPacket {
+    TeleportConfirm {
+        teleport_id: u32,
+    },
+    ChatMessage {
+        message: String,
+    },
+    TabComplete {
+        transaction_id: u32,
+        text: String,
+    },
+    PluginMessage {
+        channel: String,
+        data: Vec<u8>,
+    },
     PlayerPosition {
         x: f64, 
         y: f64, 
@@ -41,6 +55,9 @@ pub enum ServerboundPacket {
         cursor_position: Vector3<f32>,
         inside_block: bool,
     },
+    KeepAlive {
+        id: u64,
+    },
     Disconnect {
         reason: String,
     },
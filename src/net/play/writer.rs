@@ -1,12 +1,13 @@
 use anyhow::Result;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
-use super::clientbound::ClientboundPacket;
+use super::clientbound::{ClientboundPacket, CommandNode, MetadataValue};
 use super::super::builder::PacketBuilder;
+use super::super::framing::frame;
 
 impl ClientboundPacket {
-    pub async fn send<W>(&self, writer: &mut W) -> Result<()>
-        where W: AsyncWrite + Unpin
+    pub async fn send<W>(&self, writer: &mut W, compression: Option<u32>) 
+        -> Result<()> where W: AsyncWrite + Unpin
     {
         let bytes = match self {
             Self::JoinGame { 
@@ -37,7 +38,7 @@ impl ClientboundPacket {
             Self::PluginMessage { channel, content } => {
                 PacketBuilder::new(0x17)
                     .add_str(channel.as_str())
-                    .add_str(content.as_str())
+                    .add_bytes(content)
                     .build()
             }
             Self::ChunkData
@@ -97,15 +98,33 @@ impl ClientboundPacket {
                     .add_bytes(&id.to_be_bytes())
                     .build()
             }
-            Self::PlayerPosition(x, y, z) => {
+            Self::PlayerPositionAndLook { x, y, z, yaw, pitch, teleport_id } => {
                 PacketBuilder::new(0x34)
                     .add_bytes(&x.to_be_bytes())
                     .add_bytes(&y.to_be_bytes())
                     .add_bytes(&z.to_be_bytes())
-                    .add_bytes(&0f32.to_be_bytes()) // Yaw
-                    .add_bytes(&0f32.to_be_bytes()) // Pitch
-                    .add_bytes(&[0b11000]) // Rotation relative, position absolute
-                    .add_varint(0) // Teleport ID, used by client to confirm
+                    .add_bytes(&yaw.to_be_bytes())
+                    .add_bytes(&pitch.to_be_bytes())
+                    .add_bytes(&[0]) // Nothing is relative
+                    .add_varint(*teleport_id) // Used by client to confirm
+                    .build()
+            }
+            Self::ChangeGameState { reason, value } => {
+                PacketBuilder::new(0x1D)
+                    .add_bytes(&[*reason])
+                    .add_bytes(&value.to_be_bytes())
+                    .build()
+            }
+            Self::PlayerAbilities { flags, flying_speed, fov_modifier } => {
+                PacketBuilder::new(0x30)
+                    .add_bytes(&[*flags])
+                    .add_bytes(&flying_speed.to_be_bytes())
+                    .add_bytes(&fov_modifier.to_be_bytes())
+                    .build()
+            }
+            Self::SpawnPosition(pos) => {
+                PacketBuilder::new(0x42)
+                    .add_block_position(pos)
                     .build()
             }
             Self::UpdateViewPosition(x, z) => {
@@ -150,10 +169,24 @@ impl ClientboundPacket {
                 packet.build()
             }
             Self::PlayerInfoUpdateGamemode(updates) => {
-                unimplemented!()
+                let mut packet = PacketBuilder::new(0x32);
+                packet.add_varint(1)
+                    .add_varint(updates.len() as u32);
+                for (uuid, gamemode) in updates {
+                    packet.add_bytes(uuid.as_bytes())
+                        .add_varint(*gamemode as u32);
+                }
+                packet.build()
             }
             Self::PlayerInfoUpdateLatency(updates) => {
-                unimplemented!()
+                let mut packet = PacketBuilder::new(0x32);
+                packet.add_varint(2)
+                    .add_varint(updates.len() as u32);
+                for (uuid, ping) in updates {
+                    packet.add_bytes(uuid.as_bytes())
+                        .add_varint(*ping as u32);
+                }
+                packet.build()
             }
             Self::PlayerInfoRemovePlayers(players) => {
                 let mut packet = PacketBuilder::new(0x32);
@@ -219,6 +252,36 @@ impl ClientboundPacket {
                 }
                 packet.build()
             }
+            Self::SpawnEntity {
+                entity_id, uuid, entity_type, x, y, z, pitch, yaw, data, velocity
+            } => {
+                PacketBuilder::new(0x00)
+                    .add_varint(*entity_id)
+                    .add_bytes(uuid.as_bytes())
+                    .add_varint(*entity_type)
+                    .add_bytes(&x.to_be_bytes())
+                    .add_bytes(&y.to_be_bytes())
+                    .add_bytes(&z.to_be_bytes())
+                    .add_angle(*pitch)
+                    .add_angle(*yaw)
+                    .add_bytes(&data.to_be_bytes())
+                    .add_velocity(velocity)
+                    .build()
+            }
+            Self::EntityMetadata { entity_id, metadata } => {
+                let mut packet = PacketBuilder::new(0x44);
+                packet.add_varint(*entity_id);
+                for (index, value) in metadata {
+                    packet.add_bytes(&[*index]);
+                    match value {
+                        MetadataValue::Slot(slot) => {
+                            packet.add_varint(6).add_slot(slot);
+                        }
+                    }
+                }
+                packet.add_bytes(&[0xFF]) // End of the metadata
+                    .build()
+            }
             Self::SpawnPlayer{ entity_id, uuid, x, y, z, yaw, pitch } => {
                 PacketBuilder::new(0x04)
                     .add_varint(*entity_id)
@@ -230,23 +293,40 @@ impl ClientboundPacket {
                     .add_angle(*pitch)
                     .build()
             }
+            Self::CollectItem { collected, collector, count } => {
+                PacketBuilder::new(0x55)
+                    .add_varint(*collected)
+                    .add_varint(*collector)
+                    .add_varint(*count as u32)
+                    .build()
+            }
             Self::BlockChange{ pos, block_state } => {
                 PacketBuilder::new(0x0B)
                     .add_block_position(pos)
                     .add_varint(*block_state)
                     .build()
             }
+            Self::AcknowledgePlayerDigging { pos, block_state, status, successful } => {
+                PacketBuilder::new(0x07)
+                    .add_block_position(pos)
+                    .add_varint(*block_state)
+                    .add_varint(*status as u32)
+                    .add_bytes(&[*successful as u8])
+                    .build()
+            }
+            Self::BlockBreakAnimation { entity_id, pos, stage } => {
+                PacketBuilder::new(0x08)
+                    .add_varint(*entity_id)
+                    .add_block_position(pos)
+                    .add_bytes(&stage.to_be_bytes())
+                    .build()
+            }
             Self::WindowItems{ window, items } => {
                 let mut pack = PacketBuilder::new(0x13);
                 pack.add_bytes(&[*window])
                     .add_bytes(&(items.len() as u16).to_be_bytes());
                 for slot in items {
-                    pack.add_bytes(&[slot.is_some() as u8]);
-                    if let Some(stack) = slot {
-                        pack.add_varint(stack.item.to_numeric() as u32);
-                        pack.add_bytes(&[stack.count]);
-                        pack.add_bytes(&[0]);
-                    }
+                    pack.add_slot(slot);
                 }
                 pack.build()
             }
@@ -258,7 +338,14 @@ impl ClientboundPacket {
             }
             Self::Disconnect{ reason } => {
                 PacketBuilder::new(0x19)
-                    .add_str(&reason.to_string())
+                    .add_str(&reason.to_json())
+                    .build()
+            }
+            Self::ChatMessage{ message, position, sender } => {
+                PacketBuilder::new(0x0E)
+                    .add_str(&message.to_json())
+                    .add_bytes(&[*position as u8])
+                    .add_bytes(sender.as_bytes())
                     .build()
             }
             Self::Tags{ raw } => {
@@ -266,8 +353,56 @@ impl ClientboundPacket {
                     .add_bytes(raw)
                     .build()
             }
+            Self::DeclareCommands(nodes) => {
+                let mut pack = PacketBuilder::new(0x10);
+                pack.add_varint(nodes.len() as u32);
+                for node in nodes {
+                    let (flags, children) = match node {
+                        CommandNode::Root { children } => (0, children),
+                        CommandNode::Literal { executable, children, .. } => {
+                            (1 | (*executable as u8) << 2, children)
+                        }
+                        CommandNode::Argument { executable, children, ask_server, .. } => {
+                            (2 | (*executable as u8) << 2 | (*ask_server as u8) << 4, children)
+                        }
+                    };
+                    pack.add_bytes(&[flags])
+                        .add_varint(children.len() as u32);
+                    for child in children {
+                        pack.add_varint(*child);
+                    }
+                    match node {
+                        CommandNode::Root { .. } => (),
+                        CommandNode::Literal { name, .. } => {
+                            pack.add_str(name);
+                        }
+                        CommandNode::Argument { name, parser, properties, ask_server, .. } => {
+                            pack.add_str(name)
+                                .add_str(parser)
+                                .add_bytes(properties);
+                            if *ask_server {
+                                pack.add_str("minecraft:ask_server");
+                            }
+                        }
+                    }
+                }
+                pack.add_varint(0) // Root index
+                    .build()
+            }
+            Self::TabComplete{ id, start, length, matches } => {
+                let mut pack = PacketBuilder::new(0x0F);
+                pack.add_varint(*id)
+                    .add_varint(*start as u32)
+                    .add_varint(*length as u32)
+                    .add_varint(matches.len() as u32);
+                for suggestion in matches {
+                    pack.add_str(suggestion)
+                        .add_bytes(&[0]); // Has tooltip
+                }
+                pack.build()
+            }
         };
-        writer.write_all(&bytes).await?;
+        writer.write_all(&frame(&bytes, compression)).await?;
         Ok(())
     }
 }
\ No newline at end of file
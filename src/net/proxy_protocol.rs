@@ -0,0 +1,132 @@
+use std::convert::TryInto;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::time::timeout;
+
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+// Longest possible v1 header, with the line ending
+const V1_MAX_LENGTH: usize = 107;
+// Balancers send the header right after connecting
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Reads the HAProxy PROXY protocol header sent by load balancers before
+// anything else, and returns the address of the real client. It's None
+// when the balancer doesn't know it, like for its own health checks.
+// The header is read byte by byte when needed so nothing after it is
+// consumed from the connection
+pub async fn read_header<R>(reader: &mut R) -> Result<Option<SocketAddr>>
+    where R: AsyncRead + Unpin
+{
+    timeout(HEADER_TIMEOUT, read_any(reader)).await
+        .map_err(|_| anyhow!("Timed out waiting for the PROXY header"))?
+}
+
+async fn read_any<R>(reader: &mut R) -> Result<Option<SocketAddr>>
+    where R: AsyncRead + Unpin
+{
+    // The shortest v1 header is longer than this
+    let mut start = [0; 12];
+    reader.read_exact(&mut start).await?;
+    if start == V2_SIGNATURE {
+        read_v2(reader).await
+    } else if start.starts_with(b"PROXY ") {
+        read_v1(reader, &start).await
+    } else {
+        Err(anyhow!("Expected a PROXY protocol header"))
+    }
+}
+
+async fn read_v1<R>(reader: &mut R, start: &[u8]) -> Result<Option<SocketAddr>>
+    where R: AsyncRead + Unpin
+{
+    let mut line = start.to_vec();
+    while !line.ends_with(b"\r\n") {
+        if line.len() >= V1_MAX_LENGTH {
+            return Err(anyhow!("PROXY header is too long"));
+        }
+        line.push(reader.read_u8().await?);
+    }
+    let line = std::str::from_utf8(&line[..line.len() - 2])?;
+    let parts: Vec<&str> = line.split(' ').collect();
+    match parts.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", "TCP4" | "TCP6", source, _, source_port, _] => {
+            let ip: IpAddr = source.parse()?;
+            let port: u16 = source_port.parse()?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(anyhow!("Invalid PROXY header '{}'", line)),
+    }
+}
+
+async fn read_v2<R>(reader: &mut R) -> Result<Option<SocketAddr>>
+    where R: AsyncRead + Unpin
+{
+    let version_command = reader.read_u8().await?;
+    if version_command >> 4 != 2 {
+        return Err(anyhow!("Unsupported PROXY protocol version {}", version_command >> 4));
+    }
+    let family = reader.read_u8().await? >> 4;
+    let length = reader.read_u16().await? as usize;
+    let mut addresses = vec![0; length];
+    reader.read_exact(&mut addresses).await?;
+    // LOCAL connections come from the balancer itself
+    if version_command & 0x0F == 0 {
+        return Ok(None);
+    }
+    match family {
+        // AF_INET, then the source and destination addresses and ports
+        1 if length >= 12 => {
+            let octets: [u8; 4] = addresses[0..4].try_into()?;
+            let ip = Ipv4Addr::from(octets);
+            let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+            Ok(Some(SocketAddr::new(ip.into(), port)))
+        }
+        // AF_INET6
+        2 if length >= 36 => {
+            let octets: [u8; 16] = addresses[0..16].try_into()?;
+            let ip = Ipv6Addr::from(octets);
+            let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+            Ok(Some(SocketAddr::new(ip.into(), port)))
+        }
+        1 | 2 => Err(anyhow!("PROXY header addresses are too short")),
+        // AF_UNSPEC and AF_UNIX don't have an address we can use
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use tokio::io::AsyncReadExt;
+    use super::read_header;
+
+    #[tokio::test]
+    async fn v1_test() {
+        let mut data: &[u8] = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 25565\r\n\x10";
+        let address = read_header(&mut data).await.unwrap();
+        assert_eq!(address, Some("192.168.0.1:56324".parse::<SocketAddr>().unwrap()));
+        assert_eq!(data.read_u8().await.unwrap(), 0x10);
+
+        let mut data: &[u8] = b"PROXY UNKNOWN\r\n";
+        assert_eq!(read_header(&mut data).await.unwrap(), None);
+
+        let mut data: &[u8] = b"\x10\x00\xf2\x05\x09localhost\x63\xdd\x02";
+        assert!(read_header(&mut data).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn v2_test() {
+        let mut data = b"\r\n\r\n\0\r\nQUIT\n\x21\x11\x00\x0c".to_vec();
+        data.extend_from_slice(&[10, 0, 0, 5, 10, 0, 0, 1, 0xdb, 0xf4, 0x63, 0xdd, 0x10]);
+        let mut reader = data.as_slice();
+        let address = read_header(&mut reader).await.unwrap();
+        assert_eq!(address, Some("10.0.0.5:56308".parse::<SocketAddr>().unwrap()));
+        assert_eq!(reader, &[0x10]);
+
+        let mut local: &[u8] = b"\r\n\r\n\0\r\nQUIT\n\x20\x00\x00\x00";
+        assert_eq!(read_header(&mut local).await.unwrap(), None);
+    }
+}
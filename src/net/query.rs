@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use rand::random;
+use tokio::net::UdpSocket;
+use super::server_info::ServerInfo;
+use super::{BRAND, VERSION_NAME};
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const HANDSHAKE: u8 = 9;
+const STAT: u8 = 0;
+const CHALLENGE_LIFETIME: Duration = Duration::from_secs(30);
+const MAP_NAME: &str = "world";
+
+// GameSpy4 Query protocol, as implemented by vanilla's `enable-query`
+pub async fn listen(port: u16, game_port: u16, info: Arc<RwLock<ServerInfo>>) -> Result<()> {
+    let socket = UdpSocket::bind(format!("0.0.0.0:{}", port)).await?;
+    log!("Query listening on port {}", port);
+    let mut challenges = Challenges::new();
+    let mut buffer = [0; 1460];
+    loop {
+        // Errors here are about a single client, like an ICMP port unreachable
+        let (len, address) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            Err(_) => continue,
+        };
+        let response = handle_request(&buffer[..len], address, &mut challenges,
+            &info.read().unwrap(), game_port);
+        if let Some(response) = response {
+            let _ = socket.send_to(&response, address).await;
+        }
+    }
+}
+
+struct Challenges {
+    tokens: HashMap<SocketAddr, (i32, Instant)>,
+}
+
+impl Challenges {
+    fn new() -> Self {
+        Self {
+            tokens: HashMap::new(),
+        }
+    }
+
+    fn create(&mut self, address: SocketAddr) -> i32 {
+        self.tokens.retain(|_, (_, created)| created.elapsed() < CHALLENGE_LIFETIME);
+        let token = random::<i32>() & 0x7FFF_FFFF;
+        self.tokens.insert(address, (token, Instant::now()));
+        token
+    }
+
+    fn check(&self, address: SocketAddr, token: i32) -> bool {
+        match self.tokens.get(&address) {
+            Some((expected, created)) => {
+                *expected == token && created.elapsed() < CHALLENGE_LIFETIME
+            }
+            None => false,
+        }
+    }
+}
+
+fn handle_request(request: &[u8], address: SocketAddr, challenges: &mut Challenges,
+    info: &ServerInfo, game_port: u16) -> Option<Vec<u8>>
+{
+    if request.len() < 7 || request[..2] != MAGIC {
+        return None;
+    }
+    let kind = request[2];
+    let session = &request[3..7];
+    let payload = &request[7..];
+    let mut response = vec![kind];
+    response.extend_from_slice(session);
+    match kind {
+        HANDSHAKE => {
+            let token = challenges.create(address);
+            push_str(&mut response, &token.to_string());
+        }
+        STAT if payload.len() >= 4 => {
+            let token = i32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+            if !challenges.check(address, token) {
+                return None;
+            }
+            // Full stat requests are padded with 4 more bytes
+            if payload.len() >= 8 {
+                full_stat(&mut response, info, game_port);
+            } else {
+                basic_stat(&mut response, info, game_port);
+            }
+        }
+        _ => return None,
+    }
+    Some(response)
+}
+
+fn basic_stat(response: &mut Vec<u8>, info: &ServerInfo, game_port: u16) {
+    push_str(response, &info.motd());
+    push_str(response, "SMP");
+    push_str(response, MAP_NAME);
+    push_str(response, &info.online_players().to_string());
+    push_str(response, &info.max_players().to_string());
+    response.extend_from_slice(&game_port.to_le_bytes());
+    push_str(response, "0.0.0.0");
+}
+
+fn full_stat(response: &mut Vec<u8>, info: &ServerInfo, game_port: u16) {
+    response.extend_from_slice(b"splitnum\0\x80\0");
+    let values = [
+        ("hostname", info.motd()),
+        ("gametype", "SMP".to_owned()),
+        ("game_id", "MINECRAFT".to_owned()),
+        ("version", VERSION_NAME.to_owned()),
+        // No plugins, just the server name in vanilla's "name: plugin; plugin" format
+        ("plugins", format!("{} {}", BRAND, VERSION_NAME)),
+        ("map", MAP_NAME.to_owned()),
+        ("numplayers", info.online_players().to_string()),
+        ("maxplayers", info.max_players().to_string()),
+        ("hostport", game_port.to_string()),
+        ("hostip", "0.0.0.0".to_owned()),
+    ];
+    for (key, value) in values.iter() {
+        push_str(response, key);
+        push_str(response, value);
+    }
+    response.push(0);
+    response.extend_from_slice(b"\x01player_\0\0");
+    for name in info.player_names() {
+        push_str(response, name);
+    }
+    response.push(0);
+}
+
+fn push_str(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(value.as_bytes());
+    buffer.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use crate::config::Config;
+    use crate::net::server_info::ServerInfo;
+    use super::{Challenges, handle_request};
+
+    #[test]
+    fn query_test() {
+        let address = "127.0.0.1:50000".parse().unwrap();
+        let mut challenges = Challenges::new();
+        let config = Config {
+            motd: "A server".into(),
+            ..Config::default()
+        };
+        let mut info = ServerInfo::new(&config);
+        info.update_players(vec![
+            (Uuid::nil(), "Notch".into()),
+            (Uuid::nil(), "jeb_".into()),
+        ]);
+        let handshake = [0xFE, 0xFD, 9, 0, 0, 0, 1];
+        let response = handle_request(&handshake, address, &mut challenges, &info, 25565)
+            .unwrap();
+        assert_eq!(&response[..5], &[9, 0, 0, 0, 1]);
+        let token: i32 = std::str::from_utf8(&response[5..response.len() - 1])
+            .unwrap().parse().unwrap();
+
+        let mut basic = vec![0xFE, 0xFD, 0, 0, 0, 0, 1];
+        basic.extend_from_slice(&token.to_be_bytes());
+        let response = handle_request(&basic, address, &mut challenges, &info, 25565)
+            .unwrap();
+        assert_eq!(&response[5..], b"A server\0SMP\0world\x002\x00100\0\xDD\x630.0.0.0\0");
+
+        let mut full = basic.clone();
+        full.extend_from_slice(&[0; 4]);
+        let response = handle_request(&full, address, &mut challenges, &info, 25565)
+            .unwrap();
+        assert!(response.ends_with(b"\x01player_\0\0Notch\0jeb_\0\0"));
+
+        let mut wrong_token = vec![0xFE, 0xFD, 0, 0, 0, 0, 1];
+        wrong_token.extend_from_slice(&(token ^ 1).to_be_bytes());
+        assert!(handle_request(&wrong_token, address, &mut challenges, &info, 25565).is_none());
+    }
+}
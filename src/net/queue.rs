@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use anyhow::{Result, anyhow};
+use tokio::sync::Notify;
+use super::play::ClientboundPacket;
+
+// How many queued packets to look through for a movement to merge into
+const COALESCE_WINDOW: usize = 256;
+// Relative moves are sent as shorts in 1/4096 of a block
+const MAX_MOVE_DELTA: f64 = 7.99;
+
+// Outbound packets of a single player. Once more than half of the byte
+// budget is queued, entity movements are merged into the ones still waiting,
+// and if the whole budget is used up the queue is closed for good
+pub fn packet_queue(max_bytes: usize) -> (PacketSender, PacketReceiver) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(QueueState {
+            packets: VecDeque::new(),
+            queued_bytes: 0,
+            senders: 1,
+            receiver_alive: true,
+            overflowed: false,
+        }),
+        notify: Notify::new(),
+        max_bytes,
+    });
+    (PacketSender { shared: shared.clone() }, PacketReceiver { shared })
+}
+
+struct Shared {
+    state: Mutex<QueueState>,
+    notify: Notify,
+    max_bytes: usize,
+}
+
+struct QueueState {
+    packets: VecDeque<(ClientboundPacket, usize)>,
+    queued_bytes: usize,
+    senders: usize,
+    receiver_alive: bool,
+    overflowed: bool,
+}
+
+pub struct PacketSender {
+    shared: Arc<Shared>,
+}
+
+impl PacketSender {
+    pub fn send(&self, packet: ClientboundPacket) -> Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.overflowed || !state.receiver_alive {
+            return Err(anyhow!("Tried to send a packet to a closed connection"));
+        }
+        let size = estimated_size(&packet);
+        let under_pressure = state.queued_bytes + size > self.shared.max_bytes / 2;
+        if under_pressure && coalesce(&mut state.packets, &packet) {
+            return Ok(());
+        }
+        state.queued_bytes += size;
+        state.packets.push_back((packet, size));
+        if state.queued_bytes > self.shared.max_bytes {
+            state.overflowed = true;
+            state.packets.clear();
+            state.queued_bytes = 0;
+            drop(state);
+            self.shared.notify.notify_one();
+            return Err(anyhow!("Outbound packet queue is full"));
+        }
+        drop(state);
+        self.shared.notify.notify_one();
+        Ok(())
+    }
+
+    pub fn is_overflowed(&self) -> bool {
+        self.shared.state.lock().unwrap().overflowed
+    }
+}
+
+impl Clone for PacketSender {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().unwrap().senders += 1;
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl Drop for PacketSender {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().senders -= 1;
+        self.shared.notify.notify_one();
+    }
+}
+
+pub struct PacketReceiver {
+    shared: Arc<Shared>,
+}
+
+impl PacketReceiver {
+    // Returns None once every sender is gone and the queue is drained,
+    // or right away if the queue overflowed
+    pub async fn recv(&mut self) -> Option<ClientboundPacket> {
+        loop {
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                if state.overflowed {
+                    return None;
+                }
+                if let Some((packet, size)) = state.packets.pop_front() {
+                    state.queued_bytes -= size;
+                    return Some(packet);
+                }
+                if state.senders == 0 {
+                    return None;
+                }
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+
+    // Completes when the queue overflows, used to stop waiting on a stalled client
+    pub async fn overflowed(&self) {
+        loop {
+            if self.shared.state.lock().unwrap().overflowed {
+                return;
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+}
+
+impl Drop for PacketReceiver {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().receiver_alive = false;
+    }
+}
+
+// Rough size of the encoded packet, only the big packets need to be accurate
+fn estimated_size(packet: &ClientboundPacket) -> usize {
+    match packet {
+        ClientboundPacket::JoinGame { dimension_codec, dimension, .. } => {
+            dimension_codec.len() + dimension.len() + 64
+        }
+        ClientboundPacket::ChunkData { data, biomes, .. } => {
+            data.len() + biomes.as_ref().map_or(0, |biomes| biomes.len() * 4) + 256
+        }
+        ClientboundPacket::UpdateLight { sky_light, block_light, .. } => {
+            sky_light.iter().chain(block_light).map(|array| array.len() + 3).sum::<usize>() + 32
+        }
+        ClientboundPacket::PlayerInfoAddPlayers(players) => players.len() * 512,
+        ClientboundPacket::WindowItems { items, .. } => items.len() * 8,
+        ClientboundPacket::Tags { raw } => raw.len(),
+        _ => 32,
+    }
+}
+
+// Tries to merge an entity movement into the latest queued packet about the
+// same entity, returning false if it has to be queued separately
+fn coalesce(packets: &mut VecDeque<(ClientboundPacket, usize)>,
+    packet: &ClientboundPacket) -> bool
+{
+    let id = match movement_entity(packet) {
+        Some(id) => id,
+        None => return false,
+    };
+    let is_head_look = matches!(packet, ClientboundPacket::EntityHeadLook { .. });
+    for (queued, _) in packets.iter_mut().rev().take(COALESCE_WINDOW) {
+        if !concerns_entity(queued, id) {
+            continue;
+        }
+        // Head rotation is independent from the body, so they can pass each other
+        if is_head_look != matches!(queued, ClientboundPacket::EntityHeadLook { .. })
+            && movement_entity(queued).is_some()
+        {
+            continue;
+        }
+        return merge(queued, packet);
+    }
+    false
+}
+
+fn movement_entity(packet: &ClientboundPacket) -> Option<u32> {
+    match packet {
+        ClientboundPacket::EntityPosition { id, .. }
+        | ClientboundPacket::EntityPositionAndRotation { id, .. }
+        | ClientboundPacket::EntityRotation { id, .. }
+        | ClientboundPacket::EntityHeadLook { id, .. } => Some(*id),
+        _ => None,
+    }
+}
+
+fn concerns_entity(packet: &ClientboundPacket, entity: u32) -> bool {
+    match packet {
+        ClientboundPacket::EntityTeleport { id, .. } => *id == entity,
+        ClientboundPacket::SpawnPlayer { entity_id, .. }
+        | ClientboundPacket::SpawnEntity { entity_id, .. }
+        | ClientboundPacket::EntityMetadata { entity_id, .. } => *entity_id == entity,
+        ClientboundPacket::CollectItem { collected, .. } => *collected == entity,
+        ClientboundPacket::DestroyEntities(ids) => ids.contains(&entity),
+        packet => movement_entity(packet) == Some(entity),
+    }
+}
+
+fn merge(queued: &mut ClientboundPacket, packet: &ClientboundPacket) -> bool {
+    use ClientboundPacket::*;
+    let merged = match (&*queued, packet) {
+        (EntityHeadLook { .. }, EntityHeadLook { .. })
+        | (EntityRotation { .. }, EntityRotation { .. }) => packet.clone(),
+        (first, second) => {
+            let (first_delta, first_rotation) = match move_parts(first) {
+                Some(parts) => parts,
+                None => return false,
+            };
+            let (second_delta, second_rotation) = match move_parts(second) {
+                Some(parts) => parts,
+                None => return false,
+            };
+            let delta = [
+                first_delta[0] + second_delta[0],
+                first_delta[1] + second_delta[1],
+                first_delta[2] + second_delta[2],
+            ];
+            if delta.iter().any(|value| value.abs() > MAX_MOVE_DELTA) {
+                return false;
+            }
+            let id = movement_entity(packet).unwrap();
+            let on_ground = match packet {
+                EntityPosition { on_ground, .. }
+                | EntityPositionAndRotation { on_ground, .. } => *on_ground,
+                _ => unreachable!(),
+            };
+            match second_rotation.or(first_rotation) {
+                Some((yaw, pitch)) => EntityPositionAndRotation {
+                    id, delta_x: delta[0], delta_y: delta[1], delta_z: delta[2],
+                    yaw, pitch, on_ground,
+                },
+                None => EntityPosition {
+                    id, delta_x: delta[0], delta_y: delta[1], delta_z: delta[2],
+                    on_ground,
+                },
+            }
+        }
+    };
+    *queued = merged;
+    true
+}
+
+// Position delta and the new yaw and pitch, if the move changes them
+type MoveParts = ([f64; 3], Option<(f32, f32)>);
+
+fn move_parts(packet: &ClientboundPacket) -> Option<MoveParts> {
+    match packet {
+        ClientboundPacket::EntityPosition { delta_x, delta_y, delta_z, .. } => {
+            Some(([*delta_x, *delta_y, *delta_z], None))
+        }
+        ClientboundPacket::EntityPositionAndRotation {
+            delta_x, delta_y, delta_z, yaw, pitch, ..
+        } => Some(([*delta_x, *delta_y, *delta_z], Some((*yaw, *pitch)))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::net::ClientboundPacket;
+    use super::packet_queue;
+
+    fn movement(id: u32, delta_x: f64) -> ClientboundPacket {
+        ClientboundPacket::EntityPosition {
+            id, delta_x, delta_y: 0.0, delta_z: 0.0, on_ground: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn coalesce_test() {
+        let (sender, mut receiver) = packet_queue(64);
+        sender.send(movement(1, 1.0)).unwrap();
+        sender.send(movement(2, 1.0)).unwrap();
+        sender.send(movement(1, 2.0)).unwrap();
+        drop(sender);
+        match receiver.recv().await {
+            Some(ClientboundPacket::EntityPosition { id: 1, delta_x, .. }) => {
+                assert_eq!(delta_x, 3.0);
+            }
+            _ => panic!("Movements weren't merged"),
+        }
+        assert!(matches!(receiver.recv().await,
+            Some(ClientboundPacket::EntityPosition { id: 2, .. })));
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn overflow_test() {
+        let (sender, mut receiver) = packet_queue(100);
+        for _ in 0..3 {
+            sender.send(ClientboundPacket::KeepAlive(0)).unwrap();
+        }
+        assert!(sender.send(ClientboundPacket::KeepAlive(0)).is_err());
+        assert!(sender.is_overflowed());
+        receiver.overflowed().await;
+        assert!(receiver.recv().await.is_none());
+    }
+}
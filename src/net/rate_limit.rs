@@ -0,0 +1,53 @@
+use std::time::Instant;
+
+// Token bucket letting a client send `rate` packets per second on average,
+// in bursts of up to a second's worth
+pub struct RateLimiter {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::RateLimiter;
+
+    #[test]
+    fn rate_limit_test() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter { rate: 10.0, tokens: 10.0, last_refill: start };
+        for _ in 0..10 {
+            assert!(limiter.try_acquire_at(start));
+        }
+        assert!(!limiter.try_acquire_at(start));
+        assert!(limiter.try_acquire_at(start + Duration::from_millis(100)));
+        assert!(!limiter.try_acquire_at(start + Duration::from_millis(100)));
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::mpsc::Sender;
+use anyhow::{Result, anyhow};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use crate::commands::{CommandRequest, CommandSource};
+
+const RESPONSE_VALUE: i32 = 0;
+const AUTH_RESPONSE: i32 = 2;
+const EXEC_COMMAND: i32 = 2;
+const AUTH: i32 = 3;
+// Same limits as vanilla, bigger responses are split in several packets
+const MAX_REQUEST_LENGTH: usize = 1460;
+const MAX_RESPONSE_LENGTH: usize = 4096;
+
+// Source RCON protocol, as implemented by vanilla's `enable-rcon`
+pub async fn listen(port: u16, password: String, commands: Sender<CommandRequest>)
+    -> Result<()>
+{
+    let listener = TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
+    log!("RCON listening on port {}", port);
+    loop {
+        let (conn, address) = listener.accept().await?;
+        let password = password.clone();
+        let commands = commands.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle_client(conn, &password, commands).await {
+                log!("RCON connection from {} closed: {}", address, err);
+            }
+        });
+    }
+}
+
+async fn handle_client<S>(mut conn: S, password: &str, commands: Sender<CommandRequest>)
+    -> Result<()> where S: AsyncRead + AsyncWrite + Unpin
+{
+    let mut authenticated = false;
+    loop {
+        let packet = match read_packet(&mut conn).await {
+            Ok(packet) => packet,
+            // The client just closed the connection
+            Err(err) if is_eof(&err) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        match packet.kind {
+            AUTH => {
+                authenticated = !password.is_empty() && packet.body == password;
+                let id = if authenticated { packet.id } else { -1 };
+                write_packet(&mut conn, id, AUTH_RESPONSE, "").await?;
+            }
+            EXEC_COMMAND if authenticated => {
+                let (reply, output) = oneshot::channel();
+                commands.send(CommandRequest {
+                    command: packet.body,
+                    source: CommandSource::Rcon,
+                    reply: Some(reply),
+                })
+                    .map_err(|_| anyhow!("Server is stopping"))?;
+                let output = output.await?;
+                send_response(&mut conn, packet.id, &output).await?;
+            }
+            EXEC_COMMAND => {
+                write_packet(&mut conn, -1, AUTH_RESPONSE, "").await?;
+            }
+            kind => {
+                let message = format!("Unknown request {:x}", kind);
+                write_packet(&mut conn, packet.id, RESPONSE_VALUE, &message).await?;
+            }
+        }
+    }
+}
+
+struct RconPacket {
+    id: i32,
+    kind: i32,
+    body: String,
+}
+
+async fn read_packet<R>(reader: &mut R) -> Result<RconPacket> where R: AsyncRead + Unpin {
+    let length = reader.read_i32_le().await? as usize;
+    // ID, type and the two null bytes
+    if !(10..=MAX_REQUEST_LENGTH).contains(&length) {
+        return Err(anyhow!("Invalid RCON packet length: {}", length));
+    }
+    let mut buffer = vec![0; length];
+    reader.read_exact(&mut buffer).await?;
+    let id = i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+    let kind = i32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
+    let body = &buffer[8..];
+    let end = body.iter().position(|byte| *byte == 0).unwrap_or(body.len());
+    Ok(RconPacket {
+        id,
+        kind,
+        body: String::from_utf8_lossy(&body[..end]).into_owned(),
+    })
+}
+
+async fn write_packet<W>(writer: &mut W, id: i32, kind: i32, body: &str) -> Result<()>
+    where W: AsyncWrite + Unpin
+{
+    let mut packet = Vec::with_capacity(body.len() + 14);
+    packet.extend_from_slice(&(body.len() as i32 + 10).to_le_bytes());
+    packet.extend_from_slice(&id.to_le_bytes());
+    packet.extend_from_slice(&kind.to_le_bytes());
+    packet.extend_from_slice(body.as_bytes());
+    packet.extend_from_slice(&[0, 0]);
+    writer.write_all(&packet).await?;
+    Ok(())
+}
+
+async fn send_response<W>(writer: &mut W, id: i32, output: &str) -> Result<()>
+    where W: AsyncWrite + Unpin
+{
+    let mut rest = output;
+    loop {
+        let mut split = rest.len().min(MAX_RESPONSE_LENGTH);
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        let (part, remaining) = rest.split_at(split);
+        write_packet(writer, id, RESPONSE_VALUE, part).await?;
+        if remaining.is_empty() {
+            return Ok(());
+        }
+        rest = remaining;
+    }
+}
+
+fn is_eof(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .is_some_and(|err| err.kind() == std::io::ErrorKind::UnexpectedEof)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use super::{AUTH, AUTH_RESPONSE, EXEC_COMMAND, RESPONSE_VALUE};
+    use super::{handle_client, read_packet, write_packet};
+
+    #[tokio::test]
+    async fn rcon_test() {
+        let (mut client, server) = tokio::io::duplex(8192);
+        let (commands, requests) = channel();
+        tokio::spawn(handle_client(server, "secret", commands));
+        std::thread::spawn(move || {
+            for request in requests {
+                let output = format!("Ran {}", request.command);
+                request.reply.unwrap().send(output).unwrap();
+            }
+        });
+
+        write_packet(&mut client, 1, EXEC_COMMAND, "list").await.unwrap();
+        assert_eq!(read_packet(&mut client).await.unwrap().id, -1);
+
+        write_packet(&mut client, 2, AUTH, "wrong").await.unwrap();
+        assert_eq!(read_packet(&mut client).await.unwrap().id, -1);
+
+        write_packet(&mut client, 3, AUTH, "secret").await.unwrap();
+        let response = read_packet(&mut client).await.unwrap();
+        assert_eq!((response.id, response.kind), (3, AUTH_RESPONSE));
+
+        write_packet(&mut client, 4, EXEC_COMMAND, "list").await.unwrap();
+        let response = read_packet(&mut client).await.unwrap();
+        assert_eq!((response.id, response.kind), (4, RESPONSE_VALUE));
+        assert_eq!(response.body, "Ran list");
+    }
+}
@@ -1,70 +1,145 @@
 use crate::config::Config;
 use tokio::net::{TcpListener, TcpStream};
-use uuid::Uuid;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender, TryIter, channel};
 use anyhow::{Result, anyhow};
-use super::initial_handling::initial_handling;
+use super::initial_handling::{initial_handling, NewPlayer};
 use super::server_info::ServerInfo;
+use super::settings::ConnectionSettings;
 use super::{PlayerConnection, connection::connection};
 use super::play::play;
+use super::{proxy_protocol, query, rcon};
+use crate::commands::CommandRequest;
+use crate::access::AccessLists;
 use std::sync::RwLock;
+use uuid::Uuid;
 
 pub struct Server {
-    player_recv: Receiver<(Uuid, String, PlayerConnection)>,
+    player_recv: Receiver<(NewPlayer, PlayerConnection)>,
     info: Arc<RwLock<ServerInfo>>,
+    access: Arc<RwLock<AccessLists>>,
 }
 
 impl Server {
-    pub fn start(config: &Config) -> Self {
-        let info = Arc::new(RwLock::new(ServerInfo::new(&config.motd)));
+    pub fn start(config: &Config, command_send: Sender<CommandRequest>) -> Self {
+        let (server, acceptor) = Self::new(config);
+        tokio::spawn(acceptor.listen(config.port));
+        if config.enable_query {
+            let (port, game_port, info) = (config.query_port, config.port, server.info.clone());
+            tokio::spawn(async move {
+                if let Err(err) = query::listen(port, game_port, info).await {
+                    log!("Query stopped: {}", err);
+                }
+            });
+        }
+        if config.enable_rcon {
+            if config.rcon_password.is_empty() {
+                log!("RCON is enabled but rcon_password is empty, not starting it");
+            } else {
+                let (port, password) = (config.rcon_port, config.rcon_password.clone());
+                tokio::spawn(async move {
+                    if let Err(err) = rcon::listen(port, password, command_send).await {
+                        log!("RCON stopped: {}", err);
+                    }
+                });
+            }
+        }
+        server
+    }
+
+    // Accepts players from a listener that's already bound, without query
+    // or RCON, so tests can pick a free port
+    #[cfg(test)]
+    pub fn with_listener(config: &Config, listener: TcpListener) -> Self {
+        let (server, acceptor) = Self::new(config);
+        tokio::spawn(acceptor.accept(listener));
+        server
+    }
+
+    fn new(config: &Config) -> (Self, Acceptor) {
+        let info = Arc::new(RwLock::new(ServerInfo::new(config)));
+        let access = Arc::new(RwLock::new(AccessLists::load(&config.data_dir, config.whitelist)));
         let (player_send, player_recv) = channel();
-        tokio::spawn(listen(
-            config.port, 
-            player_send, 
-            info.clone(),
-        ));
-        Self {
+        let acceptor = Acceptor {
+            settings: Arc::new(ConnectionSettings::new(config)),
+            player_send,
+            info: info.clone(),
+            access: access.clone(),
+        };
+        let server = Self {
             player_recv,
             info,
-        }
+            access,
+        };
+        (server, acceptor)
     }
 
-    pub fn get_new_players(&mut self) -> Vec<(Uuid, String, PlayerConnection)> {
+    pub fn get_new_players(&mut self) -> Vec<(NewPlayer, PlayerConnection)> {
         self.player_recv.try_iter().collect()
     }
 
-    pub fn update_list(&mut self, count: usize, sample: Vec<String>) {
-        self.info.write().unwrap().update_players(sample, count);
+    pub fn access(&self) -> Arc<RwLock<AccessLists>> {
+        self.access.clone()
+    }
+
+    pub fn max_players(&self) -> usize {
+        self.info.read().unwrap().max_players()
+    }
+
+    pub fn update_list(&mut self, players: Vec<(Uuid, String)>) {
+        self.info.write().unwrap().update_players(players);
     }
 }
 
-async fn listen(port: u16, player_send: Sender<(Uuid, String, PlayerConnection)>, 
-    info: Arc<RwLock<ServerInfo>>) -> Result<()> 
-{
-    let addr = format!("0.0.0.0:{}", port);
-    let listener = TcpListener::bind(addr).await?;
-    println!("Listening on port {}", port);
-    loop {
-        let (conn, address) = listener.accept().await?;
-        conn.set_nodelay(true).unwrap();
-        let status = info.read().unwrap().to_status_str();
-        tokio::spawn(handle_to_end(
-            conn, 
-            status, 
-            player_send.clone()));
+// What each new connection needs, the other end of `Server`
+struct Acceptor {
+    settings: Arc<ConnectionSettings>,
+    player_send: Sender<(NewPlayer, PlayerConnection)>,
+    info: Arc<RwLock<ServerInfo>>,
+    access: Arc<RwLock<AccessLists>>,
+}
+
+impl Acceptor {
+    async fn listen(self, port: u16) -> Result<()> {
+        let addr = format!("0.0.0.0:{}", port);
+        let listener = TcpListener::bind(addr).await?;
+        log!("Listening on port {}", port);
+        self.accept(listener).await
+    }
+
+    async fn accept(self, listener: TcpListener) -> Result<()> {
+        loop {
+            let (conn, address) = listener.accept().await?;
+            conn.set_nodelay(true).unwrap();
+            tokio::spawn(handle_to_end(
+                conn, 
+                address,
+                self.info.clone(),
+                self.access.clone(),
+                self.settings.clone(),
+                self.player_send.clone()));
+        }
     }
 }
 
-async fn handle_to_end(mut conn: TcpStream, status: String, 
-    player_send: Sender<(Uuid, String, PlayerConnection)>) -> Result<()> 
+async fn handle_to_end(mut conn: TcpStream, address: SocketAddr, info: Arc<RwLock<ServerInfo>>,
+    access: Arc<RwLock<AccessLists>>, settings: Arc<ConnectionSettings>, player_send: Sender<(NewPlayer, PlayerConnection)>) 
+    -> Result<()> 
 {
-    if let Some(player) = initial_handling(&mut conn, status).await {
-        let (player_conn, game_conn) = connection();
-        player_send.send((player.0, player.1, player_conn)).map_err(|e| {
+    let address = if settings.proxy_protocol {
+        proxy_protocol::read_header(&mut conn).await?.unwrap_or(address)
+    } else {
+        address
+    };
+    let player = initial_handling(&mut conn, address.ip(), &info, &access, &settings).await;
+    if let Some((player, encryption)) = player {
+        let (player_conn, game_conn) = connection(settings.max_queued_bytes);
+        player_send.send((player, player_conn)).map_err(|e| {
             anyhow!("Couldn't send new player to the server: {:?}", e)
         })?;
-        play(conn, game_conn).await?;
+        play(conn, game_conn, settings.compression, settings.packet_rate_limit, encryption)
+            .await?;
     }
     Ok(())
 }
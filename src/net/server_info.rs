@@ -1,58 +1,167 @@
+use std::fs;
+use std::io::ErrorKind::NotFound;
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use serde::Serialize;
-use serde_json::json;
+use serde_json::{Map, Value as Json, json};
+use uuid::Uuid;
+use crate::config::Config;
+use crate::text::TextComponent;
+use super::{PROTOCOL_VERSION, VERSION_NAME};
+use super::initial_handling::LegacyFormat;
+
+const ICON_PATH: &str = "server-icon.png";
+const ICON_SIZE: u32 = 64;
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const SAMPLE_SIZE: usize = 12;
+// Protocol number legacy clients get, they'll show the server as incompatible
+const LEGACY_PROTOCOL_VERSION: u32 = 127;
 
 pub struct ServerInfo {
     motd: String,
-    player_sample: Vec<PlayerSample>,
-    player_count: usize,
+    max_players: usize,
+    hide_player_sample: bool,
+    favicon: Option<String>,
+    players: Vec<(Uuid, String)>,
 }
 
 impl ServerInfo {
-    pub fn new(motd: &str) -> Self {
+    pub fn new(config: &Config) -> Self {
+        let favicon = load_favicon(&config.data_dir.join(ICON_PATH)).unwrap_or_else(|err| {
+            log!("Couldn't load {}: {}", ICON_PATH, err);
+            None
+        });
         Self {
-            motd: motd.to_owned(),
-            player_sample: vec![],
-            player_count: 0,
+            motd: config.motd.clone(),
+            max_players: config.max_players,
+            hide_player_sample: config.hide_player_sample,
+            favicon,
+            players: vec![],
         }
     }
 
-    pub fn update_players(&mut self, sample: Vec<String>, count: usize) {
-        self.player_sample = sample.into_iter().map(|name| {
-            PlayerSample::new(name)
-        }).collect();
-        self.player_count = count;
+    pub fn update_players(&mut self, players: Vec<(Uuid, String)>) {
+        self.players = players;
+    }
+
+    // MOTD with the {online} and {max} placeholders filled in
+    pub fn motd(&self) -> String {
+        self.motd
+            .replace("{online}", &self.players.len().to_string())
+            .replace("{max}", &self.max_players.to_string())
+    }
+
+    pub fn player_names(&self) -> impl Iterator<Item = &str> {
+        self.players.iter().map(|(_, name)| name.as_str())
+    }
+
+    pub fn online_players(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn max_players(&self) -> usize {
+        self.max_players
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= self.max_players
     }
 
     pub fn to_status_str(&self) -> String {
-        json!({
-            "version": {
-                "name": "1.16.5",
-                "protocol": 754
-            },
-            "players": {
-                "max": 100,
-                "online": self.player_count,
-                "sample": self.player_sample,
-            },
-            "description": {
-                "text": self.motd,
-            },
-            "favicon": "data:image/png;base64,<data>"
-        }).to_string()
+        let mut players = json!({
+            "max": self.max_players,
+            "online": self.players.len(),
+        });
+        if !self.hide_player_sample {
+            let sample: Vec<PlayerSample> = self.players.iter()
+                .take(SAMPLE_SIZE)
+                .map(|(uuid, name)| PlayerSample::new(*uuid, name.clone()))
+                .collect();
+            players["sample"] = json!(sample);
+        }
+        let mut status = Map::new();
+        status.insert("version".into(), json!({
+            "name": VERSION_NAME,
+            "protocol": PROTOCOL_VERSION
+        }));
+        status.insert("players".into(), players);
+        status.insert("description".into(), json!(TextComponent::text(self.motd())));
+        if let Some(favicon) = &self.favicon {
+            status.insert("favicon".into(), json!(favicon));
+        }
+        Json::Object(status).to_string()
+    }
+
+    pub fn to_legacy_str(&self, format: LegacyFormat) -> String {
+        match format {
+            LegacyFormat::Beta => format!("{}§{}§{}",
+                self.motd().replace('§', ""), self.players.len(), self.max_players),
+            LegacyFormat::Extended => format!("§1\0{}\0{}\0{}\0{}\0{}",
+                LEGACY_PROTOCOL_VERSION, VERSION_NAME, self.motd(),
+                self.players.len(), self.max_players),
+        }
+    }
+}
+
+// The icon is optional, so a missing file isn't an error
+fn load_favicon(path: &Path) -> Result<Option<String>> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(Some(encode_favicon(&data)?))
+}
+
+fn encode_favicon(data: &[u8]) -> Result<String> {
+    // The signature is followed by the IHDR chunk: length, type, width and height
+    if data.len() < 24 || !data.starts_with(PNG_SIGNATURE) || &data[12..16] != b"IHDR" {
+        return Err(anyhow!("Not a PNG image"));
     }
+    let width = u32::from_be_bytes([data[16], data[17], data[18], data[19]]);
+    let height = u32::from_be_bytes([data[20], data[21], data[22], data[23]]);
+    if width != ICON_SIZE || height != ICON_SIZE {
+        return Err(anyhow!("Icon must be {0}x{0} pixels, not {1}x{2}",
+            ICON_SIZE, width, height));
+    }
+    Ok(format!("data:image/png;base64,{}", BASE64.encode(data)))
 }
 
 #[derive(Serialize)]
 struct PlayerSample {
     name: String,
-    id: &'static str,
+    id: String,
 }
 
 impl PlayerSample {
-    fn new(name: String) -> Self {
+    fn new(uuid: Uuid, name: String) -> Self {
         Self {
             name,
-            id: "4566e69f-c907-48ee-8d71-d7ba5aa00d20",
+            id: uuid.to_hyphenated().to_string(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{PNG_SIGNATURE, encode_favicon};
+
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend_from_slice(&13u32.to_be_bytes());
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn favicon_test() {
+        let favicon = encode_favicon(&png_header(64, 64)).unwrap();
+        assert!(favicon.starts_with("data:image/png;base64,iVBORw0KGgo"));
+        assert!(encode_favicon(&png_header(128, 64)).is_err());
+        assert!(encode_favicon(b"GIF89a").is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use crate::config::{Config, Forwarding};
+use std::time::Duration;
+use super::initial_handling::Authenticator;
+use super::throttle::ConnectionThrottle;
+
+pub struct ConnectionSettings {
+    pub compression: Option<u32>,
+    pub auth: Option<Authenticator>,
+    pub forwarding: Forwarding,
+    pub max_queued_bytes: usize,
+    pub packet_rate_limit: Option<u32>,
+    pub proxy_protocol: bool,
+    pub throttle: Option<ConnectionThrottle>,
+}
+
+impl ConnectionSettings {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            compression: config.compression_threshold,
+            auth: if config.online_mode {
+                Some(Authenticator::new(&config.session_server))
+            } else {
+                None
+            },
+            forwarding: config.forwarding.clone(),
+            max_queued_bytes: config.outbound_queue_limit,
+            packet_rate_limit: config.packet_rate_limit,
+            proxy_protocol: config.proxy_protocol,
+            throttle: config.connection_throttle
+                .map(|millis| ConnectionThrottle::new(Duration::from_millis(millis))),
+        }
+    }
+}
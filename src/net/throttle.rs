@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Lets each address log in once per `interval`, like Bukkit's
+// connection-throttle. Server list pings aren't counted
+pub struct ConnectionThrottle {
+    interval: Duration,
+    last_attempts: Mutex<HashMap<IpAddr, Instant>>,
+}
+
+impl ConnectionThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn try_connect(&self, address: IpAddr) -> bool {
+        self.try_connect_at(address, Instant::now())
+    }
+
+    fn try_connect_at(&self, address: IpAddr, now: Instant) -> bool {
+        let mut last_attempts = self.last_attempts.lock().unwrap();
+        last_attempts.retain(|_, last| now.duration_since(*last) < self.interval);
+        let allowed = !last_attempts.contains_key(&address);
+        // Retrying too early restarts the wait
+        last_attempts.insert(address, now);
+        allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::ConnectionThrottle;
+
+    #[test]
+    fn throttle_test() {
+        let throttle = ConnectionThrottle::new(Duration::from_secs(4));
+        let start = Instant::now();
+        let address = "10.0.0.1".parse().unwrap();
+        assert!(throttle.try_connect_at(address, start));
+        assert!(!throttle.try_connect_at(address, start + Duration::from_secs(1)));
+        assert!(throttle.try_connect_at("10.0.0.2".parse().unwrap(), start));
+        assert!(throttle.try_connect_at(address, start + Duration::from_secs(6)));
+    }
+}
@@ -2,6 +2,10 @@ use nalgebra::{Vector3, vector};
 use tokio::io::{AsyncRead, AsyncReadExt};
 use anyhow::{Result, anyhow};
 
+// Default maximum length of a string in characters, used where vanilla
+// doesn't have a tighter limit
+pub const MAX_STRING_LENGTH: usize = 32767;
+
 pub async fn read_varint<R: AsyncRead>(reader: &mut R) -> Result<u32>
     where R: Unpin
 {
@@ -21,13 +25,34 @@ pub async fn read_varint<R: AsyncRead>(reader: &mut R) -> Result<u32>
     }
 }
 
-pub async fn read_str<R: AsyncRead>(reader: &mut R) -> Result<String>
+// `max_length` is in characters, like in vanilla, and each one can take up
+// to 4 bytes so the byte length is checked before allocating anything
+pub async fn read_str<R: AsyncRead>(reader: &mut R, max_length: usize) -> Result<String>
     where R: Unpin
 {
     let length = read_varint(reader).await? as usize;
+    if length > max_length * 4 {
+        return Err(anyhow!("String is too long ({} > {})", length, max_length * 4));
+    }
+    let mut buffer = vec![0; length];
+    reader.read_exact(buffer.as_mut_slice()).await?;
+    let string = String::from_utf8(buffer).map_err(|e| anyhow::Error::new(e))?;
+    if string.chars().count() > max_length {
+        return Err(anyhow!("String is too long (more than {} characters)", max_length));
+    }
+    Ok(string)
+}
+
+pub async fn read_bytes<R>(reader: &mut R, max_length: usize) -> Result<Vec<u8>>
+    where R: AsyncRead + Unpin
+{
+    let length = read_varint(reader).await? as usize;
+    if length > max_length {
+        return Err(anyhow!("Byte array is too long ({} > {})", length, max_length));
+    }
     let mut buffer = vec![0; length];
     reader.read_exact(buffer.as_mut_slice()).await?;
-    String::from_utf8(buffer).map_err(|e| anyhow::Error::new(e))
+    Ok(buffer)
 }
 
 pub async fn read_block_pos<R: AsyncRead>(reader: &mut R) -> Result<Vector3<i32>>
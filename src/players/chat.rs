@@ -0,0 +1,63 @@
+use legion::*;
+use uuid::Uuid;
+use world::SubWorld;
+use crate::net::{ChatPosition, ClientboundPacket, PlayerConnection};
+use crate::text::TextComponent;
+
+pub struct Chat {
+    pending: Vec<(TextComponent, ChatPosition, Uuid)>,
+}
+
+impl Chat {
+    pub fn new() -> Self {
+        Self {
+            pending: vec![],
+        }
+    }
+
+    pub fn broadcast(&mut self, message: TextComponent, position: ChatPosition) {
+        if position != ChatPosition::GameInfo {
+            log!("{}", message.to_plain());
+        }
+        self.pending.push((message, position, Uuid::nil()));
+    }
+
+    pub fn player_message(&mut self, sender: Uuid, name: &str, message: &str) {
+        log!("<{}> {}", name, message);
+        let message = TextComponent::translate("chat.type.text", vec![
+            name.into(),
+            message.into(),
+        ]);
+        self.pending.push((message, ChatPosition::Chat, sender));
+    }
+}
+
+impl Default for Chat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Same rules as vanilla, which kicks players sending any of these
+pub fn has_illegal_characters(message: &str) -> bool {
+    message.chars().any(|c| c == '§' || c < ' ' || c == '\u{7F}')
+}
+
+pub fn normalize_message(message: &str) -> String {
+    message.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[system]
+#[read_component(PlayerConnection)]
+pub fn send_chat(world: &SubWorld, #[resource] chat: &mut Chat) {
+    if chat.pending.is_empty() {
+        return;
+    }
+    let mut query = <&PlayerConnection>::query();
+    for (message, position, sender) in chat.pending.drain(..) {
+        let packet = ClientboundPacket::ChatMessage { message, position, sender };
+        query.for_each(world, |conn| {
+            conn.send(packet.clone());
+        });
+    }
+}
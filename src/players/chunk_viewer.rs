@@ -1,14 +1,14 @@
 use legion::*;
-use tokio::sync::mpsc::UnboundedSender;
 use std::collections::HashSet;
 use std::sync::{Arc, RwLock};
 use nalgebra::{Vector3, vector};
 use crate::chunks::ChunkData;
 use crate::chunks::events::ChunkEvent;
-use crate::net::PlayerConnection;
+use crate::net::{PacketSender, PlayerConnection};
 use crate::entities::{EntityId, Position};
 use crate::chunks::{ChunkCoords, World as Chunks};
 use crate::net::ClientboundPacket;
+use super::new_players::world_spawn;
 
 #[system(for_each)]
 pub fn update_chunk_view(id: &EntityId, pos: &Position, view: &mut ChunkViewer, 
@@ -31,9 +31,14 @@ pub fn update_chunk_view(id: &EntityId, pos: &Position, view: &mut ChunkViewer,
         chunks.unsubscribe(coords, id.0);
         conn.send(ClientboundPacket::UnloadChunk(coords.0, coords.1));
     }
+    // Like vanilla, after the chunk the player is in
+    if !view.sent_spawn && chunks.is_loaded(ChunkCoords::from_pos(&pos.0)) {
+        conn.send(ClientboundPacket::SpawnPosition(world_spawn()));
+        view.sent_spawn = true;
+    }
 }
 
-fn handle_chunk_event(sender: &UnboundedSender<ClientboundPacket>, 
+fn handle_chunk_event(sender: &PacketSender, 
     coords: ChunkCoords, event: ChunkEvent)
 {
     match event {
@@ -48,7 +53,7 @@ fn handle_chunk_event(sender: &UnboundedSender<ClientboundPacket>,
     }
 }
 
-fn send_chunk(sender: &UnboundedSender<ClientboundPacket>, 
+fn send_chunk(sender: &PacketSender, 
     coords: ChunkCoords, chunk: Arc<RwLock<ChunkData>>)
 {
     let chunk = chunk.read().unwrap();
@@ -79,10 +84,14 @@ fn send_chunk(sender: &UnboundedSender<ClientboundPacket>,
     });
 }
 
+// Radius in chunks from the config, the same for every player
+pub struct ViewDistance(pub u8);
+
 pub struct ChunkViewer {
     pub in_view: HashSet<ChunkCoords>,
     last_pos: Option<Vector3<f64>>,
     range: i32,
+    sent_spawn: bool,
 }
 
 impl ChunkViewer {
@@ -91,6 +100,7 @@ impl ChunkViewer {
             last_pos: None,
             range,
             in_view: HashSet::new(),
+            sent_spawn: false,
         }
     }
 
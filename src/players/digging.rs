@@ -0,0 +1,292 @@
+use std::time::{Duration, Instant};
+use legion::*;
+use world::SubWorld;
+use nalgebra::{Vector3, vector};
+use crate::blocks::Block;
+use crate::buckets::EntityTracker;
+use crate::buckets::events::{EntityEvent, EntityEventData};
+use crate::chunks::World as ChunkWorld;
+use crate::entities::{EntityId, ItemSpawner, OnGround, Position};
+use crate::inventory::Inventory;
+use crate::items::{ItemStack, Tool};
+use crate::loot::block_drops;
+use crate::net::{ClientboundPacket, PlayerConnection};
+use super::game_mode::GameMode;
+
+const START_DIGGING: u8 = 0;
+const CANCEL_DIGGING: u8 = 1;
+const FINISH_DIGGING: u8 = 2;
+const TICK_DURATION: Duration = Duration::from_millis(50);
+const EYE_HEIGHT: f64 = 1.62;
+// Squared distance from the block's center, the same check as vanilla
+const MAX_REACH_SQUARED: f64 = 36.0;
+const MIN_FINISH_PROGRESS: f32 = 0.7;
+// Any stage outside 0 to 9 removes the cracks
+const NO_STAGE: i8 = -1;
+
+// The block a survival player is breaking. The client says when it's done,
+// the server only checks that it didn't take too little time
+pub struct Digging {
+    target: Option<Target>,
+}
+
+struct Target {
+    pos: Vector3<i32>,
+    started: Instant,
+    stage: i8,
+}
+
+// What decides how fast a player breaks blocks
+pub struct Digger<'a> {
+    pub id: u32,
+    pub position: Vector3<f64>,
+    pub on_ground: bool,
+    pub gamemode: GameMode,
+    pub held: Option<&'a ItemStack>,
+}
+
+impl Digging {
+    pub fn new() -> Self {
+        Self { target: None }
+    }
+
+    // Returns whether it worked, for the statuses the client waits an
+    // answer to. Send it with `acknowledge`
+    pub fn handle(&mut self, status: u8, pos: Vector3<i32>, digger: &Digger,
+        chunks: &ChunkWorld, tracker: &EntityTracker, items: &mut ItemSpawner) -> Option<bool>
+    {
+        match status {
+            START_DIGGING => {
+                if let Some(previous) = self.target.take() {
+                    send_stage(tracker, digger, previous.pos, NO_STAGE);
+                }
+                if !digger.gamemode.may_build() || !in_reach(digger.position, pos) {
+                    return Some(false);
+                }
+                let progress = break_progress(chunks.get_block(&pos), digger,
+                    is_underwater(digger.position, chunks));
+                if digger.gamemode.is_creative() || progress >= 1.0 {
+                    destroy(chunks, pos, digger, items);
+                } else {
+                    self.target = Some(Target {
+                        pos,
+                        started: Instant::now(),
+                        stage: NO_STAGE,
+                    });
+                }
+                Some(true)
+            }
+            CANCEL_DIGGING => {
+                if let Some(previous) = self.target.take() {
+                    send_stage(tracker, digger, previous.pos, NO_STAGE);
+                }
+                Some(true)
+            }
+            FINISH_DIGGING => match self.target.take() {
+                Some(target) if target.pos == pos && digger.gamemode.may_build() => {
+                    send_stage(tracker, digger, pos, NO_STAGE);
+                    let progress = break_progress(chunks.get_block(&pos), digger,
+                        is_underwater(digger.position, chunks));
+                    let finished = in_reach(digger.position, pos)
+                        && is_finished(progress, target.ticks());
+                    if finished {
+                        destroy(chunks, pos, digger, items);
+                    }
+                    Some(finished)
+                }
+                _ => Some(false),
+            },
+            _ => None,
+        }
+    }
+}
+
+// Tells the client which block is really there, after breaking it or not
+pub fn acknowledge(conn: &PlayerConnection, chunks: &ChunkWorld, status: u8, pos: Vector3<i32>,
+    successful: bool)
+{
+    conn.send(ClientboundPacket::AcknowledgePlayerDigging {
+        pos,
+        block_state: chunks.get_block(&pos).id as u32,
+        status,
+        successful,
+    });
+}
+
+impl Target {
+    // Whole ticks since it started
+    fn ticks(&self) -> u32 {
+        (self.started.elapsed().as_millis() / TICK_DURATION.as_millis()) as u32
+    }
+}
+
+// Shows the cracks to everyone nearby, as the block gets closer to breaking
+#[system]
+#[read_component(EntityId)]
+#[read_component(Position)]
+#[read_component(OnGround)]
+#[read_component(Inventory)]
+#[read_component(GameMode)]
+#[write_component(Digging)]
+pub fn update_digging(world: &mut SubWorld, #[resource] chunks: &ChunkWorld,
+    #[resource] tracker: &EntityTracker)
+{
+    let mut query =
+        <(&EntityId, &Position, &OnGround, &Inventory, &GameMode, &mut Digging)>::query();
+    for (id, position, on_ground, inventory, gamemode, digging) in query.iter_mut(world) {
+        let target = match &mut digging.target {
+            Some(target) => target,
+            None => continue,
+        };
+        let digger = Digger {
+            id: id.0,
+            position: position.0,
+            on_ground: on_ground.0,
+            gamemode: *gamemode,
+            held: inventory.get_held(),
+        };
+        let progress = break_progress(chunks.get_block(&target.pos), &digger,
+            is_underwater(digger.position, chunks)) * (target.ticks() + 1) as f32;
+        let stage = ((progress * 10.0) as i8).min(9);
+        if stage != target.stage {
+            target.stage = stage;
+            send_stage(tracker, &digger, target.pos, stage);
+        }
+    }
+}
+
+// Fraction of the block broken each tick, 1 or more breaks it instantly
+pub fn break_progress(block: &Block, digger: &Digger, underwater: bool) -> f32 {
+    let hardness = block.btype.hardness;
+    if hardness < 0.0 {
+        return 0.0;
+    }
+    let tool = digger.held.and_then(|stack| Tool::from_item(stack.item));
+    let mut speed = tool.map(|tool| tool.destroy_speed(block)).unwrap_or(1.0);
+    if speed > 1.0 {
+        let efficiency = digger.held
+            .map(|stack| stack.enchantment_level("minecraft:efficiency"))
+            .unwrap_or(0) as f32;
+        if efficiency > 0.0 {
+            speed += efficiency * efficiency + 1.0;
+        }
+    }
+    if underwater {
+        speed /= 5.0;
+    }
+    if !digger.on_ground {
+        speed /= 5.0;
+    }
+    speed / hardness / if can_harvest(block, digger.held) { 30.0 } else { 100.0 }
+}
+
+// Like vanilla, finishing is accepted a bit early because of lag
+fn is_finished(progress: f32, ticks: u32) -> bool {
+    progress * (ticks + 1) as f32 >= MIN_FINISH_PROGRESS
+}
+
+fn in_reach(position: Vector3<f64>, pos: Vector3<i32>) -> bool {
+    let center = pos.cast::<f64>() + vector!(0.5, 0.5 - 1.5, 0.5);
+    (position - center).norm_squared() <= MAX_REACH_SQUARED
+}
+
+// Blocks that require a tool don't drop anything without the right one
+fn can_harvest(block: &Block, held: Option<&ItemStack>) -> bool {
+    !block.btype.requires_tool || held
+        .and_then(|stack| Tool::from_item(stack.item))
+        .map(|tool| tool.is_correct_for_drops(block))
+        .unwrap_or(false)
+}
+
+fn is_underwater(position: Vector3<f64>, chunks: &ChunkWorld) -> bool {
+    let eyes = position + vector!(0.0, EYE_HEIGHT, 0.0);
+    let eyes = eyes.map(|coord| coord.floor() as i32);
+    chunks.get_block(&eyes).material.name == "minecraft:water"
+}
+
+// Nothing drops in creative
+fn destroy(chunks: &ChunkWorld, pos: Vector3<i32>, digger: &Digger, items: &mut ItemSpawner) {
+    let block = chunks.get_block(&pos);
+    if !digger.gamemode.is_creative() && can_harvest(block, digger.held) {
+        for stack in block_drops(block, digger.held) {
+            items.spawn_at_block(pos, stack);
+        }
+    }
+    let view = chunks.get_view(pos);
+    block.destroy(&view);
+}
+
+// The digger already sees its own cracks
+fn send_stage(tracker: &EntityTracker, digger: &Digger, pos: Vector3<i32>, stage: i8) {
+    tracker.send_event(&digger.position, EntityEvent {
+        id: digger.id,
+        data: EntityEventData::BreakBlock { pos, stage },
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+    use crate::blocks::{Block, BlockType};
+    use crate::items::{ItemStack, ItemType};
+    use super::{Digger, GameMode, break_progress, in_reach, is_finished};
+
+    fn stone() -> &'static Block {
+        let btype = BlockType::from_name("minecraft:stone").unwrap();
+        btype.with_props(&btype.default_state).unwrap()
+    }
+
+    fn stack(name: &str) -> ItemStack {
+        ItemStack { item: ItemType::from_name(name).unwrap(), count: 1, nbt: None }
+    }
+
+    fn digger(held: Option<&ItemStack>, on_ground: bool) -> Digger<'_> {
+        Digger {
+            id: 0,
+            position: vector!(0.5, 64.0, 0.5),
+            on_ground,
+            gamemode: GameMode::Survival,
+            held,
+        }
+    }
+
+    #[test]
+    fn break_progress_test() {
+        let pickaxe = stack("minecraft:diamond_pickaxe");
+        let shovel = stack("minecraft:diamond_shovel");
+        // The right tool is faster and lets it drop, the wrong one is like a hand
+        assert_eq!(break_progress(stone(), &digger(Some(&pickaxe), true), false),
+            8.0 / 1.5 / 30.0);
+        assert_eq!(break_progress(stone(), &digger(Some(&shovel), true), false),
+            1.0 / 1.5 / 100.0);
+        assert_eq!(break_progress(stone(), &digger(None, true), false), 1.0 / 1.5 / 100.0);
+        // Water and not standing on anything are 5 times slower each
+        assert_eq!(break_progress(stone(), &digger(Some(&pickaxe), true), true),
+            8.0 / 5.0 / 1.5 / 30.0);
+        assert_eq!(break_progress(stone(), &digger(Some(&pickaxe), false), false),
+            8.0 / 5.0 / 1.5 / 30.0);
+        assert_eq!(break_progress(stone(), &digger(Some(&pickaxe), false), true),
+            8.0 / 5.0 / 5.0 / 1.5 / 30.0);
+    }
+
+    #[test]
+    fn is_finished_test() {
+        // A diamond pickaxe takes 6 ticks on stone, 4 are about 70%
+        let progress = 8.0 / 1.5 / 30.0;
+        assert!(!is_finished(progress, 0));
+        assert!(!is_finished(progress, 2));
+        assert!(is_finished(progress, 3));
+        assert!(is_finished(progress, 5));
+    }
+
+    #[test]
+    fn in_reach_test() {
+        let position = vector!(0.5, 64.0, 0.5);
+        assert!(in_reach(position, vector!(0, 63, 0)));
+        assert!(in_reach(position, vector!(5, 65, 0)));
+        assert!(in_reach(position, vector!(0, 70, 0)));
+        assert!(!in_reach(position, vector!(6, 65, 1)));
+        assert!(!in_reach(position, vector!(0, 57, 0)));
+        assert!(!in_reach(position, vector!(0, 72, 0)));
+    }
+}
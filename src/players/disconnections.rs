@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::Mutex;
 use std::sync::mpsc::{Receiver, Sender, channel};
 
@@ -8,8 +9,10 @@ use world::SubWorld;
 use crate::buckets::EntityTracker;
 use crate::entities::{EntityId, Position, Rotation};
 use crate::inventory::Inventory;
-use crate::players::player_data::PlayerData;
+use crate::players::player_data::{PlayerData, PlayerDataFolder};
+use super::game_mode::GameMode;
 use crate::chunks::World as Chunks;
+use crate::net::PlayerConnection;
 
 use super::chunk_viewer::ChunkViewer;
 use super::player_list::PlayerList;
@@ -34,30 +37,60 @@ impl DisconnectionQueue {
     } 
 }
 
+impl Default for DisconnectionQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The client isn't reading fast enough to send it anything, including a
+// Disconnect packet, so it's just dropped
+#[system(for_each)]
+pub fn kick_backlogged(entity: &Entity, conn: &PlayerConnection,
+    #[resource] queue: &DisconnectionQueue)
+{
+    if conn.is_overflowed() {
+        queue.send(*entity, "Too many packets queued".to_owned());
+    }
+}
+
 #[system]
 #[read_component(Name)]
 #[read_component(Uuid)]
 #[read_component(Position)]
 #[read_component(Rotation)]
 #[read_component(Inventory)]
+#[read_component(GameMode)]
 pub fn handle_disconnections(world: &mut SubWorld, #[resource] tracker: &EntityTracker, 
-    #[resource] queue: &DisconnectionQueue, cmd: &mut CommandBuffer) 
+    #[resource] queue: &DisconnectionQueue, #[resource] folder: &PlayerDataFolder,
+    cmd: &mut CommandBuffer) 
 {
-    for (entity, reason) in queue.receiver.lock().unwrap().try_recv() {
-        let entry = world.entry_ref(entity).unwrap();
+    let mut handled = HashSet::new();
+    for (entity, reason) in queue.receiver.lock().unwrap().try_iter() {
+        // The same player can be disconnected for several reasons in one tick
+        if !handled.insert(entity) {
+            continue;
+        }
+        let entry = match world.entry_ref(entity) {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
         let name = entry.get_component::<Name>().unwrap().0.clone();
-        println!("{} disconnected, reason: {}", name, reason);
+        log!("{} disconnected, reason: {}", name, reason);
         let uuid = *entry.get_component::<Uuid>().unwrap();
         let position = entry.get_component::<Position>().unwrap().0;
         let rotation = entry.get_component::<Rotation>().unwrap();
         let inventory = entry.get_component::<Inventory>().unwrap().clone();
+        let gamemode = *entry.get_component::<GameMode>().unwrap();
         let data = PlayerData {
             pos: position,
             rotation: (rotation.0, rotation.1),
             inventory,
+            gamemode,
         };
+        let folder = folder.clone();
         tokio::spawn(async move {
-            data.save(uuid).await
+            data.save(&folder, uuid).await
         });
         cmd.exec_mut(move |world, resources| {
             remove_player(entity, world, resources);
@@ -4,15 +4,22 @@ use legion::*;
 use world::SubWorld;
 use crate::buckets::{EntityTracker, Observer};
 use crate::buckets::events::{EntityEvent, EntityEventData};
-use crate::entities::{EntityId, Position, Rotation};
+use crate::entities::{DroppedItem, EntityId, Position, Rotation, Velocity};
 use crate::net::PlayerConnection;
-use crate::net::ClientboundPacket;
+use crate::net::{ClientboundPacket, MetadataValue};
+
+const ITEM_ENTITY_TYPE: u32 = 37;
+// Spawn Entity data for items, anything but 0 works
+const ITEM_DATA: i32 = 1;
+const ITEM_METADATA_INDEX: u8 = 7;
 
 #[system]
 #[read_component(Uuid)]
 #[read_component(EntityId)]
 #[read_component(Position)]
 #[read_component(Rotation)]
+#[read_component(Velocity)]
+#[read_component(DroppedItem)]
 #[read_component(PlayerConnection)]
 #[write_component(Observer)]
 pub fn send_entity_events(world: &mut SubWorld, #[resource] tracker: &EntityTracker) {
@@ -40,17 +47,42 @@ pub fn send_entity_events(world: &mut SubWorld, #[resource] tracker: &EntityTrac
         let entity_id = entry.get_component::<EntityId>().unwrap().0;
         let uuid = *entry.get_component::<Uuid>().unwrap();
         let position = entry.get_component::<Position>().unwrap().0;
-        let rotation = entry.get_component::<Rotation>().unwrap();
+        let packets = match entry.get_component::<DroppedItem>() {
+            Ok(item) => vec![
+                ClientboundPacket::SpawnEntity {
+                    entity_id,
+                    uuid,
+                    entity_type: ITEM_ENTITY_TYPE,
+                    x: position.x,
+                    y: position.y,
+                    z: position.z,
+                    pitch: 0.0,
+                    yaw: 0.0,
+                    data: ITEM_DATA,
+                    velocity: entry.get_component::<Velocity>().unwrap().0,
+                },
+                ClientboundPacket::EntityMetadata {
+                    entity_id,
+                    metadata: vec![(ITEM_METADATA_INDEX, MetadataValue::Slot(Some(item.stack.clone())))],
+                },
+            ],
+            Err(_) => {
+                let rotation = entry.get_component::<Rotation>().unwrap();
+                vec![ClientboundPacket::SpawnPlayer {
+                    entity_id,
+                    uuid,
+                    x: position.x,
+                    y: position.y,
+                    z: position.z,
+                    yaw: rotation.0,
+                    pitch: rotation.1,
+                }]
+            }
+        };
         for sender in senders {
-            sender.send(ClientboundPacket::SpawnPlayer {
-                entity_id,
-                uuid,
-                x: position.x,
-                y: position.y,
-                z: position.z,
-                yaw: rotation.0,
-                pitch: rotation.1,
-            });
+            for packet in &packets {
+                sender.send(packet.clone());
+            }
         }
     }
 }
@@ -92,6 +124,18 @@ fn send_event(event: EntityEvent, conn: &PlayerConnection) {
         EntityEventData::RotateHead { yaw } => {
             conn.send(ClientboundPacket::EntityHeadLook { id, yaw });
         },
+        EntityEventData::BreakBlock { pos, stage } => {
+            conn.send(ClientboundPacket::BlockBreakAnimation { entity_id: id, pos, stage });
+        },
+        EntityEventData::Collect { collector, count } => {
+            conn.send(ClientboundPacket::CollectItem { collected: id, collector, count });
+        },
+        EntityEventData::SetItem { stack } => {
+            conn.send(ClientboundPacket::EntityMetadata {
+                entity_id: id,
+                metadata: vec![(ITEM_METADATA_INDEX, MetadataValue::Slot(Some(stack)))],
+            });
+        },
         _ => panic!("Invalid event")
     }
 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+use legion::world::EntryMut;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+use uuid::Uuid;
+use crate::net::{ClientboundPacket, PlayerConnection};
+use super::player_list::PlayerList;
+
+const CHANGE_GAMEMODE: u8 = 3;
+const INVULNERABLE: u8 = 0x01;
+const FLYING: u8 = 0x02;
+const ALLOW_FLYING: u8 = 0x04;
+const INSTANT_BREAK: u8 = 0x08;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
+pub enum GameMode {
+    Survival = 0,
+    Creative = 1,
+    Adventure = 2,
+    Spectator = 3,
+}
+
+impl GameMode {
+    // How vanilla shows it in command feedback
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Survival => "Survival Mode",
+            Self::Creative => "Creative Mode",
+            Self::Adventure => "Adventure Mode",
+            Self::Spectator => "Spectator Mode",
+        }
+    }
+
+    // Blocks and items aren't used up
+    pub fn is_creative(self) -> bool {
+        self == Self::Creative
+    }
+
+    // Placing and breaking blocks
+    pub fn may_build(self) -> bool {
+        matches!(self, Self::Survival | Self::Creative)
+    }
+
+    // Spectators go through everything without touching it
+    pub fn may_interact(self) -> bool {
+        self != Self::Spectator
+    }
+
+    pub fn abilities(self) -> ClientboundPacket {
+        let flags = match self {
+            Self::Survival | Self::Adventure => 0,
+            Self::Creative => INVULNERABLE | ALLOW_FLYING | INSTANT_BREAK,
+            Self::Spectator => INVULNERABLE | FLYING | ALLOW_FLYING,
+        };
+        ClientboundPacket::PlayerAbilities {
+            flags,
+            flying_speed: 0.05,
+            fov_modifier: 0.1,
+        }
+    }
+}
+
+// Tells the player and everyone's tab list, does nothing if it's the same mode
+pub fn change_game_mode(entry: &mut EntryMut, list: &mut PlayerList, mode: GameMode)
+    -> anyhow::Result<bool>
+{
+    let current = entry.get_component_mut::<GameMode>()?;
+    if *current == mode {
+        return Ok(false);
+    }
+    *current = mode;
+    let conn = entry.get_component::<PlayerConnection>()?;
+    conn.send(ClientboundPacket::ChangeGameState {
+        reason: CHANGE_GAMEMODE,
+        value: mode as u8 as f32,
+    });
+    conn.send(mode.abilities());
+    list.update_gamemode(*entry.get_component::<Uuid>()?, mode as u8);
+    Ok(true)
+}
+
+// Saved as an int, like vanilla's playerGameType
+impl Serialize for GameMode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let id = i32::deserialize(deserializer)?;
+        GameMode::from_i32(id)
+            .ok_or_else(|| D::Error::custom(format!("Unknown gamemode {}", id)))
+    }
+}
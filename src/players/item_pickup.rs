@@ -0,0 +1,115 @@
+use legion::*;
+use systems::CommandBuffer;
+use world::SubWorld;
+use nalgebra::Vector3;
+use crate::buckets::EntityTracker;
+use crate::buckets::events::{EntityEvent, EntityEventData};
+use crate::entities::{DroppedItem, EntityId, Position};
+use crate::inventory::Inventory;
+use crate::net::{ClientboundPacket, PlayerConnection};
+use super::game_mode::GameMode;
+
+// How far from the feet of a player items are picked up, the player's box
+// grown by 1 block horizontally and half a block vertically
+const REACH_HORIZONTAL: f64 = 1.425;
+const REACH_BELOW: f64 = 0.75;
+const REACH_ABOVE: f64 = 2.3;
+
+#[system]
+#[read_component(EntityId)]
+#[read_component(Position)]
+#[read_component(GameMode)]
+#[read_component(PlayerConnection)]
+#[write_component(Inventory)]
+#[write_component(DroppedItem)]
+pub fn pick_up_items(world: &mut SubWorld, cmd: &mut CommandBuffer,
+    #[resource] tracker: &EntityTracker)
+{
+    let mut items: Vec<(Entity, u32, Vector3<f64>)> =
+        <(Entity, &EntityId, &Position, &DroppedItem)>::query()
+        .iter(world)
+        .filter(|(.., item)| item.can_pick_up())
+        .map(|(entity, id, position, _)| (*entity, id.0, position.0))
+        .collect();
+    if items.is_empty() {
+        return;
+    }
+    let (mut item_world, mut player_world) = world.split::<&mut DroppedItem>();
+    let mut players = <(&EntityId, &Position, &GameMode, &mut Inventory, &PlayerConnection)>::query();
+    for (player_id, position, gamemode, inventory, conn) in players.iter_mut(&mut player_world) {
+        if !gamemode.may_interact() {
+            continue;
+        }
+        let mut picked_up = false;
+        items.retain(|(entity, id, pos)| {
+            if !in_reach(position.0, *pos) {
+                return true;
+            }
+            let mut entry = item_world.entry_mut(*entity).unwrap();
+            let item = entry.get_component_mut::<DroppedItem>().unwrap();
+            let left = inventory.insert(&item.stack);
+            let count = item.stack.count - left;
+            if count == 0 {
+                return true;
+            }
+            picked_up = true;
+            tracker.send_event(pos, EntityEvent {
+                id: *id,
+                data: EntityEventData::Collect { collector: player_id.0, count },
+            });
+            if left == 0 {
+                cmd.remove(*entity);
+                tracker.remove(*id, pos);
+                return false;
+            }
+            item.stack.count = left;
+            tracker.send_event(pos, EntityEvent {
+                id: *id,
+                data: EntityEventData::SetItem { stack: item.stack.clone() },
+            });
+            true
+        });
+        if picked_up {
+            conn.send(ClientboundPacket::WindowItems {
+                window: 0,
+                items: inventory.get_window(),
+            });
+        }
+    }
+}
+
+fn in_reach(player: Vector3<f64>, item: Vector3<f64>) -> bool {
+    let delta = item - player;
+    delta.x.abs() < REACH_HORIZONTAL && delta.z.abs() < REACH_HORIZONTAL
+        && delta.y > -REACH_BELOW && delta.y < REACH_ABOVE
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+    use crate::inventory::Inventory;
+    use crate::items::{ItemStack, ItemType};
+    use super::in_reach;
+
+    #[test]
+    fn in_reach_test() {
+        let player = vector!(0.5, 64.0, 0.5);
+        assert!(in_reach(player, vector!(1.5, 64.0, -0.5)));
+        assert!(in_reach(player, vector!(0.5, 63.5, 0.5)));
+        assert!(in_reach(player, vector!(0.5, 66.0, 0.5)));
+        assert!(!in_reach(player, vector!(2.0, 64.0, 0.5)));
+        assert!(!in_reach(player, vector!(0.5, 63.0, 0.5)));
+        assert!(!in_reach(player, vector!(0.5, 66.5, 0.5)));
+    }
+
+    #[test]
+    fn full_inventory_test() {
+        let stone = ItemType::from_name("minecraft:stone").unwrap();
+        let stack = ItemStack { item: stone, count: 10, nbt: None };
+        let mut inventory = Inventory::new();
+        assert_eq!(inventory.add(stone, 64 * 36 - 4), 0);
+        // Only part of the stack fits, the rest stays on the ground
+        assert_eq!(inventory.insert(&stack), 6);
+        assert_eq!(inventory.insert(&stack), 10);
+    }
+}
@@ -0,0 +1,92 @@
+use legion::*;
+use crate::net::{ClientboundPacket, PlayerConnection};
+use crate::text::TextComponent;
+use crate::util::get_time_millis;
+use super::disconnections::DisconnectionQueue;
+
+// Same timings as vanilla: a keep alive every 15 seconds, and the client
+// is kicked if the previous one is still unanswered when the next is due
+const KEEP_ALIVE_INTERVAL: u64 = 15_000;
+
+pub struct KeepAlive {
+    last_sent: u64,
+    pending: Option<u64>,
+    latency: u32,
+}
+
+impl KeepAlive {
+    pub fn new() -> Self {
+        Self {
+            last_sent: get_time_millis(),
+            pending: None,
+            latency: 0,
+        }
+    }
+
+    // Returns the updated latency if `id` answers the pending keep alive
+    pub fn receive(&mut self, id: u64, now: u64) -> Option<u32> {
+        if self.pending != Some(id) {
+            return None;
+        }
+        self.pending = None;
+        let measured = now.saturating_sub(self.last_sent) as u32;
+        // Smoothed like vanilla so a single slow answer doesn't spike the ping
+        self.latency = (self.latency * 3 + measured) / 4;
+        Some(self.latency)
+    }
+
+    fn tick(&mut self, now: u64) -> KeepAliveAction {
+        if now.saturating_sub(self.last_sent) < KEEP_ALIVE_INTERVAL {
+            return KeepAliveAction::None;
+        }
+        if self.pending.is_some() {
+            return KeepAliveAction::TimedOut;
+        }
+        self.last_sent = now;
+        self.pending = Some(now);
+        KeepAliveAction::Send(now)
+    }
+}
+
+enum KeepAliveAction {
+    None,
+    Send(u64),
+    TimedOut,
+}
+
+#[system(for_each)]
+pub fn keepalive(entity: &Entity, conn: &PlayerConnection, keep_alive: &mut KeepAlive,
+    #[resource] disconnections: &DisconnectionQueue)
+{
+    match keep_alive.tick(get_time_millis()) {
+        KeepAliveAction::None => (),
+        KeepAliveAction::Send(id) => {
+            conn.send(ClientboundPacket::KeepAlive(id));
+        }
+        KeepAliveAction::TimedOut => {
+            conn.send(ClientboundPacket::Disconnect {
+                reason: TextComponent::translate("disconnect.timeout", vec![]),
+            });
+            disconnections.send(*entity, "Timed out".to_owned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeepAlive, KeepAliveAction, KEEP_ALIVE_INTERVAL};
+
+    #[test]
+    fn keep_alive_test() {
+        let mut keep_alive = KeepAlive { last_sent: 0, pending: None, latency: 0 };
+        assert!(matches!(keep_alive.tick(1000), KeepAliveAction::None));
+        let id = match keep_alive.tick(KEEP_ALIVE_INTERVAL) {
+            KeepAliveAction::Send(id) => id,
+            _ => panic!("Keep alive wasn't sent"),
+        };
+        assert_eq!(keep_alive.receive(id + 1, KEEP_ALIVE_INTERVAL + 100), None);
+        assert_eq!(keep_alive.receive(id, KEEP_ALIVE_INTERVAL + 100), Some(25));
+        assert!(matches!(keep_alive.tick(KEEP_ALIVE_INTERVAL * 2), KeepAliveAction::Send(_)));
+        assert!(matches!(keep_alive.tick(KEEP_ALIVE_INTERVAL * 3), KeepAliveAction::TimedOut));
+    }
+}
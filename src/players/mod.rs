@@ -5,70 +5,116 @@ mod entity_viewer;
 mod packet_handler;
 mod player_data;
 mod disconnections;
-
-use std::io::Write;
+mod keep_alive;
+mod chat;
+mod plugin_messages;
+mod teleport;
+mod game_mode;
+mod digging;
+mod item_pickup;
+mod tick_times;
 
 use legion::*;
-use serde_json::json;
 use systems::Builder;
 use uuid::Uuid;
+use crate::config::Config;
 use crate::entities::Position;
 use crate::entities::Rotation;
 use crate::inventory::Inventory;
 use crate::net::PlayerConnection;
 use crate::net::ClientboundPacket;
-use crate::util::get_time_millis;
-use player_list::{PlayerList, update_player_list_system};
-use chunk_viewer::update_chunk_view_system;
+use player_list::update_player_list_system;
+use chunk_viewer::{ViewDistance, update_chunk_view_system};
 use new_players::{JoiningPlayerQueue, join_players_system, load_player_data_system};
 use entity_viewer::send_entity_events_system;
-use packet_handler::receive_events_system;
-use disconnections::{DisconnectionQueue, handle_disconnections_system};
+use packet_handler::{receive_events_system, receive_messages_system};
+use disconnections::{handle_disconnections_system, kick_backlogged_system};
+use keep_alive::keepalive_system;
+use chat::send_chat_system;
+use plugin_messages::send_plugin_messages_system;
+use digging::update_digging_system;
+use item_pickup::pick_up_items_system;
+use tick_times::TickTimes;
 
-use self::player_data::PlayerData;
+use self::player_data::{PlayerData, PlayerDataFolder};
 
-#[system(for_each)]
-fn keepalive(conn: &PlayerConnection) {
-    conn.send(ClientboundPacket::KeepAlive(get_time_millis()));
-}
+pub use player_list::PlayerList;
+pub use chat::Chat;
+pub use disconnections::DisconnectionQueue;
+pub use plugin_messages::{PluginMessage, PluginMessages};
+pub use game_mode::{GameMode, change_game_mode};
+pub use tick_times::{TICK_TIMES_CHANNEL, record_tick_time};
 
-pub fn register_early(schedule: &mut Builder, resources: &mut Resources) {
+pub fn register_early(schedule: &mut Builder, resources: &mut Resources, config: &Config) {
     schedule
         .add_system(receive_events_system())
+        .add_system(receive_messages_system())
         .add_system(update_player_list_system());
     resources.insert(PlayerList::new());
     resources.insert(JoiningPlayerQueue::new());
     resources.insert(DisconnectionQueue::new());
+    resources.insert(Chat::new());
+    resources.insert(PluginMessages::new());
+    resources.insert(TickTimes::new());
+    resources.insert(ViewDistance(config.view_distance));
+    resources.insert(PlayerDataFolder::new(&config.data_dir));
 }
 
 pub fn register_late(schedule: &mut Builder, resources: &mut Resources) {
     schedule
         .add_system(keepalive_system())
+        .add_system(update_digging_system())
+        .add_system(pick_up_items_system())
         .add_system(send_entity_events_system())
+        .add_system(send_chat_system())
+        .add_system(send_plugin_messages_system())
         .add_thread_local(update_chunk_view_system())
         .add_system(join_players_system())
         .add_thread_local(load_player_data_system())
+        .add_system(kick_backlogged_system())
         .add_thread_local(handle_disconnections_system());
 }
 
 pub async fn on_stop(world: &mut World, resources: &mut Resources) {
-    print!("Saving players...        ");
-    let _ = std::io::stdout().flush();
-    let mut query = <(&Uuid, &Position, &Rotation, &Inventory, &PlayerConnection)>::query();
-    for (uuid, pos, rot, inv, conn) in query.iter(world) {
+    log!("Saving players...");
+    let mut query = <&PlayerConnection>::query();
+    for conn in query.iter(world) {
         conn.send(ClientboundPacket::Disconnect {
-            reason: json!({
-                "text": "Server closing :(",
-            }),
+            reason: "Server closing :(".into(),
         });
-        PlayerData {
+    }
+    save_players(world, resources).await;
+    log!("Saved players");
+}
+
+pub async fn save_players(world: &World, resources: &Resources) {
+    let folder = resources.get::<PlayerDataFolder>().unwrap().clone();
+    save_data(folder, collect_data(world)).await;
+}
+
+// Saves in the background, for when the tick can't wait for it
+pub fn spawn_save_players(world: &World, resources: &Resources) {
+    let folder = resources.get::<PlayerDataFolder>().unwrap().clone();
+    tokio::spawn(save_data(folder, collect_data(world)));
+}
+
+fn collect_data(world: &World) -> Vec<(Uuid, PlayerData)> {
+    let mut query = <(&Uuid, &Position, &Rotation, &Inventory, &GameMode)>::query();
+    query.iter(world)
+        .map(|(uuid, pos, rot, inv, gamemode)| (*uuid, PlayerData {
             pos: pos.0,
             rotation: (rot.0, rot.1),
             inventory: inv.clone(),
-        }.save(*uuid).await
-        .unwrap_or_else(|err| {
-            eprintln!("Error saving player {}: {}", uuid, err);
-        });
+            gamemode: *gamemode,
+        }))
+        .collect()
+}
+
+async fn save_data(folder: PlayerDataFolder, players: Vec<(Uuid, PlayerData)>) {
+    for (uuid, data) in players {
+        data.save(&folder, uuid).await
+            .unwrap_or_else(|err| {
+                log!("Error saving player {}: {}", uuid, err);
+            });
     }
-    println!("Done");
 }
\ No newline at end of file
@@ -4,27 +4,35 @@ use std::sync::mpsc::channel;
 
 use legion::*;
 use systems::CommandBuffer;
-use nalgebra::vector;
-use uuid::Uuid;
+use nalgebra::{Vector3, vector};
 
-use super::chunk_viewer::ChunkViewer;
+use super::chunk_viewer::{ChunkViewer, ViewDistance};
+use super::keep_alive::KeepAlive;
 use super::PlayerList;
-use super::player_data::PlayerData;
+use super::game_mode::GameMode;
+use super::player_data::{PlayerData, PlayerDataFolder};
+use super::teleport::PendingTeleport;
+use super::packet_handler::PendingMessages;
+use super::digging::Digging;
 use crate::buckets::EntityTracker;
 use crate::buckets::Observer;
-use crate::entities::{Name, Position};
-use crate::entities::EntityIdGenerator;
+use crate::entities::{Address, Name, OnGround, Position};
+use crate::entities::{EntityId, EntityIdGenerator};
 use crate::entities::Rotation;
-use crate::net::PlayerConnection;
+use crate::net::{NewPlayer, PlayerConnection, PlayerInfo};
 use crate::net::Server;
 use crate::net::ClientboundPacket;
 use crate::inventory::Inventory;
+use crate::tags::TAG_PACKET;
+use crate::commands::{Dispatcher, Operators, PermissionLevel};
+use super::plugin_messages::{PluginChannels, PluginMessages, REGISTER};
 
 pub struct JoiningPlayer {
-    uuid: Uuid,
-    name: String,
+    player: NewPlayer,
     conn: PlayerConnection,
     data: PlayerData,
+    id: EntityId,
+    permission: PermissionLevel,
 }
 
 pub struct JoiningPlayerQueue {
@@ -44,18 +52,41 @@ impl JoiningPlayerQueue {
 #[system]
 pub fn join_players(cmd: &mut CommandBuffer, #[resource] queue: &mut JoiningPlayerQueue, 
     #[resource] list: &mut PlayerList, #[resource] tracker: &mut EntityTracker,
-    #[resource] entity_id_gen: &EntityIdGenerator)
+    #[resource] dispatcher: &Dispatcher, #[resource] plugin_messages: &PluginMessages,
+    #[resource] view_distance: &ViewDistance)
 {
-    for JoiningPlayer{ uuid, name, conn, data } in queue.receiver.try_iter() {
-        conn.send(ClientboundPacket::PlayerPosition(
-            data.pos.x, data.pos.y, data.pos.z));
+    for JoiningPlayer{ player, conn, data, id, permission } in queue.receiver.try_iter() {
+        let NewPlayer { uuid, name, properties, address } = player;
+        log!("{} joined from {}", name, address);
+        // In the order vanilla sends them. The chunks and then the spawn
+        // position follow from update_chunk_view
+        conn.send(ClientboundPacket::join_game(id.0, data.gamemode as u8, view_distance.0));
+        conn.send(ClientboundPacket::brand());
+        conn.send(data.gamemode.abilities());
+        conn.send(ClientboundPacket::Tags {
+            raw: TAG_PACKET.into(),
+        });
+        conn.send(ClientboundPacket::DeclareCommands(dispatcher.declare(permission.0)));
+        if let Some(channels) = plugin_messages.register_payload() {
+            conn.send(ClientboundPacket::PluginMessage {
+                channel: REGISTER.into(),
+                content: channels,
+            });
+        }
+        let mut teleport = PendingTeleport::new();
+        teleport.teleport(&conn, data.pos, data.rotation);
+        list.send_player(&conn.get_sender());
+        list.add(uuid, PlayerInfo {
+            name: name.clone(),
+            properties,
+            gamemode: data.gamemode as u8,
+            ping: 0,
+            display_name: None,
+        });
         conn.send(ClientboundPacket::WindowItems{
             window: 0,
             items: data.inventory.get_window(),
         });
-        list.send_player(&conn.get_sender());
-        let id = entity_id_gen.get_new();
-        list.add(uuid, name.clone());
         let entity = cmd.push((
             id,
             uuid,
@@ -64,8 +95,17 @@ pub fn join_players(cmd: &mut CommandBuffer, #[resource] queue: &mut JoiningPlay
             Name(name),
             data.inventory,
             conn,
-            ChunkViewer::new(8),
+            ChunkViewer::new(view_distance.0 as i32),
             Observer::new(16*6),
+            KeepAlive::new(),
+            permission,
+            PluginChannels::new(),
+            Address(address),
+            teleport,
+            data.gamemode,
+            OnGround(true),
+            Digging::new(),
+            PendingMessages::new(),
         ));
         tracker.add(id.0, entity, &data.pos);
     }
@@ -73,26 +113,40 @@ pub fn join_players(cmd: &mut CommandBuffer, #[resource] queue: &mut JoiningPlay
 
 #[system]
 pub fn load_player_data(#[resource] server: &mut Server, 
-    #[resource] queue: &JoiningPlayerQueue)
+    #[resource] queue: &JoiningPlayerQueue, #[resource] folder: &PlayerDataFolder,
+    #[resource] entity_id_gen: &EntityIdGenerator, #[resource] operators: &Operators)
 {
-    for (uuid, name, conn) in server.get_new_players() {
+    for (player, conn) in server.get_new_players() {
+        let id = entity_id_gen.get_new();
+        let permission = operators.level(player.uuid, &player.name);
         let sender = queue.sender.clone();
+        let folder = folder.clone();
         tokio::spawn(async move {
-            let data = PlayerData::load(uuid).await
+            PlayerData::migrate_legacy(&folder, player.uuid, &player.name).await
+                .unwrap_or_else(|err| {
+                    log!("Error migrating data of player {}: {}", player.name, err);
+                });
+            let data = PlayerData::load(&folder, player.uuid).await
                 .unwrap_or_else(|_| { 
                     gen_new_player() 
                 });
             sender.send(JoiningPlayer {
-                uuid, name, conn, data
+                player, conn, data, id, permission
             })
         });
     }
 }
 
+// Where new players start
+pub fn world_spawn() -> Vector3<i32> {
+    vector!(0, 3, 0)
+}
+
 fn gen_new_player() -> PlayerData {
     PlayerData {
-        pos: vector!(0.0, 3.0, 0.0),
+        pos: world_spawn().cast(),
         rotation: (0.0, 0.0),
         inventory: Inventory::new(),
+        gamemode: GameMode::Creative,
     }
 }
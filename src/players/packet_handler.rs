@@ -1,119 +1,295 @@
+use std::mem::take;
 use legion::*;
+use world::SubWorld;
 use nalgebra::vector;
 use crate::buckets::EntityTracker;
 use crate::buckets::events::{EntityEvent, EntityEventData};
 use crate::blocks::InteractionResult;
 use crate::entities::EntityId;
 use crate::net::PlayerConnection;
-use crate::entities::{Position, Rotation};
-use crate::net::ServerboundPacket;
+use crate::entities::{ItemSpawner, OnGround, Position, Rotation};
+use crate::net::{ClientboundPacket, ServerboundPacket};
 use crate::chunks::World as ChunkWorld;
 use super::disconnections::DisconnectionQueue;
 use crate::inventory::{Inventory, SlotIndex};
+use crate::util::get_time_millis;
+use super::keep_alive::KeepAlive;
+use super::player_list::PlayerList;
+use uuid::Uuid;
+use crate::entities::Name;
+use crate::text::TextComponent;
+use crate::commands::{CommandQueue, CommandSource, Dispatcher, PermissionLevel};
+use super::plugin_messages::{PluginChannels, PluginMessages, REGISTER, UNREGISTER};
+use super::chat::{Chat, has_illegal_characters, normalize_message};
+use super::teleport::PendingTeleport;
+use super::game_mode::GameMode;
+use super::digging::{Digger, Digging, acknowledge};
 
-#[system(for_each)]
-pub fn receive_events(entity: &Entity, id: &EntityId, conn: &mut PlayerConnection, 
-    position: &mut Position, rotation: &mut Rotation, inventory: &mut Inventory,
-    #[resource] chunks: &ChunkWorld, #[resource] disconnections: &DisconnectionQueue, 
-    #[resource] tracker: &mut EntityTracker) 
+const DROP_ITEM_STACK: u8 = 3;
+const DROP_ITEM: u8 = 4;
+
+// Chat, tab completions and plugin messages, left for receive_messages
+pub struct PendingMessages(Vec<ServerboundPacket>);
+
+impl PendingMessages {
+    pub fn new() -> Self {
+        Self(vec![])
+    }
+}
+
+#[system]
+#[read_component(EntityId)]
+#[read_component(Uuid)]
+#[read_component(GameMode)]
+#[write_component(PlayerConnection)]
+#[write_component(Position)]
+#[write_component(Rotation)]
+#[write_component(Inventory)]
+#[write_component(KeepAlive)]
+#[write_component(PendingTeleport)]
+#[write_component(OnGround)]
+#[write_component(Digging)]
+#[write_component(PendingMessages)]
+pub fn receive_events(world: &mut SubWorld, #[resource] chunks: &ChunkWorld,
+    #[resource] disconnections: &DisconnectionQueue, #[resource] tracker: &mut EntityTracker,
+    #[resource] list: &mut PlayerList, #[resource] items: &mut ItemSpawner)
 {
-    for event in conn.receive() {
-        match event {
-            ServerboundPacket::PlayerPosition { x, y, z, .. } => {
-                let new_position = vector!(x, y, z);
-                tracker.move_entity(id.0, *entity, position.0, new_position);
-                tracker.send_event(&new_position, EntityEvent {
-                    id: id.0,
-                    data: EntityEventData::Move {
-                        delta: new_position - position.0,
-                    }
-                });
-                position.0 = new_position;
-            },
-            ServerboundPacket::PlayerRotation { yaw, pitch, .. } => {
-                tracker.send_event(&position.0, 
-                    EntityEvent {
-                        id: id.0,
-                        data: EntityEventData::Rotate{ pitch, yaw },
-                    }
-                );
-                tracker.send_event(&position.0, 
-                    EntityEvent {
-                        id: id.0,
-                        data: EntityEventData::RotateHead{ yaw },
-                    }
-                );
-                *rotation = Rotation(yaw, pitch);
-            },
-            ServerboundPacket::PlayerPositionAndRotation {
-                x, y, z, yaw, pitch, ..
-            } => {
-                let new_position = vector!(x, y, z);
-                tracker.move_entity(id.0, *entity, position.0, new_position);
-                tracker.send_event(&new_position, 
-                    EntityEvent {
+    let mut query = <(Entity, &EntityId, &Uuid, &GameMode, &mut PlayerConnection,
+        &mut Position, &mut Rotation, &mut Inventory, &mut KeepAlive, &mut PendingTeleport,
+        &mut OnGround, &mut Digging, &mut PendingMessages)>::query();
+    for (entity, id, uuid, gamemode, conn, position, rotation, inventory, keep_alive, teleport,
+        on_ground, digging, messages) in query.iter_mut(world)
+    {
+        let events: Vec<_> = conn.receive().collect();
+        for event in events {
+            match event {
+                ServerboundPacket::TeleportConfirm { teleport_id } => {
+                    teleport.confirm(teleport_id);
+                },
+                ServerboundPacket::PlayerPosition { .. }
+                | ServerboundPacket::PlayerRotation { .. }
+                | ServerboundPacket::PlayerPositionAndRotation { .. }
+                    if teleport.is_awaiting() => (),
+                event @ (ServerboundPacket::ChatMessage { .. }
+                    | ServerboundPacket::TabComplete { .. }
+                    | ServerboundPacket::PluginMessage { .. }) => {
+                    messages.0.push(event);
+                },
+                ServerboundPacket::PlayerPosition { x, y, z, on_ground: ground } => {
+                    let new_position = vector!(x, y, z);
+                    on_ground.0 = ground;
+                    tracker.move_entity(id.0, *entity, position.0, new_position);
+                    tracker.send_event(&new_position, EntityEvent {
                         id: id.0,
-                        data: EntityEventData::MoveRotate {
+                        data: EntityEventData::Move {
                             delta: new_position - position.0,
-                            yaw,
-                            pitch,
+                        }
+                    });
+                    position.0 = new_position;
+                },
+                ServerboundPacket::PlayerRotation { yaw, pitch, on_ground: ground } => {
+                    on_ground.0 = ground;
+                    tracker.send_event(&position.0,
+                        EntityEvent {
+                            id: id.0,
+                            data: EntityEventData::Rotate{ pitch, yaw },
+                        }
+                    );
+                    tracker.send_event(&position.0,
+                        EntityEvent {
+                            id: id.0,
+                            data: EntityEventData::RotateHead{ yaw },
+                        }
+                    );
+                    *rotation = Rotation(yaw, pitch);
+                },
+                ServerboundPacket::PlayerPositionAndRotation {
+                    x, y, z, yaw, pitch, on_ground: ground
+                } => {
+                    let new_position = vector!(x, y, z);
+                    on_ground.0 = ground;
+                    tracker.move_entity(id.0, *entity, position.0, new_position);
+                    tracker.send_event(&new_position,
+                        EntityEvent {
+                            id: id.0,
+                            data: EntityEventData::MoveRotate {
+                                delta: new_position - position.0,
+                                yaw,
+                                pitch,
+                            }
+                        }
+                    );
+                    tracker.send_event(&position.0,
+                        EntityEvent {
+                            id: id.0,
+                            data: EntityEventData::RotateHead{ yaw },
+                        }
+                    );
+                    position.0 = new_position;
+                    *rotation = Rotation(yaw, pitch);
+                },
+                ServerboundPacket::PlayerDigging { status, .. }
+                    if status == DROP_ITEM || status == DROP_ITEM_STACK =>
+                {
+                    let count = if status == DROP_ITEM { 1 } else { u8::MAX };
+                    if gamemode.may_interact() {
+                        if let Some(stack) = inventory.take_held(count) {
+                            items.drop_from_player(position.0, (rotation.0, rotation.1), stack);
                         }
                     }
-                );
-                tracker.send_event(&position.0, 
-                    EntityEvent {
+                },
+                ServerboundPacket::PlayerDigging {
+                    status, position: block, ..
+                } => {
+                    let digger = Digger {
                         id: id.0,
-                        data: EntityEventData::RotateHead{ yaw },
+                        position: position.0,
+                        on_ground: on_ground.0,
+                        gamemode: *gamemode,
+                        held: inventory.get_held(),
+                    };
+                    let result = digging.handle(status, block, &digger, chunks, tracker, items);
+                    if let Some(successful) = result {
+                        acknowledge(conn, chunks, status, block, successful);
                     }
-                );
-                position.0 = new_position;
-                *rotation = Rotation(yaw, pitch);
-            },
-            ServerboundPacket::PlayerDigging {
-                status, position, face
-            } => {
-                match status {
-                    0 => {
-                        let view = chunks.get_view(position);
-                        chunks.get_block(&position).destroy(&view);
-                    },
-                    2 => {
-                        
-                    },
-                    _ => (),
-                }
-            },
-            ServerboundPacket::HeldItemChange { slot } => {
-                inventory.set_held_slot(
-                    SlotIndex::from_hotbar(slot as u8));
-            },
-            ServerboundPacket::CreativeInventoryAction {
-                slot, stack
-            } => {
-                if slot != -1 {
-                    let index = SlotIndex::from_network(slot as u8);
-                    inventory.set_slot(index, stack);
-                }
-            },
-            ServerboundPacket::PlayerBlockPlacement {
-                hand, location, face, cursor_position, ..
-            } => {
-                let view = chunks.get_view(location);
-                match chunks.get_block(&location).interact(&view) {
-                    InteractionResult::None => {
-                        if let Some(stack) = inventory.get_held() {
-                            if let Some(block_type) = stack.item.get_block() {
-                                block_type.place(&view, face, cursor_position, 
-                                    (rotation.0, rotation.1));
-                            }
+                },
+                ServerboundPacket::HeldItemChange { slot } => {
+                    inventory.set_held_slot(
+                        SlotIndex::from_hotbar(slot as u8));
+                },
+                ServerboundPacket::CreativeInventoryAction {
+                    slot, stack
+                } => {
+                    if !gamemode.is_creative() {
+                        continue;
+                    }
+                    if slot == -1 {
+                        // Thrown out of the creative inventory
+                        if let Some(stack) = stack {
+                            items.drop_from_player(position.0, (rotation.0, rotation.1), stack);
                         }
-                    },
-                    InteractionResult::PreventPlacing => (),
-                }
-            },
-            ServerboundPacket::Disconnect { reason } => {
-                disconnections.send(*entity, reason);
-            },
+                    } else {
+                        let index = SlotIndex::from_network(slot as u8);
+                        inventory.set_slot(index, stack);
+                    }
+                },
+                ServerboundPacket::PlayerBlockPlacement {
+                    hand, location, face, cursor_position, ..
+                } if gamemode.may_interact() => {
+                    let view = chunks.get_view(location);
+                    match chunks.get_block(&location).interact(&view) {
+                        InteractionResult::None if gamemode.may_build() => {
+                            let placed = inventory.get_held()
+                                .and_then(|stack| stack.item.get_block())
+                                .is_some_and(|block_type| block_type.place(&view, face,
+                                    cursor_position, (rotation.0, rotation.1)));
+                            if placed && use_up_placed(inventory, *gamemode) {
+                                conn.send(ClientboundPacket::WindowItems {
+                                    window: 0,
+                                    items: inventory.get_window(),
+                                });
+                            }
+                        },
+                        _ => (),
+                    }
+                },
+                ServerboundPacket::PlayerBlockPlacement { .. } => (),
+                ServerboundPacket::KeepAlive { id } => {
+                    if let Some(ping) = keep_alive.receive(id, get_time_millis()) {
+                        list.update_latency(*uuid, ping);
+                    }
+                },
+                ServerboundPacket::Disconnect { reason } => {
+                    conn.send(ClientboundPacket::Disconnect {
+                        reason: reason.as_str().into(),
+                    });
+                    disconnections.send(*entity, reason);
+                },
+            }
+        }
+    }
+}
+
+// Outside creative the placed block comes out of the held stack
+fn use_up_placed(inventory: &mut Inventory, gamemode: GameMode) -> bool {
+    !gamemode.is_creative() && inventory.take_held(1).is_some()
+}
+
+#[system]
+#[read_component(Uuid)]
+#[read_component(Name)]
+#[read_component(PermissionLevel)]
+#[write_component(PlayerConnection)]
+#[write_component(PluginChannels)]
+#[write_component(PendingMessages)]
+pub fn receive_messages(world: &mut SubWorld, #[resource] disconnections: &DisconnectionQueue,
+    #[resource] list: &PlayerList, #[resource] chat: &mut Chat,
+    #[resource] commands: &CommandQueue, #[resource] dispatcher: &Dispatcher,
+    #[resource] plugin_messages: &mut PluginMessages)
+{
+    let mut query = <(Entity, &Uuid, &Name, &PermissionLevel, &mut PlayerConnection,
+        &mut PluginChannels, &mut PendingMessages)>::query();
+    for (entity, uuid, name, permission, conn, channels, messages) in query.iter_mut(world) {
+        for message in take(&mut messages.0) {
+            match message {
+                ServerboundPacket::ChatMessage { message } => {
+                    if has_illegal_characters(&message) {
+                        conn.send(ClientboundPacket::Disconnect {
+                            reason: TextComponent::translate(
+                                "multiplayer.disconnect.illegal_characters", vec![]),
+                        });
+                        disconnections.send(*entity, "Illegal characters in chat".into());
+                        continue;
+                    }
+                    let message = normalize_message(&message);
+                    if let Some(command) = message.strip_prefix('/') {
+                        log!("{} issued server command: /{}", name.0, command);
+                        commands.send(CommandSource::Player(*entity), command);
+                    } else if !message.is_empty() {
+                        chat.player_message(*uuid, &name.0, &message);
+                    }
+                },
+                ServerboundPacket::TabComplete { transaction_id, text } => {
+                    if let Some(command) = text.strip_prefix('/') {
+                        let (start, matches) = dispatcher.suggest(
+                            command, permission.0, &list.get_names());
+                        // The client counts in UTF-16 code units, with the slash
+                        conn.send(ClientboundPacket::TabComplete {
+                            id: transaction_id,
+                            start: command[..start].encode_utf16().count() + 1,
+                            length: command[start..].encode_utf16().count(),
+                            matches,
+                        });
+                    }
+                },
+                ServerboundPacket::PluginMessage { channel, data } => match channel.as_str() {
+                    REGISTER => channels.register(&data),
+                    UNREGISTER => channels.unregister(&data),
+                    _ => plugin_messages.receive(*entity, &channel, data),
+                },
+                _ => (),
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::inventory::Inventory;
+    use crate::items::ItemType;
+    use super::{GameMode, use_up_placed};
+
+    #[test]
+    fn use_up_placed_test() {
+        let stone = ItemType::from_name("minecraft:stone").unwrap();
+        let mut inventory = Inventory::new();
+        inventory.add(stone, 2);
+        assert!(!use_up_placed(&mut inventory, GameMode::Creative));
+        assert_eq!(inventory.get_held().unwrap().count, 2);
+        assert!(use_up_placed(&mut inventory, GameMode::Survival));
+        assert_eq!(inventory.get_held().unwrap().count, 1);
+        assert!(use_up_placed(&mut inventory, GameMode::Survival));
+        assert!(inventory.get_held().is_none());
+        assert!(!use_up_placed(&mut inventory, GameMode::Survival));
+    }
+}
@@ -1,5 +1,5 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use nalgebra::Vector3;
@@ -9,6 +9,23 @@ use uuid::Uuid;
 use crate::util::{read_file, write_file};
 
 use crate::inventory::Inventory;
+use super::game_mode::GameMode;
+
+const PLAYER_DATA_DIR: &str = "world/playerdata";
+
+// Where the player files are
+#[derive(Clone)]
+pub struct PlayerDataFolder(PathBuf);
+
+impl PlayerDataFolder {
+    pub fn new(data_dir: &Path) -> Self {
+        Self(data_dir.join(PLAYER_DATA_DIR))
+    }
+
+    fn get_path(&self, uuid: Uuid) -> PathBuf {
+        self.0.join(format!("{}.dat", uuid))
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all="PascalCase")] 
@@ -17,26 +34,45 @@ pub struct PlayerData {
     #[serde(serialize_with="serialize_rotation")]
     pub rotation: (f32, f32),
     pub inventory: Inventory,
+    // Named like in vanilla, files from before it was saved are from
+    // when everyone played in creative
+    #[serde(rename="playerGameType", default="default_gamemode")]
+    pub gamemode: GameMode,
 }
 
 impl PlayerData {
-    pub async fn load(uuid: Uuid) -> Result<Self> {
-        let file = read_file(get_path(uuid)).await?;
+    pub async fn load(folder: &PlayerDataFolder, uuid: Uuid) -> Result<Self> {
+        let file = read_file(folder.get_path(uuid)).await?;
         Ok(nbt::from_gzip_reader(file.as_slice())?)
     }
 
-    pub async fn save(&self, uuid: Uuid) -> Result<()> {
+    pub async fn save(&self, folder: &PlayerDataFolder, uuid: Uuid) -> Result<()> {
         let mut data = vec![];
         nbt::to_gzip_writer(&mut data, self, None)?;
-        let path = get_path(uuid);
+        let path = folder.get_path(uuid);
         fs::create_dir_all(path.parent().unwrap()).unwrap();
         write_file(path, data.as_slice()).await?;
         Ok(())
     }
+
+    // Player files used to be named after a v3 UUID of the player's name in 
+    // the nil namespace. They're renamed the first time their owner joins,
+    // after that there's nothing to do
+    pub async fn migrate_legacy(folder: &PlayerDataFolder, uuid: Uuid, name: &str) -> Result<()> {
+        let path = folder.get_path(uuid);
+        if tokio::fs::metadata(&path).await.is_ok() {
+            return Ok(());
+        }
+        let legacy_path = folder.get_path(Uuid::new_v3(&Uuid::nil(), name.as_bytes()));
+        if legacy_path != path && tokio::fs::metadata(&legacy_path).await.is_ok() {
+            tokio::fs::rename(legacy_path, path).await?;
+        }
+        Ok(())
+    }
 }
 
-fn get_path(uuid: Uuid) -> PathBuf {
-    PathBuf::from(format!("./world/playerdata/{}.dat", uuid))
+fn default_gamemode() -> GameMode {
+    GameMode::Creative
 }
 
 fn serialize_rotation<S>(rotation: &(f32, f32), serializer: S) 
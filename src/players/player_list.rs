@@ -1,8 +1,7 @@
 use legion::*;
-use tokio::sync::mpsc::UnboundedSender;
 use world::SubWorld;
 use crate::net::{ClientboundPacket, PlayerInfo};
-use crate::net::{PlayerConnection, Server};
+use crate::net::{PacketSender, PlayerConnection, Server};
 use std::mem::take;
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -14,26 +13,27 @@ pub fn update_player_list(world: &SubWorld, #[resource] list: &mut PlayerList,
 {
     let updates = list.flush_updates();
     if updates.len() > 0 {
-        server.update_list(list.count(), list.get_sample());
+        server.update_list(list.get_profiles());
         for update in updates {
             let packet = match &update {
-                PlayerListUpdate::Add(uuid, name) => {
+                PlayerListUpdate::Add(uuid, info) => {
                     ClientboundPacket::PlayerInfoAddPlayers(vec![
-                        (
-                            *uuid,
-                            PlayerInfo {
-                                name: name.clone(),
-                                properties: Vec::new(),
-                                gamemode: 0,
-                                ping: 0,
-                                display_name: None,
-                            }
-                        )
+                        (*uuid, info.clone())
                     ])
                 }
                 PlayerListUpdate::Remove(uuid) => {
                     ClientboundPacket::PlayerInfoRemovePlayers(vec![*uuid])
                 }
+                PlayerListUpdate::Latency(uuid, ping) => {
+                    ClientboundPacket::PlayerInfoUpdateLatency(vec![
+                        (*uuid, *ping as u16)
+                    ])
+                }
+                PlayerListUpdate::Gamemode(uuid, gamemode) => {
+                    ClientboundPacket::PlayerInfoUpdateGamemode(vec![
+                        (*uuid, *gamemode)
+                    ])
+                }
             };
             let mut query = <(&PlayerConnection,)>::query();
             query.for_each(world, |(conn,)| {
@@ -44,7 +44,7 @@ pub fn update_player_list(world: &SubWorld, #[resource] list: &mut PlayerList,
 }
 
 pub struct PlayerList {
-    players: HashMap<Uuid, String>,
+    players: HashMap<Uuid, PlayerInfo>,
     pending_updates: Vec<PlayerListUpdate>,
 }
 
@@ -56,14 +56,22 @@ impl PlayerList {
         }
     }
 
-    pub fn add(&mut self, uuid: Uuid, name: String) {
-        self.pending_updates.push(PlayerListUpdate::Add(uuid, name));
+    pub fn add(&mut self, uuid: Uuid, info: PlayerInfo) {
+        self.pending_updates.push(PlayerListUpdate::Add(uuid, info));
     }
 
     pub fn remove(&mut self, uuid: Uuid) {
         self.pending_updates.push(PlayerListUpdate::Remove(uuid));
     }
 
+    pub fn update_latency(&mut self, uuid: Uuid, ping: u32) {
+        self.pending_updates.push(PlayerListUpdate::Latency(uuid, ping));
+    }
+
+    pub fn update_gamemode(&mut self, uuid: Uuid, gamemode: u8) {
+        self.pending_updates.push(PlayerListUpdate::Gamemode(uuid, gamemode));
+    }
+
     pub fn flush_updates(&mut self) -> Vec<PlayerListUpdate> {
         let updates = take(&mut self.pending_updates);
         for update in &updates {
@@ -72,10 +80,15 @@ impl PlayerList {
         updates
     }
 
-    pub fn get_sample(&self) -> Vec<String> {
-        (&self.players).into_iter()
-            .take(5)
-            .map(|s| s.1.clone())
+    pub fn get_names(&self) -> Vec<String> {
+        self.players.values()
+            .map(|info| info.name.clone())
+            .collect()
+    }
+
+    pub fn get_profiles(&self) -> Vec<(Uuid, String)> {
+        self.players.iter()
+            .map(|(uuid, info)| (*uuid, info.name.clone()))
             .collect()
     }
 
@@ -83,40 +96,51 @@ impl PlayerList {
         self.players.len()
     }
 
-    pub fn get_players(&self) -> &HashMap<Uuid, String> {
+    pub fn get_players(&self) -> &HashMap<Uuid, PlayerInfo> {
         &self.players
     }
 
-    pub fn send_player(&self, sender: &UnboundedSender<ClientboundPacket>) {
-        let players = self.get_players().into_iter().map(|(uuid, name)| (
-            *uuid,
-            PlayerInfo {
-                name: name.clone(),
-                properties: Vec::new(),
-                gamemode: 0,
-                ping: 0,
-                display_name: None,
-            }
-        )).collect();
+    pub fn send_player(&self, sender: &PacketSender) {
+        let players = self.get_players().into_iter()
+            .map(|(uuid, info)| (*uuid, info.clone()))
+            .collect();
         sender.send(ClientboundPacket::PlayerInfoAddPlayers(players));
     }
 }
 
+impl Default for PlayerList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum PlayerListUpdate {
-    Add(Uuid, String),
+    Add(Uuid, PlayerInfo),
     Remove(Uuid),
+    Latency(Uuid, u32),
+    Gamemode(Uuid, u8),
 }
 
 impl PlayerListUpdate {
     fn apply(&self, list: &mut PlayerList) {
         match self {
-            PlayerListUpdate::Add(uuid, name) => {
-                list.players.insert(*uuid, name.clone());
+            PlayerListUpdate::Add(uuid, info) => {
+                list.players.insert(*uuid, info.clone());
             }
             PlayerListUpdate::Remove(uuid) => {
                 list.players.remove(uuid);
             }
+            PlayerListUpdate::Latency(uuid, ping) => {
+                if let Some(info) = list.players.get_mut(uuid) {
+                    info.ping = *ping;
+                }
+            }
+            PlayerListUpdate::Gamemode(uuid, gamemode) => {
+                if let Some(info) = list.players.get_mut(uuid) {
+                    info.gamemode = *gamemode;
+                }
+            }
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use legion::*;
+use world::SubWorld;
+use crate::net::{ClientboundPacket, PlayerConnection};
+
+pub const REGISTER: &str = "minecraft:register";
+pub const UNREGISTER: &str = "minecraft:unregister";
+// Same cap as Bukkit, so clients can't make us store any number of names
+const MAX_CHANNELS: usize = 128;
+
+// The channels a client said it listens to with `minecraft:register`
+pub struct PluginChannels {
+    registered: HashSet<String>,
+}
+
+impl PluginChannels {
+    pub fn new() -> Self {
+        Self {
+            registered: HashSet::new(),
+        }
+    }
+
+    pub fn register(&mut self, data: &[u8]) {
+        for channel in parse_channels(data) {
+            if self.registered.len() >= MAX_CHANNELS {
+                break;
+            }
+            self.registered.insert(channel);
+        }
+    }
+
+    pub fn unregister(&mut self, data: &[u8]) {
+        for channel in parse_channels(data) {
+            self.registered.remove(&channel);
+        }
+    }
+
+    // Vanilla channels are always understood, others only once registered
+    pub fn accepts(&self, channel: &str) -> bool {
+        channel.starts_with("minecraft:") || self.registered.contains(channel)
+    }
+}
+
+// The payload of register and unregister, names separated by null bytes
+fn parse_channels(data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    data.split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+}
+
+// A message from a client, with the player that sent it
+pub type PluginMessage = (Entity, Vec<u8>);
+
+pub struct PluginMessages {
+    subscribers: HashMap<String, Vec<Sender<PluginMessage>>>,
+    pending: Vec<(Option<Entity>, String, Vec<u8>)>,
+}
+
+impl PluginMessages {
+    pub fn new() -> Self {
+        Self {
+            subscribers: HashMap::new(),
+            pending: vec![],
+        }
+    }
+
+    // Messages from clients on the channel are sent to the receiver.
+    // Clients are told we listen to it, the ones joining later too
+    pub fn subscribe(&mut self, name: &str) -> Receiver<PluginMessage> {
+        let (sender, receiver) = channel();
+        if !self.subscribers.contains_key(name) && !name.starts_with("minecraft:") {
+            self.broadcast(REGISTER, name.as_bytes().to_vec());
+        }
+        self.subscribers.entry(name.to_owned())
+            .or_default()
+            .push(sender);
+        receiver
+    }
+
+    pub fn send(&mut self, player: Entity, channel: &str, data: Vec<u8>) {
+        self.pending.push((Some(player), channel.to_owned(), data));
+    }
+
+    pub fn broadcast(&mut self, channel: &str, data: Vec<u8>) {
+        self.pending.push((None, channel.to_owned(), data));
+    }
+
+    pub fn receive(&mut self, player: Entity, channel: &str, data: Vec<u8>) {
+        if let Some(subscribers) = self.subscribers.get_mut(channel) {
+            subscribers.retain(|subscriber| subscriber.send((player, data.clone())).is_ok());
+        }
+    }
+
+    // The payload of the register message telling clients what we listen to
+    pub fn register_payload(&self) -> Option<Vec<u8>> {
+        let channels: Vec<&str> = self.subscribers.keys()
+            .map(|channel| channel.as_str())
+            .filter(|channel| !channel.starts_with("minecraft:"))
+            .collect();
+        if channels.is_empty() {
+            None
+        } else {
+            Some(channels.join("\0").into_bytes())
+        }
+    }
+}
+
+impl Default for PluginMessages {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[system]
+#[read_component(PlayerConnection)]
+#[read_component(PluginChannels)]
+pub fn send_plugin_messages(world: &SubWorld, #[resource] messages: &mut PluginMessages) {
+    let mut query = <(&PlayerConnection, &PluginChannels)>::query();
+    for (target, channel, content) in messages.pending.drain(..) {
+        let packet = ClientboundPacket::PluginMessage { channel: channel.clone(), content };
+        let send = |(conn, channels): (&PlayerConnection, &PluginChannels)| {
+            if channels.accepts(&channel) {
+                conn.send(packet.clone());
+            }
+        };
+        match target {
+            Some(entity) => {
+                if let Ok(player) = query.get(world, entity) {
+                    send(player);
+                }
+            }
+            None => query.for_each(world, send),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PluginChannels, PluginMessages, REGISTER};
+
+    #[test]
+    fn register_test() {
+        let mut channels = PluginChannels::new();
+        channels.register(b"glow:a\0glow:b\0");
+        assert!(channels.accepts("glow:a") && channels.accepts("glow:b"));
+        assert!(channels.accepts("minecraft:brand"));
+        channels.unregister(b"glow:a");
+        assert!(!channels.accepts("glow:a"));
+        assert!(!channels.accepts("glow:c"));
+    }
+
+    #[test]
+    fn subscribe_test() {
+        let mut messages = PluginMessages::new();
+        let _first = messages.subscribe("glow:a");
+        let _second = messages.subscribe("glow:a");
+        let _vanilla = messages.subscribe("minecraft:brand");
+        assert_eq!(messages.pending.len(), 1);
+        let (target, channel, content) = &messages.pending[0];
+        assert!(target.is_none());
+        assert_eq!(channel, REGISTER);
+        assert_eq!(content, b"glow:a");
+    }
+}
@@ -0,0 +1,42 @@
+use nalgebra::Vector3;
+use crate::net::{ClientboundPacket, PlayerConnection};
+
+// Movement sent before the client confirms the last teleport is from where it
+// was before, so it's ignored until then
+pub struct PendingTeleport {
+    last_id: u32,
+    awaiting: bool,
+}
+
+impl PendingTeleport {
+    pub fn new() -> Self {
+        Self {
+            last_id: 0,
+            awaiting: false,
+        }
+    }
+
+    pub fn teleport(&mut self, conn: &PlayerConnection, pos: Vector3<f64>, rotation: (f32, f32)) {
+        self.last_id = self.last_id.wrapping_add(1);
+        self.awaiting = true;
+        conn.send(ClientboundPacket::PlayerPositionAndLook {
+            x: pos.x,
+            y: pos.y,
+            z: pos.z,
+            yaw: rotation.0,
+            pitch: rotation.1,
+            teleport_id: self.last_id,
+        });
+    }
+
+    // Confirmations of older teleports don't count
+    pub fn confirm(&mut self, id: u32) {
+        if id == self.last_id {
+            self.awaiting = false;
+        }
+    }
+
+    pub fn is_awaiting(&self) -> bool {
+        self.awaiting
+    }
+}
@@ -0,0 +1,66 @@
+use std::mem::take;
+use std::time::Duration;
+use legion::Resources;
+use super::plugin_messages::PluginMessages;
+
+// Clients that register it get how long the ticks took, like the swarm.
+// Every second it's the average and the longest tick in microseconds, as
+// two big endian u32
+pub const TICK_TIMES_CHANNEL: &str = "glow:tick_times";
+const TICKS_PER_REPORT: usize = 20;
+
+pub struct TickTimes {
+    durations: Vec<Duration>,
+}
+
+impl TickTimes {
+    pub fn new() -> Self {
+        Self {
+            durations: vec![],
+        }
+    }
+
+    // Returns the message to send once there are enough ticks for one
+    fn record(&mut self, duration: Duration) -> Option<Vec<u8>> {
+        self.durations.push(duration);
+        if self.durations.len() < TICKS_PER_REPORT {
+            return None;
+        }
+        let durations = take(&mut self.durations);
+        let average = durations.iter().sum::<Duration>() / durations.len() as u32;
+        let max = durations.iter().max().unwrap();
+        let mut data = vec![];
+        data.extend_from_slice(&(average.as_micros() as u32).to_be_bytes());
+        data.extend_from_slice(&(max.as_micros() as u32).to_be_bytes());
+        Some(data)
+    }
+}
+
+impl Default for TickTimes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn record_tick_time(resources: &mut Resources, duration: Duration) {
+    let report = resources.get_mut::<TickTimes>().unwrap().record(duration);
+    if let Some(data) = report {
+        resources.get_mut::<PluginMessages>().unwrap().broadcast(TICK_TIMES_CHANNEL, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::{TICKS_PER_REPORT, TickTimes};
+
+    #[test]
+    fn report_test() {
+        let mut times = TickTimes::new();
+        for tick in 1..TICKS_PER_REPORT {
+            assert!(times.record(Duration::from_millis(tick as u64)).is_none());
+        }
+        let report = times.record(Duration::from_millis(20)).unwrap();
+        assert_eq!(report, [0, 0, 0x29, 0x04, 0, 0, 0x4e, 0x20]);
+    }
+}
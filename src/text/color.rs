@@ -0,0 +1,88 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+    // 24 bit RGB, supported since 1.16
+    Hex(u32),
+}
+
+impl Color {
+    pub fn name(&self) -> String {
+        match self {
+            Color::Black => "black".into(),
+            Color::DarkBlue => "dark_blue".into(),
+            Color::DarkGreen => "dark_green".into(),
+            Color::DarkAqua => "dark_aqua".into(),
+            Color::DarkRed => "dark_red".into(),
+            Color::DarkPurple => "dark_purple".into(),
+            Color::Gold => "gold".into(),
+            Color::Gray => "gray".into(),
+            Color::DarkGray => "dark_gray".into(),
+            Color::Blue => "blue".into(),
+            Color::Green => "green".into(),
+            Color::Aqua => "aqua".into(),
+            Color::Red => "red".into(),
+            Color::LightPurple => "light_purple".into(),
+            Color::Yellow => "yellow".into(),
+            Color::White => "white".into(),
+            Color::Hex(rgb) => format!("#{:06X}", rgb & 0xFFFFFF),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(hex) = name.strip_prefix('#') {
+            return u32::from_str_radix(hex, 16).ok().map(Color::Hex);
+        }
+        let color = match name {
+            "black" => Color::Black,
+            "dark_blue" => Color::DarkBlue,
+            "dark_green" => Color::DarkGreen,
+            "dark_aqua" => Color::DarkAqua,
+            "dark_red" => Color::DarkRed,
+            "dark_purple" => Color::DarkPurple,
+            "gold" => Color::Gold,
+            "gray" => Color::Gray,
+            "dark_gray" => Color::DarkGray,
+            "blue" => Color::Blue,
+            "green" => Color::Green,
+            "aqua" => Color::Aqua,
+            "red" => Color::Red,
+            "light_purple" => Color::LightPurple,
+            "yellow" => Color::Yellow,
+            "white" => Color::White,
+            _ => return None,
+        };
+        Some(color)
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Color::from_name(&name)
+            .ok_or_else(|| D::Error::custom(format!("Unknown color: {}", name)))
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+use super::{ClickEvent, Color, HoverEvent};
+
+// JSON text component, as used by chat, disconnect reasons and the MOTD
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextComponent {
+    #[serde(flatten)]
+    pub content: Content,
+    #[serde(flatten)]
+    pub style: Style,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<TextComponent>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    Text {
+        text: String,
+    },
+    Translation {
+        translate: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        with: Vec<TextComponent>,
+    },
+    Keybind {
+        keybind: String,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Style {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    // Text inserted in the chat box when shift clicked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insertion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<ClickEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl TextComponent {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::new(Content::Text { text: text.into() })
+    }
+
+    pub fn translate(key: impl Into<String>, with: Vec<TextComponent>) -> Self {
+        Self::new(Content::Translation { translate: key.into(), with })
+    }
+
+    pub fn keybind(key: impl Into<String>) -> Self {
+        Self::new(Content::Keybind { keybind: key.into() })
+    }
+
+    fn new(content: Content) -> Self {
+        Self {
+            content,
+            style: Style::default(),
+            extra: vec![],
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.style.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.style.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.style.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+
+    pub fn insertion(mut self, insertion: impl Into<String>) -> Self {
+        self.style.insertion = Some(insertion.into());
+        self
+    }
+
+    pub fn on_click(mut self, event: ClickEvent) -> Self {
+        self.style.click_event = Some(event);
+        self
+    }
+
+    pub fn on_hover(mut self, event: HoverEvent) -> Self {
+        self.style.hover_event = Some(event);
+        self
+    }
+
+    pub fn append(mut self, child: TextComponent) -> Self {
+        self.extra.push(child);
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    // Text without formatting for the console. Translations can't be
+    // resolved here, so their arguments are shown after the key
+    pub fn to_plain(&self) -> String {
+        let mut plain = match &self.content {
+            Content::Text { text } => text.clone(),
+            Content::Translation { translate, with } if with.is_empty() => translate.clone(),
+            Content::Translation { translate, with } => {
+                let args: Vec<String> = with.iter().map(|arg| arg.to_plain()).collect();
+                format!("{} [{}]", translate, args.join(", "))
+            }
+            Content::Keybind { keybind } => keybind.clone(),
+        };
+        for child in &self.extra {
+            plain.push_str(&child.to_plain());
+        }
+        plain
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::text::{ClickEvent, Color, HoverEvent, TextComponent};
+
+    #[test]
+    fn serialize_test() {
+        let component = TextComponent::text("Hello")
+            .color(Color::Red)
+            .bold(true)
+            .on_click(ClickEvent::RunCommand("/list".into()))
+            .on_hover(HoverEvent::Text(Box::new("Click me".into())))
+            .append(TextComponent::translate("chat.type.text", vec![
+                "Notch".into(),
+                TextComponent::text("hi").color(Color::Hex(0x12AB34)),
+            ]));
+        assert_eq!(TextComponent::from_json(&component.to_json()).unwrap(), component);
+        let json: serde_json::Value = serde_json::from_str(&component.to_json()).unwrap();
+        assert_eq!(json, json!({
+            "text": "Hello",
+            "color": "red",
+            "bold": true,
+            "clickEvent": { "action": "run_command", "value": "/list" },
+            "hoverEvent": { "action": "show_text", "contents": { "text": "Click me" } },
+            "extra": [{
+                "translate": "chat.type.text",
+                "with": [{ "text": "Notch" }, { "text": "hi", "color": "#12AB34" }],
+            }],
+        }));
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use super::TextComponent;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "value", rename_all = "snake_case")]
+pub enum ClickEvent {
+    OpenUrl(String),
+    RunCommand(String),
+    SuggestCommand(String),
+    ChangePage(String),
+    CopyToClipboard(String),
+}
+
+// Uses the `contents` format introduced in 1.16
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "contents")]
+pub enum HoverEvent {
+    #[serde(rename = "show_text")]
+    Text(Box<TextComponent>),
+    #[serde(rename = "show_item")]
+    Item {
+        id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        count: Option<u8>,
+        // SNBT of the item's tag
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+    },
+    #[serde(rename = "show_entity")]
+    Entity {
+        #[serde(rename = "type")]
+        kind: String,
+        id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<Box<TextComponent>>,
+    },
+}
@@ -0,0 +1,7 @@
+mod component;
+mod color;
+mod events;
+
+pub use component::TextComponent;
+pub use color::Color;
+pub use events::{ClickEvent, HoverEvent};
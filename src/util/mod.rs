@@ -1,9 +1,22 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 use anyhow::Result;
+use lazy_static::lazy_static;
 use nalgebra::{Vector3, vector};
 use tokio::io::AsyncWriteExt;
 use tokio::{fs::File, io::AsyncReadExt};
+use tokio::task;
+
+static WRITE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    // The newest write done so far to each file by spawn_write
+    static ref LAST_WRITES: Mutex<HashMap<PathBuf, u64>> = Mutex::new(HashMap::new());
+}
 
 pub fn get_time_millis() -> u64 {
     let since_epoch = SystemTime::now()
@@ -29,6 +42,25 @@ pub async fn write_file(path: impl AsRef<Path>, data: &[u8])
     Ok(())
 }
 
+// Writes the file on a blocking thread, which the runtime waits for when
+// the server stops. If it's written again before this one runs, this one
+// is skipped so older data can't replace newer
+pub fn spawn_write(path: impl Into<PathBuf>, data: Vec<u8>) {
+    let path = path.into();
+    let write = WRITE_COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+    task::spawn_blocking(move || {
+        let mut last_writes = LAST_WRITES.lock().unwrap();
+        let last = last_writes.entry(path.clone()).or_insert(0);
+        if *last > write {
+            return;
+        }
+        *last = write;
+        if let Err(err) = fs::write(&path, data) {
+            log!("Couldn't save {}: {}", path.display(), err);
+        }
+    });
+}
+
 pub fn cardinal_to_vec(dir: &str) -> Vector3<i32> {
     match dir {
         "south" => vector!(0, 0, 1),